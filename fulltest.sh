@@ -26,6 +26,7 @@ mode="cmp"
 err_out="null"
 inputs=()
 flags=()
+bench_flags=()
 
 [ -d "./cargo" ] && cargo_exe="./cargo/bin/cargo"
 
@@ -55,6 +56,13 @@ do
         --pure)
             mode="pure"
             ;;
+        --exact)
+            mode="exact"
+            ;;
+        --baseline|--report)
+            bench_flags+=("$1" "$2")
+            shift
+            ;;
         *) inputs+=( "$1" )
             ;;
     esac
@@ -73,7 +81,7 @@ do
             $execpath < "$input"
             ;;
         cmp)
-            time $execpath "$input" $flags
+            $execpath "$input" $flags
             cat "$output"
             echo
             ;;
@@ -81,9 +89,23 @@ do
             echo -n "\e[1m${input:t:r} \e[0m"
             [[ "$err_out" = "stderr" ]] && echo
             my_result=$($execpath "$input" $flags 2>/dev/$err_out)
-            diff=$(git --no-pager diff --no-index =(echo $my_result) "$output")
+            # Leave out the dual values some expected results end with
+            diff=$(git --no-pager diff --no-index =(echo $my_result) =(head -n 3 "$output"))
             [[ "$?" = "0" ]] && echo "\e[1m\e[32mOK\e[0m" || (echo && echo "$diff" | diff-so-fancy)
             [[ "$err_out" = "stderr" ]] && echo
             ;;
+        exact)
+            echo -n "\e[1m${input:t:r} \e[0m"
+            # Exit codes other than 1 are the problem's status
+            check=$($execpath "$input" --compare-exact $flags 2>&1 >/dev/null)
+            [[ "$?" != "1" ]] && echo "\e[1m\e[32mOK\e[0m" || echo "\e[1m\e[31m$check\e[0m"
+            ;;
         esac
 done
+
+# The pivots, factorizations and time for each model in cmp mode,
+# compared against the baseline report if one was given
+if [[ "$mode" = "cmp" ]] && (( $#inputs ))
+then
+    $execpath bench $inputs $bench_flags
+fi
@@ -0,0 +1,514 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::model::{self, ModelFormat};
+use crate::parse::{self, ParsedLP};
+use crate::scale::Scaling;
+use crate::solve::{self, Cuts, Limits, Method, Monitor, SolveResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
+
+/**
+ * Times below this many seconds are too noisy to be flagged
+ * as regressions
+ */
+const MIN_REGRESSION_SECONDS: f64 = 0.01;
+
+/**
+ * Largest relative difference from the baseline objective value
+ * that isn't flagged as a regression
+ */
+const OBJECTIVE_TOLERANCE: f64 = 1e-6;
+
+/**
+ * The columns of a CSV report, in order
+ */
+const CSV_HEADER: &str = "model,options,status,objective,pivots,\
+                          factorizations,aux_seconds,primal_seconds,\
+                          dual_seconds,seconds";
+
+/**
+ * A set of solver options to benchmark, written as comma separated
+ * settings such as `scaling=both,perturb=off`. The settings are
+ * `method` (simplex or ipm), `scaling` (none, geometric, equilibrate or
+ * both) and `perturb` (on or off), and `default` leaves them all at
+ * their defaults. Pricing isn't a setting: the simplex methods always
+ * use the largest coefficient and only switch to Bland's rule when
+ * they stall on degenerate pivots.
+ */
+#[derive(Clone, Debug)]
+pub struct OptionSet {
+    pub name: String,
+    pub method: Method,
+    pub scaling: Scaling,
+    pub no_perturb: bool,
+}
+
+impl Default for OptionSet {
+    fn default() -> Self {
+        OptionSet {
+            name: String::from("default"),
+            method: Method::Simplex,
+            scaling: Scaling::None,
+            no_perturb: false,
+        }
+    }
+}
+
+impl FromStr for OptionSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut set = OptionSet {
+            name: String::from(s),
+            ..OptionSet::default()
+        };
+        if s == "default" {
+            return Ok(set);
+        }
+
+        for setting in s.split(',') {
+            let (key, value) = setting.split_once('=').ok_or_else(|| {
+                format!("Expected key=value in option set, got '{}'", setting)
+            })?;
+            match key {
+                "method" => set.method = value.parse()?,
+                "scaling" => set.scaling = value.parse()?,
+                "perturb" => {
+                    set.no_perturb = match value {
+                        "on" => false,
+                        "off" => true,
+                        _ => {
+                            return Err(format!(
+                                "Unknown perturb setting '{}' (expected on \
+                                 or off)",
+                                value
+                            ))
+                        }
+                    }
+                }
+                _ => {
+                    return Err(format!(
+                        "Unknown option '{}' (expected method, scaling or \
+                         perturb)",
+                        key
+                    ))
+                }
+            }
+        }
+
+        Ok(set)
+    }
+}
+
+/**
+ * The formats a benchmark report can be written in
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!(
+                "Unknown report format '{}' (expected csv or json)",
+                s
+            )),
+        }
+    }
+}
+
+impl ReportFormat {
+    /**
+     * The format for a report file, JSON if it has a `.json`
+     * extension and CSV otherwise
+     */
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("json") => ReportFormat::Json,
+            _ => ReportFormat::Csv,
+        }
+    }
+}
+
+/**
+ * The result of solving one model with one option set. The pivots,
+ * factorizations and per phase times come from the simplex method,
+ * so they're zero for the interior point method without crossover.
+ * `seconds` is the wall time of the whole solve including scaling.
+ * Models which fail to solve have the status `error`, and the error
+ * message which isn't part of the report.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Record {
+    pub model: String,
+    pub options: String,
+    pub status: String,
+    pub objective: Option<f64>,
+    pub pivots: usize,
+    pub factorizations: usize,
+    pub aux_seconds: f64,
+    pub primal_seconds: f64,
+    pub dual_seconds: f64,
+    pub seconds: f64,
+    #[serde(skip)]
+    pub error: Option<String>,
+}
+
+/**
+ * A difference from the baseline that is worse than the tolerance
+ */
+#[derive(Clone, Debug)]
+pub struct Regression {
+    pub model: String,
+    pub options: String,
+    pub reason: String,
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]: {}", self.model, self.options, self.reason)
+    }
+}
+
+/**
+ * The model files to benchmark. Directories are replaced by the files
+ * in them, sorted by name.
+ */
+pub fn models(paths: &[String]) -> Result<Vec<PathBuf>, String> {
+    let mut models = Vec::new();
+    for path in paths {
+        let path = PathBuf::from(path);
+        if !path.is_dir() {
+            models.push(path);
+            continue;
+        }
+
+        let entries = std::fs::read_dir(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut files = entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        files.retain(|file| file.is_file());
+        files.sort();
+        models.extend(files);
+    }
+
+    Ok(models)
+}
+
+/**
+ * The name the records for the model at `path` are keyed by, which is
+ * its path relative to the working directory so models with the same
+ * file name in different directories are kept apart
+ */
+pub fn model_name(path: &Path) -> String {
+    let relative = std::env::current_dir()
+        .ok()
+        .and_then(|dir| path.strip_prefix(dir).ok())
+        .unwrap_or(path);
+
+    relative
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .collect::<PathBuf>()
+        .display()
+        .to_string()
+}
+
+/**
+ * Solve the model at `path` with the option set `repeat` times and
+ * record the fastest run
+ */
+pub fn run(path: &Path, set: &OptionSet, repeat: usize) -> Record {
+    let mut record = Record {
+        model: model_name(path),
+        options: set.name.clone(),
+        status: String::from("error"),
+        objective: None,
+        pivots: 0,
+        factorizations: 0,
+        aux_seconds: 0.0,
+        primal_seconds: 0.0,
+        dual_seconds: 0.0,
+        seconds: 0.0,
+        error: None,
+    };
+
+    let lp = match read(path) {
+        Ok(lp) => lp,
+        Err(e) => {
+            record.error = Some(e);
+            return record;
+        }
+    };
+    let options = solve::Options {
+        method: set.method,
+        scaling: set.scaling,
+        cuts: Cuts::None,
+        limits: Limits::default(),
+        no_perturb: set.no_perturb,
+        crossover: false,
+    };
+
+    for i in 0..repeat.max(1) {
+        let mut monitor = Monitor::default();
+        let start = Instant::now();
+        let solve_result = solve::solve(&lp, &options, &mut monitor);
+        let seconds = start.elapsed().as_secs_f64();
+        if i > 0 && seconds >= record.seconds {
+            continue;
+        }
+
+        let stats = monitor.stats();
+        let total = stats.total();
+        record.status = match &solve_result {
+            Ok(solve_result) => String::from(solve_result.status()),
+            Err(_) => String::from("error"),
+        };
+        record.error = solve_result.as_ref().err().cloned();
+        record.objective = match &solve_result {
+            Ok(SolveResult::Optimal(solution))
+            | Ok(SolveResult::Feasible(solution, _)) => {
+                Some(solution.objective_value)
+            }
+            _ => None,
+        };
+        record.pivots = total.pivots;
+        record.factorizations = total.factorizations;
+        record.aux_seconds = stats.auxiliary.seconds;
+        record.primal_seconds = stats.primal.seconds;
+        record.dual_seconds = stats.dual.seconds;
+        record.seconds = seconds;
+    }
+
+    record
+}
+
+/**
+ * Read a model in the format given by its extension
+ */
+fn read(path: &Path) -> Result<ParsedLP, String> {
+    let path = path.to_string_lossy();
+    let contents = parse::read_file(&path)?;
+    match ModelFormat::from_path(&path) {
+        None | Some(ModelFormat::Native) => parse::parse(&contents),
+        Some(format) => {
+            model::read_model::<f64>(&contents, format)?.to_parsed()
+        }
+    }
+}
+
+/**
+ * Compare the records against a baseline report, flagging changes of
+ * status or objective value, and pivots, factorizations or time which
+ * grew by more than `tolerance` relative to the baseline. Records
+ * which aren't in the baseline are skipped.
+ */
+pub fn compare(
+    records: &[Record],
+    baseline: &[Record],
+    tolerance: f64,
+) -> Vec<Regression> {
+    let mut regressions = Vec::new();
+    for record in records {
+        let base = match baseline.iter().find(|base| {
+            base.model == record.model && base.options == record.options
+        }) {
+            Some(base) => base,
+            None => continue,
+        };
+
+        let mut regression = |reason: String| {
+            regressions.push(Regression {
+                model: record.model.clone(),
+                options: record.options.clone(),
+                reason,
+            })
+        };
+
+        if record.status != base.status {
+            regression(format!("status {} was {}", record.status, base.status));
+            continue;
+        }
+
+        if let (Some(objective), Some(expected)) =
+            (record.objective, base.objective)
+        {
+            let error = (objective - expected).abs() / expected.abs().max(1.0);
+            if error > OBJECTIVE_TOLERANCE {
+                regression(format!("objective {} was {}", objective, expected));
+            }
+        }
+
+        let grew = |value: f64, base: f64| value > base * (1.0 + tolerance);
+        if grew(record.pivots as f64, base.pivots as f64) {
+            regression(format!("pivots {} was {}", record.pivots, base.pivots));
+        }
+        if grew(record.factorizations as f64, base.factorizations as f64) {
+            regression(format!(
+                "factorizations {} was {}",
+                record.factorizations, base.factorizations
+            ));
+        }
+        if grew(record.seconds, base.seconds)
+            && record.seconds - base.seconds > MIN_REGRESSION_SECONDS
+        {
+            regression(format!(
+                "time {:.3}s was {:.3}s",
+                record.seconds, base.seconds
+            ));
+        }
+    }
+
+    regressions
+}
+
+/**
+ * Write the records as a report
+ */
+pub fn write_report(
+    records: &[Record],
+    format: ReportFormat,
+) -> Result<String, String> {
+    match format {
+        ReportFormat::Json => serde_json::to_string_pretty(records)
+            .map(|json| json + "\n")
+            .map_err(|e| e.to_string()),
+        ReportFormat::Csv => {
+            let mut csv = format!("{}\n", CSV_HEADER);
+            for record in records {
+                let fields = [
+                    csv_field(&record.model),
+                    csv_field(&record.options),
+                    record.status.clone(),
+                    record.objective.map(|v| v.to_string()).unwrap_or_default(),
+                    record.pivots.to_string(),
+                    record.factorizations.to_string(),
+                    record.aux_seconds.to_string(),
+                    record.primal_seconds.to_string(),
+                    record.dual_seconds.to_string(),
+                    record.seconds.to_string(),
+                ];
+                csv.push_str(&fields.join(","));
+                csv.push('\n');
+            }
+            Ok(csv)
+        }
+    }
+}
+
+/**
+ * Read a report written by `write_report`
+ */
+pub fn read_report(
+    contents: &str,
+    format: ReportFormat,
+) -> Result<Vec<Record>, String> {
+    match format {
+        ReportFormat::Json => {
+            serde_json::from_str(contents).map_err(|e| e.to_string())
+        }
+        ReportFormat::Csv => contents
+            .lines()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                csv_record(line).map_err(|e| format!("line {}: {}", i + 1, e))
+            })
+            .collect(),
+    }
+}
+
+fn csv_record(line: &str) -> Result<Record, String> {
+    let fields = csv_fields(line);
+    if fields.len() != 10 {
+        return Err(format!("expected 10 fields, got {}", fields.len()));
+    }
+
+    let number = |i: usize| {
+        fields[i]
+            .parse::<f64>()
+            .map_err(|_| format!("invalid number '{}'", fields[i]))
+    };
+    let count = |i: usize| {
+        fields[i]
+            .parse::<usize>()
+            .map_err(|_| format!("invalid count '{}'", fields[i]))
+    };
+
+    Ok(Record {
+        model: fields[0].clone(),
+        options: fields[1].clone(),
+        status: fields[2].clone(),
+        objective: match fields[3].is_empty() {
+            true => None,
+            false => Some(number(3)?),
+        },
+        pivots: count(4)?,
+        factorizations: count(5)?,
+        aux_seconds: number(6)?,
+        primal_seconds: number(7)?,
+        dual_seconds: number(8)?,
+        seconds: number(9)?,
+        error: None,
+    })
+}
+
+/**
+ * Quote a CSV field if it contains a comma or a quote
+ */
+fn csv_field(value: &str) -> String {
+    match value.contains(',') || value.contains('"') {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => String::from(value),
+    }
+}
+
+/**
+ * Split a CSV line into its fields, undoing the quoting of `csv_field`
+ */
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let field = fields.last_mut().unwrap();
+        match ch {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            _ => field.push(ch),
+        }
+    }
+
+    fields
+}
@@ -0,0 +1,386 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::output::read_solution;
+use crate::scalar::Scalar;
+use crate::solve::Solution;
+use crate::util::{col_view, mul, norm_inf, row_view, tr_mul, transpose};
+use crate::{Matrix, Vector};
+use std::fmt;
+
+/**
+ * Largest violation accepted for `f64` solutions written at full
+ * precision. Each measure is already relative to the problem data.
+ */
+pub const CHECK_TOLERANCE: f64 = 1e-6;
+
+/**
+ * The fewest significant digits any of the solution formats are
+ * written with by default (GLPK's)
+ */
+const MIN_SOLUTION_DIGITS: usize = 6;
+
+/**
+ * How far a claimed optimal solution is from satisfying the KKT
+ * conditions of `max c^T x s.t. A x = b, x >= 0`. The dual measures
+ * are `None` when no dual values were given.
+ *
+ * - `primal_infeasibility`: the largest of `-x` and the row residuals
+ *   `|A_i x - b_i| / (1 + |A_i| |x| + |b_i|)`
+ * - `dual_infeasibility`: `max(c - A^T y) / (1 + |c|)`
+ * - `complementarity`: `max |x_j (A^T y - c)_j| / (1 + |c^T x|)`
+ * - `objective_gap`: `|c^T x - b^T y| / (1 + |c^T x|)`
+ *
+ * The norms of `c` are infinity norms, and `|A_i| |x|` is the sum of
+ * the absolute values of the terms in row `i`. Scaling each row by its
+ * terms keeps values rounded for printing from failing the check.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct KktReport {
+    pub primal_infeasibility: f64,
+    pub dual_infeasibility: Option<f64>,
+    pub complementarity: Option<f64>,
+    pub objective_gap: Option<f64>,
+}
+
+impl KktReport {
+    /**
+     * Whether every measure is within `tolerance`
+     */
+    pub fn passes(&self, tolerance: f64) -> bool {
+        [
+            Some(self.primal_infeasibility),
+            self.dual_infeasibility,
+            self.complementarity,
+            self.objective_gap,
+        ]
+        .iter()
+        .flatten()
+        .all(|v| *v <= tolerance)
+    }
+}
+
+/**
+ * Check the KKT conditions for the values `x` of all of the variables
+ * (including slacks) and the dual values `y`, one per row of `A`.
+ */
+pub fn kkt<T: Scalar>(
+    A: &Matrix<T>,
+    b: &Vector<T>,
+    c: &Vector<T>,
+    x: &Vector<T>,
+    y: Option<&Vector<T>>,
+) -> KktReport {
+    let residual = mul(A, x) - b.clone_owned();
+    let primal_infeasibility = residual
+        .iter()
+        .enumerate()
+        .map(|(i, r)| {
+            let terms: f64 = A
+                .row(i)
+                .iter()
+                .zip(x.iter())
+                .map(|(a, x_j)| (a.to_f64() * x_j.to_f64()).abs())
+                .sum();
+            r.to_f64().abs() / (1.0 + terms + b[i].to_f64().abs())
+        })
+        .chain(x.iter().map(|x_j| -x_j.to_f64()))
+        .fold(0.0, f64::max);
+
+    let objective = c.dot(x).to_f64();
+
+    let (dual_infeasibility, complementarity, objective_gap) = match y {
+        Some(y) => {
+            let z = tr_mul(A, y) - c.clone_owned();
+            let dual_infeasibility =
+                z.iter().map(|z_j| -z_j.to_f64()).fold(0.0, f64::max)
+                    / (1.0 + norm_inf(c));
+            let complementarity = x
+                .iter()
+                .zip(z.iter())
+                .map(|(x_j, z_j)| (x_j.clone() * z_j.clone()).to_f64().abs())
+                .fold(0.0, f64::max)
+                / (1.0 + objective.abs());
+            let objective_gap =
+                (objective - b.dot(y).to_f64()).abs() / (1.0 + objective.abs());
+
+            (
+                Some(dual_infeasibility),
+                Some(complementarity),
+                Some(objective_gap),
+            )
+        }
+        None => (None, None, None),
+    };
+
+    KktReport {
+        primal_infeasibility,
+        dual_infeasibility,
+        complementarity,
+        objective_gap,
+    }
+}
+
+/**
+ * Check a solution of a problem in the `[A0 | I]` form produced by
+ * `parse::parse`. The slack values are recovered from the variable
+ * values, and the dual values are only used if there is one per row.
+ */
+pub fn check_solution<T: Scalar>(
+    A: &Matrix<T>,
+    b: &Vector<T>,
+    c: &Vector<T>,
+    solution: &Solution<T>,
+) -> KktReport {
+    let x = with_slacks(A, b, &solution.variable_values);
+    let y = Vector::from_iterator(
+        solution.dual_values.len(),
+        solution.dual_values.iter().cloned(),
+    );
+
+    kkt(A, b, c, &x, Some(&y).filter(|y| y.len() == A.nrows()))
+}
+
+/**
+ * The result of checking a solution file. Solution files often round
+ * their values, and the rounding alone can be enough to push the
+ * measures past `CHECK_TOLERANCE`, so `tolerance` is widened to the
+ * largest error the rounding could cause when that is larger.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct FileReport {
+    pub report: KktReport,
+
+    /**
+     * The number of significant digits the values are assumed to have
+     * been rounded to, `None` if the file has no numbers
+     */
+    pub digits: Option<usize>,
+
+    pub tolerance: f64,
+}
+
+impl FileReport {
+    /**
+     * Whether the values were rounded enough for the tolerance to be
+     * widened past `CHECK_TOLERANCE`
+     */
+    pub fn rounded(&self) -> bool {
+        self.tolerance > CHECK_TOLERANCE
+    }
+
+    pub fn passes(&self) -> bool {
+        self.report.passes(self.tolerance)
+    }
+}
+
+/**
+ * Check a claimed solution read from a file, in any of the formats
+ * that `output::read_solution` understands
+ */
+pub fn check_file<T: Scalar>(
+    A: &Matrix<T>,
+    b: &Vector<T>,
+    c: &Vector<T>,
+    contents: &str,
+) -> Result<FileReport, String> {
+    let m = A.nrows();
+    let n = A.ncols() - m;
+    let values = read_solution::<T>(contents, n, m)?;
+
+    let x = with_slacks(A, b, &values.x);
+    let y = values.y.map(Vector::from_vec);
+    let report = kkt(A, b, c, &x, y.as_ref());
+
+    let digits = significant_digits(contents);
+    let tolerance = match digits {
+        Some(digits) => {
+            rounding_error(A, b, c, &x, y.as_ref(), digits).max(CHECK_TOLERANCE)
+        }
+        None => CHECK_TOLERANCE,
+    };
+
+    Ok(FileReport {
+        report,
+        digits,
+        tolerance,
+    })
+}
+
+/**
+ * The precision the values in `contents` are assumed to have been
+ * rounded to: the most significant digits written for any number in
+ * it, but no fewer than `MIN_SOLUTION_DIGITS` since exact values
+ * like `2` or `0.5` don't show how many digits were kept.
+ */
+fn significant_digits(contents: &str) -> Option<usize> {
+    contents
+        .split(|ch: char| ch.is_whitespace() || ch == '"' || ch == '=')
+        .filter(|word| word.parse::<f64>().is_ok())
+        .map(|word| {
+            word.split(['e', 'E'])
+                .next()
+                .unwrap_or_default()
+                .chars()
+                .filter(char::is_ascii_digit)
+                .skip_while(|ch| *ch == '0')
+                .count()
+        })
+        .max()
+        .map(|digits| digits.max(MIN_SOLUTION_DIGITS))
+}
+
+/**
+ * A bound on how far each KKT measure can be moved by rounding the
+ * structural values and the dual values to `digits` significant
+ * digits. The slacks aren't rounded themselves, but they're recomputed
+ * from the rounded structural values so they pick up their errors.
+ */
+fn rounding_error<T: Scalar>(
+    A: &Matrix<T>,
+    b: &Vector<T>,
+    c: &Vector<T>,
+    x: &Vector<T>,
+    y: Option<&Vector<T>>,
+    digits: usize,
+) -> f64 {
+    let m = A.nrows();
+    let n = A.ncols() - m;
+    let relative = 0.5 * 10f64.powi(1 - digits as i32);
+    let objective_scale = 1.0 + c.dot(x).to_f64().abs();
+    let a = |i: usize, j: usize| A[(i, j)].to_f64().abs();
+    let x: Vec<f64> = x.iter().map(|x_j| x_j.to_f64().abs()).collect();
+
+    // Error in the structural values and the slacks computed from them
+    let dx: Vec<f64> =
+        (0..n)
+            .map(|j| relative * x[j])
+            .chain((0..m).map(|i| {
+                relative * (0..n).map(|j| a(i, j) * x[j]).sum::<f64>()
+            }))
+            .collect();
+    let primal = dx.iter().cloned().fold(0.0, f64::max);
+
+    let y = match y {
+        Some(y) => y,
+        None => return 2.0 * primal,
+    };
+
+    let y: Vec<f64> = y.iter().map(|y_i| y_i.to_f64()).collect();
+    let z: Vec<f64> = (0..n + m)
+        .map(|j| {
+            (0..m).map(|i| A[(i, j)].to_f64() * y[i]).sum::<f64>()
+                - c[j].to_f64()
+        })
+        .collect();
+    let dz: Vec<f64> = (0..n + m)
+        .map(|j| relative * (0..m).map(|i| a(i, j) * y[i].abs()).sum::<f64>())
+        .collect();
+
+    let dual = dz.iter().cloned().fold(0.0, f64::max) / (1.0 + norm_inf(c));
+    let complementarity = (0..n + m)
+        .map(|j| x[j] * dz[j] + dx[j] * z[j].abs() + dx[j] * dz[j])
+        .fold(0.0, f64::max)
+        / objective_scale;
+    let objective_gap = relative
+        * (c.iter()
+            .zip(x.iter())
+            .map(|(c_j, x_j)| (c_j.to_f64() * x_j).abs())
+            .sum::<f64>()
+            + b.iter()
+                .zip(y.iter())
+                .map(|(b_i, y_i)| (b_i.to_f64() * y_i).abs())
+                .sum::<f64>())
+        / objective_scale;
+
+    // Twice the bound, to allow for the rounding in the measures
+    // themselves
+    2.0 * [primal, dual, complementarity, objective_gap]
+        .iter()
+        .cloned()
+        .fold(0.0, f64::max)
+}
+
+/**
+ * Check the optimal basic solution `x` with basis `B` in debug builds,
+ * printing a warning if it violates the KKT conditions. Numerically
+ * hard problems can legitimately end up outside the tolerance, so this
+ * doesn't abort the solve. The tolerance is looser than the solvers'
+ * own residual tolerance since the values have been through many
+ * pivots.
+ */
+pub fn debug_check<T: Scalar>(
+    A: &Matrix<T>,
+    b: &Vector<T>,
+    c: &Vector<T>,
+    x: &Vector<T>,
+    B: &[usize],
+) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+
+    let y = T::solve(&transpose(&col_view(A, B)), &row_view(c, B));
+    let report = kkt(A, b, c, x, y.as_ref());
+    let tolerance = T::residual_tolerance().sqrt();
+
+    if !report.passes(tolerance) {
+        eprintln!(
+            "warning: optimal solution failed the KKT check:\n{}",
+            report
+        );
+    }
+}
+
+/**
+ * Append the slack values `b - A0 x` to the variable values `x`
+ */
+fn with_slacks<T: Scalar>(A: &Matrix<T>, b: &Vector<T>, x: &[T]) -> Vector<T> {
+    let m = A.nrows();
+    let n = A.ncols() - m;
+    let x = Vector::from_iterator(n, x.iter().cloned());
+    let slacks = b.clone_owned() - mul(&A.columns(0, n).clone_owned(), &x);
+    Vector::from_iterator(n + m, x.iter().chain(slacks.iter()).cloned())
+}
+
+/**
+ * Format the report for the `check` command
+ */
+impl fmt::Display for KktReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = |v: Option<f64>| match v {
+            Some(v) => format!("{:.3e}", v),
+            None => String::from("n/a"),
+        };
+
+        writeln!(
+            f,
+            "primal infeasibility     {}",
+            format(Some(self.primal_infeasibility))
+        )?;
+        writeln!(
+            f,
+            "dual infeasibility       {}",
+            format(self.dual_infeasibility)
+        )?;
+        writeln!(
+            f,
+            "complementary slackness  {}",
+            format(self.complementarity)
+        )?;
+        write!(f, "objective gap            {}", format(self.objective_gap))
+    }
+}
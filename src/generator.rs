@@ -0,0 +1,386 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::parse::ParsedLP;
+use std::str::FromStr;
+
+/**
+ * Largest Klee-Minty cube whose coefficients, up to `5^n`, are
+ * exact in `f64`
+ */
+const KLEE_MINTY_MAX: usize = 22;
+
+/**
+ * The kinds of problem that can be generated. All of them are
+ * `max c^T x` subject to `A x <= b` and `x >= 0` with integer
+ * coefficients.
+ *
+ * - `Random`: coefficients drawn at random, so any status is possible
+ * - `Feasible`: feasible and bounded, so there is an optimal solution
+ * - `Infeasible`: no feasible solution
+ * - `Unbounded`: feasible with a ray along which the objective grows
+ * - `Degenerate`: the optimal vertex has more tight constraints than
+ *   variables, so the optimal basis has a basic variable at zero
+ * - `KleeMinty`: the Klee-Minty cube, on which the largest coefficient
+ *   rule visits all `2^n` vertices
+ * - `Cycling`: Beale's example (`lp_tests/input/cycle.txt`), which
+ *   cycles without perturbation
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Random,
+    Feasible,
+    Infeasible,
+    Unbounded,
+    Degenerate,
+    KleeMinty,
+    Cycling,
+}
+
+impl FromStr for Kind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(Kind::Random),
+            "feasible" => Ok(Kind::Feasible),
+            "infeasible" => Ok(Kind::Infeasible),
+            "unbounded" => Ok(Kind::Unbounded),
+            "degenerate" => Ok(Kind::Degenerate),
+            "klee-minty" => Ok(Kind::KleeMinty),
+            "cycling" => Ok(Kind::Cycling),
+            _ => Err(format!(
+                "Unknown problem kind '{}' (expected random, feasible, \
+                 infeasible, unbounded, degenerate, klee-minty or cycling)",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * The problem to generate. `density` is the probability that each
+ * coefficient of `A` is nonzero. The Klee-Minty cube has `cols` rows
+ * and columns, and the cycling example is always the same, so they
+ * ignore the other options.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct Options {
+    pub kind: Kind,
+    pub rows: usize,
+    pub cols: usize,
+    pub density: f64,
+    pub seed: u64,
+}
+
+/**
+ * The SplitMix64 generator. It's small and its output for a seed
+ * never changes, so generated problems can be reproduced from the
+ * seed alone.
+ */
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /**
+     * A uniform value in `[0, 1)`
+     */
+    fn uniform(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /**
+     * A uniform integer in `[low, high]`
+     */
+    fn int(&mut self, low: i64, high: i64) -> f64 {
+        let range = (high - low + 1) as u64;
+        (low + (self.next() % range) as i64) as f64
+    }
+
+    /**
+     * A uniform index in `[low, high]`
+     */
+    fn index(&mut self, low: usize, high: usize) -> usize {
+        low + (self.next() % (high - low + 1) as u64) as usize
+    }
+
+    /**
+     * A nonzero integer in `[-high, high]`
+     */
+    fn nonzero(&mut self, high: i64) -> f64 {
+        let value = self.int(1, high);
+        match self.next() % 2 {
+            0 => value,
+            _ => -value,
+        }
+    }
+
+    /**
+     * `count` distinct indices below `len`, in increasing order
+     */
+    fn subset(&mut self, len: usize, count: usize) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..len).collect();
+        for i in 0..count.min(len) {
+            let j = i + (self.next() % (len - i) as u64) as usize;
+            indices.swap(i, j);
+        }
+        indices.truncate(count);
+        indices.sort_unstable();
+        indices
+    }
+}
+
+/**
+ * Generate a problem
+ */
+pub fn generate(options: &Options) -> Result<ParsedLP, String> {
+    match options.kind {
+        Kind::KleeMinty => return klee_minty(options.cols),
+        Kind::Cycling => return cycling(),
+        _ => {}
+    }
+
+    let (m, n) = (options.rows, options.cols);
+    if m == 0 || n == 0 {
+        return Err(String::from(
+            "The problem needs at least one row and one column",
+        ));
+    }
+    if !(options.density > 0.0 && options.density <= 1.0) {
+        return Err(format!(
+            "The density must be in (0, 1], got {}",
+            options.density
+        ));
+    }
+    if options.kind == Kind::Degenerate && m < 2 {
+        return Err(String::from("Degenerate problems need at least two rows"));
+    }
+
+    let mut rng = Rng(options.seed);
+    let mut A: Vec<Vec<f64>> = (0..m)
+        .map(|_| {
+            (0..n)
+                .map(|_| match rng.uniform() < options.density {
+                    true => rng.nonzero(9),
+                    false => 0.0,
+                })
+                .collect()
+        })
+        .collect();
+
+    let (b, c) = match options.kind {
+        Kind::Random => {
+            let b = (0..m).map(|_| rng.int(-5, 20)).collect();
+            let c = (0..n).map(|_| rng.int(-9, 9)).collect();
+            (b, c)
+        }
+        Kind::Feasible => {
+            let x = (0..n).map(|_| rng.int(0, 5)).collect::<Vec<_>>();
+            let b = feasible_rhs(&mut rng, &A, &x, &[]);
+            let y = (0..m).map(|_| rng.int(0, 5)).collect::<Vec<_>>();
+            let c = bounded_objective(&mut rng, &A, &y, &[]);
+            (b, c)
+        }
+        Kind::Infeasible => infeasible(&mut rng, &mut A),
+        Kind::Unbounded => unbounded(&mut rng, &mut A),
+        Kind::Degenerate => {
+            // Optimal at `x` with `tight.len() > support.len()`, so
+            // fewer than `m` basic variables are nonzero
+            let k = rng.index(1, n.min(m - 1));
+            let t = rng.index(k + 1, m);
+            let support = rng.subset(n, k);
+            let tight = rng.subset(m, t);
+
+            let mut x = vec![0.0; n];
+            support.iter().for_each(|&j| x[j] = rng.int(1, 5));
+            let b = feasible_rhs(&mut rng, &A, &x, &tight);
+
+            let mut y = vec![0.0; m];
+            tight.iter().for_each(|&i| y[i] = rng.int(1, 5));
+            let c = bounded_objective(&mut rng, &A, &y, &support);
+            (b, c)
+        }
+        Kind::KleeMinty | Kind::Cycling => unreachable!(),
+    };
+
+    ParsedLP::new(c, A, b, Vec::new())
+}
+
+/**
+ * `b = A x + s` for a random slack `s >= 0` which is zero in the
+ * `tight` rows, so that `x` is feasible
+ */
+fn feasible_rhs(
+    rng: &mut Rng,
+    A: &[Vec<f64>],
+    x: &[f64],
+    tight: &[usize],
+) -> Vec<f64> {
+    A.iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let slack = match tight.contains(&i) {
+                true => 0.0,
+                false => rng.int(0, 9),
+            };
+            dot(row, x) + slack
+        })
+        .collect()
+}
+
+/**
+ * `c = A^T y - r` for a random `r >= 0` which is zero in the `support`
+ * columns. `y >= 0` and `r` are then a feasible dual solution, so the
+ * problem is bounded, and by complementary slackness a feasible `x`
+ * which is zero outside `support` and tight where `y` is positive is
+ * optimal.
+ */
+fn bounded_objective(
+    rng: &mut Rng,
+    A: &[Vec<f64>],
+    y: &[f64],
+    support: &[usize],
+) -> Vec<f64> {
+    let n = A[0].len();
+    (0..n)
+        .map(|j| {
+            let reduced = match support.contains(&j) {
+                true => 0.0,
+                false => rng.int(0, 5),
+            };
+            A.iter().zip(y).map(|(row, y)| row[j] * y).sum::<f64>() - reduced
+        })
+        .collect()
+}
+
+/**
+ * Make the last row of `A` contradict the others. With a random
+ * `y >= 0` that is 1 in the last row, the last row is set so that
+ * `y^T A >= 0` and `y^T b < 0`, which by Farkas' lemma means no
+ * `x >= 0` satisfies `A x <= b`. The other rows are feasible by
+ * themselves.
+ */
+fn infeasible(rng: &mut Rng, A: &mut [Vec<f64>]) -> (Vec<f64>, Vec<f64>) {
+    let m = A.len();
+    let n = A[0].len();
+    let x = (0..n).map(|_| rng.int(0, 5)).collect::<Vec<_>>();
+    let mut b = feasible_rhs(rng, &A[..m - 1], &x, &[]);
+    let y = (0..m - 1).map(|_| rng.int(0, 3)).collect::<Vec<_>>();
+
+    let last = (0..n)
+        .map(|j| {
+            let combined: f64 =
+                A.iter().zip(&y).map(|(row, y)| row[j] * y).sum();
+            rng.int(0, 3) - combined
+        })
+        .collect();
+    A[m - 1] = last;
+    let combined: f64 = b.iter().zip(&y).map(|(b, y)| b * y).sum();
+    b.push(-combined - rng.int(1, 9));
+
+    let c = (0..n).map(|_| rng.int(-9, 9)).collect();
+    (b, c)
+}
+
+/**
+ * Make `A` and `c` such that a random direction `d >= 0` has
+ * `A d <= 0` and `c^T d > 0`, then pick `b` so a random `x >= 0` is
+ * feasible. The objective grows without bound along `x + t d`.
+ */
+fn unbounded(rng: &mut Rng, A: &mut [Vec<f64>]) -> (Vec<f64>, Vec<f64>) {
+    let n = A[0].len();
+    let count = rng.index(1, n.min(3));
+    let support = rng.subset(n, count);
+    let mut d = vec![0.0; n];
+    support.iter().for_each(|&j| d[j] = rng.int(1, 3));
+
+    let j = support[0];
+    for row in A.iter_mut() {
+        let excess = dot(row, &d);
+        if excess > 0.0 {
+            row[j] -= (excess / d[j]).ceil();
+        }
+    }
+
+    let mut c = (0..n).map(|_| rng.int(-9, 9)).collect::<Vec<_>>();
+    let gain = dot(&c, &d);
+    if gain <= 0.0 {
+        c[j] += ((1.0 - gain) / d[j]).ceil();
+    }
+
+    let x = (0..n).map(|_| rng.int(0, 5)).collect::<Vec<_>>();
+    let b = feasible_rhs(rng, A, &x, &[]);
+    (b, c)
+}
+
+/**
+ * The Klee-Minty cube in `n` dimensions: maximize
+ * `sum 2^(n-j) x_j` subject to `sum_{j<i} 2^(i-j+1) x_j + x_i <= 5^i`.
+ * The optimal value is `5^n`.
+ */
+fn klee_minty(n: usize) -> Result<ParsedLP, String> {
+    if n == 0 || n > KLEE_MINTY_MAX {
+        return Err(format!(
+            "The Klee-Minty cube needs between 1 and {} columns",
+            KLEE_MINTY_MAX
+        ));
+    }
+
+    let pow = |base: f64, exp: usize| base.powi(exp as i32);
+    let c = (1..=n).map(|j| pow(2.0, n - j)).collect();
+    let A = (1..=n)
+        .map(|i| {
+            (1..=n)
+                .map(|j| match j {
+                    j if j < i => pow(2.0, i - j + 1),
+                    j if j == i => 1.0,
+                    _ => 0.0,
+                })
+                .collect()
+        })
+        .collect();
+    let b = (1..=n).map(|i| pow(5.0, i)).collect();
+
+    ParsedLP::new(c, A, b, Vec::new())
+}
+
+/**
+ * Beale's example, which cycles under the largest coefficient rule
+ * without perturbation. The optimal value is 1.25.
+ */
+fn cycling() -> Result<ParsedLP, String> {
+    ParsedLP::new(
+        vec![0.75, -20.0, 0.5, -6.0],
+        vec![
+            vec![0.25, -8.0, -1.0, 9.0],
+            vec![0.5, -12.0, -0.5, 3.0],
+            vec![0.0, 0.0, 1.0, 0.0],
+        ],
+        vec![0.0, 0.0, 1.0],
+        Vec::new(),
+    )
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
@@ -0,0 +1,53 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![allow(non_snake_case)]
+#![allow(clippy::neg_cmp_op_on_partial_ord)]
+#![allow(clippy::many_single_char_names)]
+
+extern crate nalgebra as na;
+
+pub mod bench;
+pub mod check;
+pub mod generator;
+pub mod model;
+pub mod output;
+pub mod parse;
+pub mod repl;
+pub mod scalar;
+pub mod scale;
+pub mod solve;
+pub mod util;
+
+use na::{DMatrix, DVector};
+pub type Matrix<T = f64> = DMatrix<T>;
+pub type Vector<T = f64> = DVector<T>;
+
+/**
+ * Floating point comparison epsilon. Previously
+ * used to mitigate floating point errors with many
+ * of the netlib tests. I'm not sure if it's still
+ * necessary after implementing perturbation but I'm
+ * leaving it in anyway.
+ */
+pub const EPSILON: f64 = 1e-9;
+
+/**
+ * Perturbation amount is copied from glpk source code.
+ * I'm not sure if there is a prescribed way for choosing
+ * this value other than for it to be "sufficiently small".
+ */
+pub const PERTURB_AMT: f64 = 1e-9;
@@ -15,80 +15,921 @@
  */
 
 #![allow(non_snake_case)]
-#![allow(clippy::neg_cmp_op_on_partial_ord)]
-#![allow(clippy::many_single_char_names)]
 
-extern crate nalgebra as na;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
 
-mod parse;
-mod solve;
-mod util;
+use clap::{Args, Parser, Subcommand};
 
-use std::collections::HashSet;
+use bblp::bench::{self, OptionSet, ReportFormat};
+use bblp::generator::{self, Kind};
+use bblp::model::{self, Model, ModelFormat, ObjectiveSense, Stats};
+use bblp::output::{
+    self, DictionaryFormat, DictionaryLayout, OutputFormat, Precision,
+    SolutionFormat,
+};
+use bblp::parse::ParsedLP;
+use bblp::scalar::{Rational, Scalar};
+use bblp::scale::Scaling;
+use bblp::solve::{
+    self, Control, Cuts, LogFormat, LogLevel, Method, Monitor, SolveResult,
+};
+use bblp::{check, parse};
 
-use na::{DMatrix, DVector};
-use solve::SolveResult;
-pub type Matrix = DMatrix<f64>;
-pub type Vector = DVector<f64>;
+/**
+ * Largest relative difference from the exact objective value
+ * accepted by `--compare-exact`
+ */
+const EXACT_TOLERANCE: f64 = 1e-6;
+
+/**
+ * The subcommands, and the arguments which print help or the version
+ * instead of running one. Anything else runs `solve`, so `bblp model.txt`
+ * keeps working.
+ */
+const COMMANDS: [&str; 13] = [
+    "solve",
+    "convert",
+    "dual",
+    "stats",
+    "bench",
+    "gen",
+    "check",
+    "repl",
+    "help",
+    "-h",
+    "--help",
+    "-V",
+    "--version",
+];
 
 /**
- * Floating point comparison epsilon. Previously
- * used to mitigate floating point errors with many
- * of the netlib tests. I'm not sure if it's still
- * necessary after implementing perturbation but I'm
- * leaving it in anyway.
+ * The process exit codes. Usage errors exit with 2, the same as the
+ * ones reported by clap.
  */
-pub const EPSILON: f64 = 1e-9;
+const EXIT_ERROR: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_INFEASIBLE: u8 = 3;
+const EXIT_UNBOUNDED: u8 = 4;
+const EXIT_LIMIT: u8 = 5;
 
 /**
- * Perturbation amount is copied from glpk source code.
- * I'm not sure if there is a prescribed way for choosing
- * this value other than for it to be "sufficiently small".
+ * An error from a subcommand. `Usage` is for options that can't be used
+ * together and inputs of the wrong kind, which clap can't check.
  */
-pub const PERTURB_AMT: f64 = 1e-9;
+enum CommandError {
+    Usage(String),
+    Failed(String),
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Failed(message)
+    }
+}
+
+/**
+ * How the results are printed
+ */
+struct PrintOptions {
+    format: OutputFormat,
+    solution_format: Option<SolutionFormat>,
+    precision: Precision,
+    debug: bool,
+}
+
+#[derive(Parser)]
+#[command(
+    name = "bblp",
+    version,
+    about = "Solve linear programs with the revised simplex method",
+    after_help = concat!(
+        "Exit codes: 0 optimal, 1 error, 2 usage error, 3 infeasible, ",
+        "4 unbounded, 5 limit reached"
+    )
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /** Solve a model (the default when no subcommand is given) */
+    Solve(SolveArgs),
+    /** Convert a model between the native, LP, MPS and JSON formats */
+    Convert(ConvertArgs),
+    /** Write the LP dual of a model */
+    Dual(DualArgs),
+    /** Print statistics about the size and numerics of a model */
+    Stats(StatsArgs),
+    /** Solve models with several option sets and report the work done */
+    Bench(BenchArgs),
+    /** Generate a random model of a given size and kind */
+    Gen(GenArgs),
+    /** Check a solution against the KKT conditions */
+    Check(CheckArgs),
+    /** Step through the simplex method on a model interactively */
+    Repl(ReplArgs),
+}
+
+#[derive(Args)]
+struct SolveArgs {
+    /** The model, read from stdin if it isn't given */
+    model: Option<String>,
+
+    /** The solve method: simplex or ipm */
+    #[arg(long, default_value = "simplex")]
+    method: Method,
+
+    /** Scale the problem: none, geometric, equilibrate or both */
+    #[arg(long, default_value = "none")]
+    scaling: Scaling,
+
+    /** Don't perturb the problem to avoid cycling */
+    #[arg(long)]
+    no_perturb: bool,
+
+    /** Cutting planes for integer variables: none, gomory or gomory-bb */
+    #[arg(long, default_value = "none")]
+    cuts: Cuts,
+
+    /** Stop branch and bound after this many nodes */
+    #[arg(long)]
+    node_limit: Option<usize>,
+
+    /** Stop branch and bound after this many seconds */
+    #[arg(long, value_parser = parse_seconds)]
+    time_limit: Option<Duration>,
+
+    /** Recover a basic solution from the interior point solution */
+    #[arg(long, requires = "method")]
+    crossover: bool,
+
+    /** Solve in exact rational arithmetic */
+    #[arg(long, conflicts_with_all = [
+        "cuts",
+        "node_limit",
+        "time_limit",
+        "crossover",
+        "compare_exact",
+        "plot",
+    ])]
+    exact: bool,
+
+    /** Check the result against the exact result */
+    #[arg(long)]
+    compare_exact: bool,
+
+    /** The output format: text or json */
+    #[arg(long, default_value = "text")]
+    output: OutputFormat,
+
+    /** Print a solution file instead: glpk, cplex, gurobi or mps-basis */
+    #[arg(long)]
+    solution_format: Option<SolutionFormat>,
+
+    /** The precision of the printed numbers, e.g. 12 or full,duals=sci:3 */
+    #[arg(long)]
+    precision: Option<Precision>,
+
+    /** Print the debugging details to stderr */
+    #[arg(long)]
+    debug: bool,
+
+    /** Draw the problem and the pivot path to an SVG file */
+    #[arg(long, value_name = "FILE")]
+    plot: Option<String>,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+#[derive(Args)]
+struct LogArgs {
+    /** What to log to stderr: none, phase or iteration */
+    #[arg(long, default_value = "none")]
+    log_level: LogLevel,
+
+    /** The log format: table or json */
+    #[arg(long, default_value = "table")]
+    log_format: LogFormat,
+
+    /** Log every this many iterations */
+    #[arg(long, default_value_t = 1)]
+    log_interval: usize,
+
+    /** Print the dictionary at each pivot to stderr */
+    #[arg(long)]
+    show_dictionaries: bool,
+
+    /** The dictionary layout: dictionary or tableau */
+    #[arg(long, default_value = "dictionary")]
+    dictionary_layout: DictionaryLayout,
+
+    /** The dictionary format: text or latex */
+    #[arg(long, default_value = "text")]
+    dictionary_format: DictionaryFormat,
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+    /** The model */
+    model: String,
+
+    /** Where to write the model, stdout if it isn't given */
+    output: Option<String>,
+
+    #[command(flatten)]
+    formats: FormatArgs,
+}
+
+#[derive(Args)]
+struct DualArgs {
+    /** The model */
+    model: String,
+
+    /** Where to write the dual, stdout if it isn't given */
+    output: Option<String>,
+
+    #[command(flatten)]
+    formats: FormatArgs,
+}
+
+#[derive(Args)]
+struct StatsArgs {
+    /** The model */
+    model: String,
+
+    /** The model's format: native, lp, mps or json (default: by extension) */
+    #[arg(long)]
+    from: Option<ModelFormat>,
+
+    /** The output format: text or json */
+    #[arg(long, default_value = "text")]
+    output: OutputFormat,
+}
+
+#[derive(Args)]
+struct BenchArgs {
+    /** The models, or directories of models, to solve */
+    #[arg(required = true)]
+    models: Vec<String>,
+
+    /** Settings to solve with, e.g. scaling=both,perturb=off (repeatable) */
+    #[arg(long = "options", value_name = "SET", default_value = "default")]
+    option_sets: Vec<OptionSet>,
+
+    /** Solve each model this many times and keep the fastest */
+    #[arg(long, default_value_t = 1)]
+    repeat: usize,
+
+    /** Write a report of the results to this file */
+    #[arg(long, value_name = "FILE")]
+    report: Option<String>,
+
+    /** The report format: csv or json (default: by extension) */
+    #[arg(long)]
+    format: Option<ReportFormat>,
 
-fn main() -> Result<(), String> {
-    let (flags, args): (Vec<String>, Vec<String>) =
-        std::env::args().skip(1).partition(|a| a.starts_with("--"));
+    /** Compare the results against an earlier report */
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<String>,
 
-    let flags: HashSet<String> = flags.into_iter().collect();
-    let no_perturb = flags.contains("--no-perturb");
+    /** Relative growth in pivots, factorizations or time that's a regression */
+    #[arg(long, default_value_t = 0.1)]
+    tolerance: f64,
+}
+
+#[derive(Args)]
+struct GenArgs {
+    /** Where to write the model, stdout if it isn't given */
+    output: Option<String>,
+
+    /** The kind of problem, e.g. random, feasible or klee-minty */
+    #[arg(long, default_value = "random")]
+    kind: Kind,
+
+    /** The number of constraints */
+    #[arg(long, default_value_t = 5)]
+    rows: usize,
+
+    /** The number of variables, and the Klee-Minty cube's dimension */
+    #[arg(long, default_value_t = 5)]
+    cols: usize,
+
+    /** The fraction of the constraint coefficients which are nonzero */
+    #[arg(long, default_value_t = 1.0)]
+    density: f64,
+
+    /** The random seed, the same seed always gives the same model */
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+}
+
+#[derive(Args)]
+struct FormatArgs {
+    /** The input format: native, lp, mps or json (default: by extension) */
+    #[arg(long)]
+    from: Option<ModelFormat>,
+
+    /** The output format: native, lp, mps or json (default: by extension) */
+    #[arg(long)]
+    to: Option<ModelFormat>,
+
+    /** Keep fractions exact */
+    #[arg(long)]
+    exact: bool,
+}
+
+#[derive(Args)]
+struct CheckArgs {
+    /** The model */
+    model: String,
+
+    /** The solution, read from stdin if it isn't given */
+    solution: Option<String>,
+}
 
-    let stdin = String::from("/dev/stdin");
-    let path = args.get(0).unwrap_or(&stdin);
+#[derive(Args)]
+struct ReplArgs {
+    /** The model */
+    model: String,
 
+    /** Pivot in exact rational arithmetic */
+    #[arg(long)]
+    exact: bool,
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().collect();
+    match args.get(1) {
+        Some(arg) if COMMANDS.contains(&arg.as_str()) => {}
+        _ => args.insert(1, String::from("solve")),
+    }
+
+    let done = |result: Result<(), String>| {
+        result
+            .map(|_| ExitCode::SUCCESS)
+            .map_err(CommandError::from)
+    };
+    let result = match Cli::parse_from(args).command {
+        Command::Solve(args) => solve(&args),
+        Command::Convert(args) => done(convert(&args)),
+        Command::Dual(args) => done(dual(&args)),
+        Command::Stats(args) => done(stats(&args)),
+        Command::Bench(args) => done(bench(&args)),
+        Command::Gen(args) => done(gen(&args)),
+        Command::Check(args) => check(&args).map(|_| ExitCode::SUCCESS),
+        Command::Repl(args) => done(repl(&args)),
+    };
+
+    let (message, code) = match result {
+        Ok(code) => return code,
+        Err(CommandError::Usage(message)) => (message, EXIT_USAGE),
+        Err(CommandError::Failed(message)) => (message, EXIT_ERROR),
+    };
+    eprintln!("Error: {}", message);
+    ExitCode::from(code)
+}
+
+/**
+ * Solve the model and print the results, returning the exit code for
+ * the result's status
+ */
+fn solve(args: &SolveArgs) -> Result<ExitCode, CommandError> {
+    let path = args.model.as_deref().unwrap_or("/dev/stdin");
     let file_contents = parse::read_file(path)?;
+    let start = Instant::now();
+
+    let method = args.method;
+    let no_perturb = args.no_perturb;
+    let print_options = PrintOptions {
+        format: args.output,
+        solution_format: args.solution_format,
+        precision: args.precision.unwrap_or_default(),
+        debug: args.debug,
+    };
+    let limits = solve::Limits {
+        nodes: args.node_limit,
+        time: args.time_limit,
+    };
+
+    if args.exact {
+        if method != Method::Simplex || args.scaling != Scaling::None {
+            return Err(CommandError::Usage(String::from(
+                "--exact can't be combined with --method or --scaling",
+            )));
+        }
+
+        let parsed = parse::parse::<Rational>(&file_contents)?;
+        let mut monitor = monitor(&args.log);
+        let solve_result = solve_exact(&parsed, no_perturb, &mut monitor)?;
+        print_result(&solve_result, &parsed, &print_options, start.elapsed())?;
+        return Ok(exit_code(&solve_result));
+    }
+
+    if args.crossover && method != Method::Ipm {
+        return Err(CommandError::Usage(String::from(
+            "--crossover only works with --method ipm",
+        )));
+    }
+    let branching = args.cuts != Cuts::None
+        || args.node_limit.is_some()
+        || args.time_limit.is_some();
+    if branching && method != Method::Simplex {
+        return Err(CommandError::Usage(String::from(
+            "--cuts, --node-limit and --time-limit only work with the \
+             simplex method",
+        )));
+    }
+
     let parsed = parse::parse(&file_contents)?;
+    let plot = args.plot.as_ref();
+    if plot.is_some() {
+        if method != Method::Simplex || !parsed.integer.is_empty() {
+            return Err(CommandError::Usage(String::from(
+                "--plot only works with the simplex method on problems \
+                 without integer variables",
+            )));
+        }
+        output::plottable(&parsed)?;
+    }
 
-    let A = parsed.A;
-    let b = parsed.b;
-    let c = parsed.c;
-    let N: Vec<usize> = (0..parsed.n).collect();
-    let B: Vec<usize> = (parsed.n..parsed.n + parsed.m).collect();
-
-    let solve_result = if !(b.min() < -f64::EPSILON) {
-        solve::primal(&A, &b, &c, B, N, no_perturb)?
-    } else if !(c.max() > f64::EPSILON) {
-        solve::dual(&A, &b, &c, B, N, no_perturb)?
-    } else {
-        let zero = Vector::zeros(b.len());
-
-        // Solve the aux problem and feed the results into the
-        // dual solver.
-        match solve::primal(&A, &zero, &c, B, N, no_perturb)? {
-            SolveResult::Optimal(aux_solution) => {
-                solve::dual(&A, &b, &c, aux_solution.B, aux_solution.N, true)?
-            }
-            SolveResult::Unbounded | SolveResult::Infeasible => {
-                SolveResult::Unbounded
-            }
+    // The bases visited by the simplex method, for the plot
+    let mut pivots = Vec::new();
+    let mut monitor = monitor(&args.log);
+    if plot.is_some() {
+        monitor = monitor.on_pivot(|pivot| {
+            pivots.push((pivot.phase, pivot.B.to_vec()));
+            Control::Continue
+        });
+    }
+
+    let options = solve::Options {
+        method,
+        scaling: args.scaling,
+        cuts: args.cuts,
+        limits,
+        no_perturb,
+        crossover: args.crossover,
+    };
+    let solve_result = solve::solve(&parsed, &options, &mut monitor)?;
+    drop(monitor);
+    print_result(&solve_result, &parsed, &print_options, start.elapsed())?;
+
+    if let Some(path) = plot {
+        let m = parsed.A.nrows();
+        let n = parsed.A.ncols() - m;
+        let bases = output::visited_bases(&pivots, &solve_result, n, m);
+        let svg = output::plot(&parsed, &bases)?;
+        std::fs::write(path, svg)
+            .map_err(|e| format!("couldn't write {}: {}", path, e))?;
+    }
+
+    if args.compare_exact {
+        let exact = solve_exact(
+            &parse::parse::<Rational>(&file_contents)?,
+            no_perturb,
+            &mut Monitor::default(),
+        )?;
+        compare_exact(&solve_result, &exact)?;
+    }
+
+    Ok(exit_code(&solve_result))
+}
+
+/**
+ * Parse a number of seconds for `--time-limit`, which has to be finite,
+ * nonnegative and small enough for a `Duration`
+ */
+fn parse_seconds(s: &str) -> Result<Duration, String> {
+    let seconds: f64 = s
+        .parse()
+        .map_err(|_| format!("'{}' isn't a number of seconds", s))?;
+    Duration::try_from_secs_f64(seconds).map_err(|_| {
+        format!(
+            "'{}' isn't a finite, nonnegative number of seconds within the \
+             range of a duration",
+            s
+        )
+    })
+}
+
+/**
+ * The exit code for the status of a result. Results that stopped at a
+ * limit before being proven optimal have their own code.
+ */
+fn exit_code<T: Scalar>(solve_result: &SolveResult<T>) -> ExitCode {
+    match solve_result {
+        SolveResult::Optimal(_) => ExitCode::SUCCESS,
+        SolveResult::Infeasible => ExitCode::from(EXIT_INFEASIBLE),
+        SolveResult::Unbounded => ExitCode::from(EXIT_UNBOUNDED),
+        SolveResult::Feasible(..) | SolveResult::Stopped => {
+            ExitCode::from(EXIT_LIMIT)
         }
+    }
+}
+
+/**
+ * Check a claimed solution to the model against the KKT conditions,
+ * exiting with an error if it doesn't pass. A basis file is a usage
+ * error, since it can't be checked at all.
+ */
+fn check(args: &CheckArgs) -> Result<(), CommandError> {
+    let solution =
+        parse::read_file(args.solution.as_deref().unwrap_or("/dev/stdin"))?;
+
+    let parsed = parse::parse::<f64>(&parse::read_file(&args.model)?)?;
+    let file_report = check::check_file(
+        &parsed.A, &parsed.b, &parsed.c, &solution,
+    )
+    .map_err(|e| match output::is_basis_file(&solution) {
+        true => CommandError::Usage(e),
+        false => CommandError::Failed(e),
+    })?;
+    println!("{}", file_report.report);
+
+    // Only worth mentioning when the rounding is what lets it pass
+    if file_report.rounded()
+        && !file_report.report.passes(check::CHECK_TOLERANCE)
+    {
+        eprintln!(
+            "assuming the values are rounded to {} significant digits, \
+             the tolerance is {:.3e}",
+            file_report.digits.unwrap_or_default(),
+            file_report.tolerance
+        );
+    }
+
+    if !file_report.passes() {
+        return Err(String::from("Solution failed the KKT check").into());
+    }
+
+    Ok(())
+}
+
+/**
+ * Convert the model to another format, writing it to the output file or
+ * stdout
+ */
+fn convert(args: &ConvertArgs) -> Result<(), String> {
+    let output = args.output.as_ref();
+    let model = match args.formats.exact {
+        true => {
+            let model = read_model::<Rational>(&args.model, args.formats.from)?;
+            write_model(&model, output, &args.formats)?
+        }
+        false => {
+            let model = read_model::<f64>(&args.model, args.formats.from)?;
+            write_model(&model, output, &args.formats)?
+        }
+    };
+
+    write_output(output, &model)
+}
+
+/**
+ * Write the LP dual of the model to the output file, or stdout if it
+ * isn't given
+ */
+fn dual(args: &DualArgs) -> Result<(), String> {
+    let output = args.output.as_ref();
+    let (dual, sense) = match args.formats.exact {
+        true => {
+            let model = read_model::<Rational>(&args.model, args.formats.from)?;
+            let dual = model.dual()?;
+            (write_model(&dual, output, &args.formats)?, dual.sense)
+        }
+        false => {
+            let model = read_model::<f64>(&args.model, args.formats.from)?;
+            let dual = model.dual()?;
+            (write_model(&dual, output, &args.formats)?, dual.sense)
+        }
+    };
+
+    // The native format has no comments to say this in
+    let format = output_format(output, &args.formats);
+    if format == ModelFormat::Native && sense == ObjectiveSense::Minimize {
+        eprintln!(
+            "the native format only holds maximization problems, so the \
+             dual is written as max -b^T y and solving it gives the primal \
+             objective value negated"
+        );
+    }
+
+    write_output(output, &dual)
+}
+
+/**
+ * Print the statistics of the model
+ */
+fn stats(args: &StatsArgs) -> Result<(), String> {
+    let model = read_model::<f64>(&args.model, args.from)?;
+    let stats = Stats::compute(&model);
+
+    match args.output {
+        OutputFormat::Text => print!("{}", stats),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&stats).map_err(|e| e.to_string())?
+        ),
+    }
+
+    Ok(())
+}
+
+/**
+ * Solve every model with every option set, printing a line for each as
+ * it finishes, then write the report and compare against the baseline.
+ * Regressions are printed to stderr and fail the command.
+ */
+fn bench(args: &BenchArgs) -> Result<(), String> {
+    let baseline = match &args.baseline {
+        Some(path) => Some(bench::read_report(
+            &parse::read_file(path)?,
+            ReportFormat::from_path(path),
+        )?),
+        None => None,
     };
 
-    match flags.contains("--debug") {
-        true => eprintln!("{:?}", solve_result),
-        false => println!("{}", solve_result),
+    let models = bench::models(&args.models)?;
+    let model_width = models
+        .iter()
+        .map(|path| bench::model_name(path).len())
+        .chain(std::iter::once(5))
+        .max()
+        .unwrap_or(5);
+    let options_width = args
+        .option_sets
+        .iter()
+        .map(|set| set.name.len())
+        .chain(std::iter::once(7))
+        .max()
+        .unwrap_or(7);
+
+    println!(
+        "{:<mw$} {:<ow$} {:<10} {:>15} {:>7} {:>7} {:>9} {:>9} {:>9} {:>9}",
+        "model",
+        "options",
+        "status",
+        "objective",
+        "pivots",
+        "factors",
+        "aux ms",
+        "primal ms",
+        "dual ms",
+        "total ms",
+        mw = model_width,
+        ow = options_width,
+    );
+
+    let mut records = Vec::new();
+    for path in &models {
+        for set in &args.option_sets {
+            let record = bench::run(path, set, args.repeat);
+            let objective = record
+                .objective
+                .map(|v| format!("{:.6e}", v))
+                .unwrap_or_else(|| String::from("-"));
+            println!(
+                "{:<mw$} {:<ow$} {:<10} {:>15} {:>7} {:>7} {:>9.2} {:>9.2} \
+                 {:>9.2} {:>9.2}",
+                record.model,
+                record.options,
+                record.status,
+                objective,
+                record.pivots,
+                record.factorizations,
+                record.aux_seconds * 1000.0,
+                record.primal_seconds * 1000.0,
+                record.dual_seconds * 1000.0,
+                record.seconds * 1000.0,
+                mw = model_width,
+                ow = options_width,
+            );
+            if let Some(e) = &record.error {
+                eprintln!("{} [{}]: {}", record.model, record.options, e);
+            }
+            records.push(record);
+        }
+    }
+
+    if let Some(path) = &args.report {
+        let format =
+            args.format.unwrap_or_else(|| ReportFormat::from_path(path));
+        let report = bench::write_report(&records, format)?;
+        std::fs::write(path, report)
+            .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    }
+
+    if let (Some(baseline), Some(path)) = (baseline, &args.baseline) {
+        let regressions = bench::compare(&records, &baseline, args.tolerance);
+        for regression in &regressions {
+            eprintln!("regression: {}", regression);
+        }
+        if !regressions.is_empty() {
+            return Err(format!(
+                "{} regression(s) against {}",
+                regressions.len(),
+                path
+            ));
+        }
+        eprintln!("no regressions against {}", path);
+    }
+
+    Ok(())
+}
+
+/**
+ * Generate a model and write it in the native format
+ */
+fn gen(args: &GenArgs) -> Result<(), String> {
+    let lp = generator::generate(&generator::Options {
+        kind: args.kind,
+        rows: args.rows,
+        cols: args.cols,
+        density: args.density,
+        seed: args.seed,
+    })?;
+    let model =
+        model::write_model(&Model::from_parsed(&lp), ModelFormat::Native)?;
+
+    write_output(args.output.as_ref(), &model)
+}
+
+/**
+ * Read a model in the format from `--from`, or otherwise the file's
+ * extension, which is native unless it's `.lp`, `.mps` or `.json`
+ */
+fn read_model<T: Scalar>(
+    path: &str,
+    from: Option<ModelFormat>,
+) -> Result<Model<T>, String> {
+    let format = from
+        .or_else(|| ModelFormat::from_path(path))
+        .unwrap_or(ModelFormat::Native);
+
+    model::read_model(&parse::read_file(path)?, format)
+}
+
+/**
+ * Write a model in the format from `--to`, or otherwise the output
+ * file's extension. Models written to stdout are native by default.
+ */
+fn write_model<T: Scalar>(
+    model: &Model<T>,
+    output: Option<&String>,
+    formats: &FormatArgs,
+) -> Result<String, String> {
+    model::write_model(model, output_format(output, formats))
+}
+
+/**
+ * The format to write a model in: `--to`, or else going by the output
+ * file's extension, or else native
+ */
+fn output_format(output: Option<&String>, formats: &FormatArgs) -> ModelFormat {
+    formats
+        .to
+        .or_else(|| output.and_then(|out| ModelFormat::from_path(out)))
+        .unwrap_or(ModelFormat::Native)
+}
+
+fn write_output(output: Option<&String>, contents: &str) -> Result<(), String> {
+    match output {
+        Some(output) => std::fs::write(output, contents)
+            .map_err(|e| format!("Failed to write {}: {}", output, e)),
+        None => {
+            print!("{}", contents);
+            Ok(())
+        }
+    }
+}
+
+/**
+ * Print the results in the chosen format. A solution file format takes
+ * priority over `--output`, and the text format goes to stderr with the
+ * extra debugging details when `--debug` is set.
+ */
+fn print_result<T: Scalar>(
+    solve_result: &SolveResult<T>,
+    parsed: &ParsedLP<T>,
+    options: &PrintOptions,
+    elapsed: Duration,
+) -> Result<(), String> {
+    let precision = &options.precision;
+    if let Some(format) = options.solution_format {
+        let solution =
+            output::write_solution(format, parsed, solve_result, precision)?;
+        print!("{}", solution);
+        return Ok(());
+    }
+
+    match options.format {
+        OutputFormat::Json => {
+            println!("{}", output::json(solve_result, precision, elapsed))
+        }
+        OutputFormat::Text if options.debug => {
+            eprintln!("{}", output::text(solve_result, precision, true))
+        }
+        OutputFormat::Text => {
+            println!("{}", output::text(solve_result, precision, false))
+        }
     }
 
     Ok(())
 }
+
+/**
+ * Step through the simplex method on the model interactively, in
+ * exact arithmetic if `--exact` is set
+ */
+fn repl(args: &ReplArgs) -> Result<(), String> {
+    let contents = parse::read_file(&args.model)?;
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+
+    match args.exact {
+        true => {
+            let lp = parse::parse::<Rational>(&contents)?;
+            bblp::repl::run(&lp, stdin.lock(), &mut stdout)
+        }
+        false => {
+            let lp = parse::parse::<f64>(&contents)?;
+            bblp::repl::run(&lp, stdin.lock(), &mut stdout)
+        }
+    }
+}
+
+/**
+ * The monitor for the iteration log and dictionaries asked for by the
+ * command line options
+ */
+fn monitor<'a, T: Scalar>(args: &LogArgs) -> Monitor<'a, T> {
+    let monitor =
+        Monitor::new(args.log_level, args.log_format, args.log_interval);
+
+    match args.show_dictionaries {
+        true => monitor
+            .show_dictionaries(args.dictionary_layout, args.dictionary_format),
+        false => monitor,
+    }
+}
+
+/**
+ * Solve the problem with the simplex method in exact rational
+ * arithmetic. Integer variables aren't supported.
+ */
+fn solve_exact(
+    parsed: &ParsedLP<Rational>,
+    no_perturb: bool,
+    monitor: &mut Monitor<Rational>,
+) -> Result<SolveResult<Rational>, String> {
+    if !parsed.integer.is_empty() {
+        return Err(String::from(
+            "Integer variables can't be solved in exact arithmetic",
+        ));
+    }
+
+    solve::simplex(&parsed.A, &parsed.b, &parsed.c, no_perturb, monitor)
+}
+
+/**
+ * Check a floating point result against the exact result for the same
+ * problem. The statuses and objective values must agree, but the
+ * variable values are allowed to differ since the optimal solution
+ * isn't always unique.
+ */
+fn compare_exact(
+    solve_result: &SolveResult,
+    exact: &SolveResult<Rational>,
+) -> Result<(), String> {
+    match (solve_result, exact) {
+        (SolveResult::Optimal(solution), SolveResult::Optimal(expected)) => {
+            let expected = expected.objective_value.to_f64();
+            let error = (solution.objective_value - expected).abs()
+                / expected.abs().max(1.0);
+
+            if error > EXACT_TOLERANCE {
+                return Err(format!(
+                    "Objective value {} differs from the exact value {} \
+                     (relative error {:e})",
+                    solution.objective_value, expected, error
+                ));
+            }
+
+            eprintln!("matches exact result (relative error {:e})", error);
+            Ok(())
+        }
+        (a, b) if a.status() == b.status() => {
+            eprintln!("matches exact result");
+            Ok(())
+        }
+        (a, b) => Err(format!(
+            "Status {} differs from the exact status {}",
+            a.status(),
+            b.status()
+        )),
+    }
+}
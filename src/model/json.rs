@@ -0,0 +1,105 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::{Constraint, Model, Variable};
+use crate::scalar::Scalar;
+use serde_json::Value;
+
+/**
+ * Write the model as JSON. Floating point numbers are written as JSON
+ * numbers and exact fractions as strings such as `"5/4"`.
+ */
+pub fn write<T: Scalar>(model: &Model<T>) -> Result<String, String> {
+    serde_json::to_string_pretty(model)
+        .map(|json| json + "\n")
+        .map_err(|e| e.to_string())
+}
+
+/**
+ * Read a model from JSON, in the layout written by `write`. Numbers can
+ * be JSON numbers or strings, and bounds are `null` when infinite.
+ */
+pub fn read<T: Scalar>(contents: &str) -> Result<Model<T>, String> {
+    let model: Model<Value> =
+        serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    let n = model.variables.len();
+
+    let variables = model
+        .variables
+        .into_iter()
+        .map(|v| {
+            Ok(Variable {
+                objective: number(&v.objective)?,
+                lower: bound(&v.lower)?,
+                upper: bound(&v.upper)?,
+                name: v.name,
+                integer: v.integer,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    let constraints = model
+        .constraints
+        .into_iter()
+        .map(|c| {
+            if c.coefficients.len() != n {
+                return Err(format!(
+                    "Constraint '{}' has {} coefficients but there are {} \
+                     variables",
+                    c.name,
+                    c.coefficients.len(),
+                    n
+                ));
+            }
+
+            Ok(Constraint {
+                coefficients: c
+                    .coefficients
+                    .iter()
+                    .map(number)
+                    .collect::<Result<_, _>>()?,
+                rhs: number(&c.rhs)?,
+                name: c.name,
+                sense: c.sense,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(Model {
+        name: model.name,
+        sense: model.sense,
+        variables,
+        constraints,
+    })
+}
+
+fn number<T: Scalar>(value: &Value) -> Result<T, String> {
+    match value {
+        Value::Number(n) => T::parse(&n.to_string()),
+        Value::String(s) => T::parse(s),
+        _ => Err(format!("Expected a number, found {}", value)),
+    }
+}
+
+/**
+ * A bound, which is infinite when it's `null`
+ */
+fn bound<T: Scalar>(value: &Option<Value>) -> Result<Option<T>, String> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => number(value).map(Some),
+    }
+}
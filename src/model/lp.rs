@@ -0,0 +1,575 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::{
+    constraint_name, number, parse_bound, ConstraintSense, Model, ModelBuilder,
+    ObjectiveSense,
+};
+use crate::scalar::Scalar;
+
+/**
+ * Terms are wrapped onto a new line once a line gets this long
+ */
+const LINE_WIDTH: usize = 72;
+
+/**
+ * Write a model in the CPLEX LP format
+ */
+pub fn write<T: Scalar>(model: &Model<T>) -> String {
+    let names: Vec<&str> =
+        model.variables.iter().map(|v| v.name.as_str()).collect();
+
+    let mut out = format!("\\ Problem: {}\n", model.name);
+    out.push_str(match model.sense {
+        ObjectiveSense::Maximize => "Maximize\n",
+        ObjectiveSense::Minimize => "Minimize\n",
+    });
+
+    let objective: Vec<T> = model
+        .variables
+        .iter()
+        .map(|v| v.objective.clone())
+        .collect();
+    out.push_str(&expression("obj", &objective, &names));
+    out.push('\n');
+
+    out.push_str("Subject To\n");
+    for constraint in &model.constraints {
+        let sense = match constraint.sense {
+            ConstraintSense::Le => "<=",
+            ConstraintSense::Ge => ">=",
+            ConstraintSense::Eq => "=",
+        };
+        out.push_str(&expression(
+            &constraint.name,
+            &constraint.coefficients,
+            &names,
+        ));
+        out.push_str(&format!(" {} {}\n", sense, number(&constraint.rhs)));
+    }
+
+    // Variables are nonnegative unless a bound says otherwise
+    out.push_str("Bounds\n");
+    for variable in &model.variables {
+        let name = &variable.name;
+        match (&variable.lower, &variable.upper) {
+            (Some(l), None) if l.is_zero() => {}
+            (None, None) => out.push_str(&format!(" {} free\n", name)),
+            (Some(l), Some(u)) if l == u => {
+                out.push_str(&format!(" {} = {}\n", name, number(l)))
+            }
+            (lower, upper) => {
+                let lower = match lower {
+                    Some(l) => number(l),
+                    None => String::from("-inf"),
+                };
+                match upper {
+                    Some(u) => out.push_str(&format!(
+                        " {} <= {} <= {}\n",
+                        lower,
+                        name,
+                        number(u)
+                    )),
+                    None => out.push_str(&format!(" {} >= {}\n", name, lower)),
+                }
+            }
+        }
+    }
+
+    let integer: Vec<&str> = model
+        .variables
+        .iter()
+        .filter(|v| v.integer)
+        .map(|v| v.name.as_str())
+        .collect();
+    if !integer.is_empty() {
+        out.push_str("General\n");
+        out.push_str(&format!(" {}\n", integer.join(" ")));
+    }
+
+    out.push_str("End\n");
+    out
+}
+
+/**
+ * A labelled linear expression such as ` c1: 2 x1 - x2`, skipping
+ * zero coefficients
+ */
+fn expression<T: Scalar>(
+    label: &str,
+    coefficients: &[T],
+    names: &[&str],
+) -> String {
+    let mut out = format!(" {}:", label);
+    let mut line = out.len();
+    let mut empty = true;
+
+    for (a, name) in coefficients.iter().zip(names) {
+        if a.is_zero() {
+            continue;
+        }
+
+        let (sign, magnitude) = if *a < T::zero() {
+            ("-", a.abs())
+        } else {
+            ("+", a.clone())
+        };
+        let term = match magnitude == T::one() {
+            true => format!(" {} {}", sign, name),
+            false => format!(" {} {} {}", sign, number(&magnitude), name),
+        };
+
+        if line + term.len() > LINE_WIDTH {
+            out.push_str("\n  ");
+            line = 2;
+        }
+        line += term.len();
+        out.push_str(&term);
+        empty = false;
+    }
+
+    // A constraint needs at least one term
+    if empty {
+        out.push_str(&format!(" 0 {}", names.first().unwrap_or(&"x1")));
+    }
+
+    out
+}
+
+/**
+ * The sections of an LP file
+ */
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Start,
+    Objective,
+    Constraints,
+    Bounds,
+    General,
+    Binary,
+    End,
+}
+
+/**
+ * The keywords that start each section, longest first so `max` doesn't
+ * match the start of `maximize`
+ */
+const SECTIONS: [(&str, Section); 18] = [
+    ("subject to", Section::Constraints),
+    ("such that", Section::Constraints),
+    ("maximize", Section::Objective),
+    ("minimize", Section::Objective),
+    ("maximum", Section::Objective),
+    ("minimum", Section::Objective),
+    ("binaries", Section::Binary),
+    ("generals", Section::General),
+    ("general", Section::General),
+    ("binary", Section::Binary),
+    ("bounds", Section::Bounds),
+    ("bound", Section::Bounds),
+    ("s.t.", Section::Constraints),
+    ("max", Section::Objective),
+    ("min", Section::Objective),
+    ("gen", Section::General),
+    ("bin", Section::Binary),
+    ("st", Section::Constraints),
+];
+
+/**
+ * The terms of a linear expression, by variable name
+ */
+type Terms<T> = Vec<(String, T)>;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(String),
+    Name(String),
+    Sign(bool),
+    Sense(ConstraintSense),
+    Colon,
+}
+
+/**
+ * Read a model in the CPLEX LP format. Ranged constraints, objective
+ * constants and the quadratic, semi-continuous and SOS extensions
+ * aren't supported. Constraints without a name are named `c1`, `c2`,
+ * ... by their position, and variables are ordered by where they first
+ * appear.
+ */
+pub fn read<T: Scalar>(contents: &str) -> Result<Model<T>, String> {
+    let mut builder = ModelBuilder::<T>::new();
+    let mut section = Section::Start;
+    let mut objective = String::new();
+    let mut constraints = String::new();
+
+    // Bounds and integer variables are applied after the constraints
+    // so the variables keep the order they're used in
+    let mut bounds = Vec::new();
+    let mut integer = Vec::new();
+
+    for (line_number, line) in contents.lines().enumerate() {
+        let err = |e: String| format!("line {}: {}", line_number + 1, e);
+
+        // The comment written by `write` keeps the model's name
+        if let Some(name) = line.trim().strip_prefix("\\ Problem:") {
+            builder.name = String::from(name.trim());
+        }
+
+        let line = match line.split_once('\\') {
+            Some((line, _)) => line,
+            None => line,
+        };
+        let lower = line.trim().to_lowercase();
+
+        let mut rest = line.trim();
+        if lower == "end" {
+            section = Section::End;
+            continue;
+        }
+        if let Some((keyword, s)) = SECTIONS.iter().find(|(keyword, _)| {
+            lower.starts_with(keyword)
+                && lower[keyword.len()..]
+                    .chars()
+                    .next()
+                    .is_none_or(char::is_whitespace)
+        }) {
+            if *s == Section::Objective {
+                builder.sense = match lower.starts_with("max") {
+                    true => ObjectiveSense::Maximize,
+                    false => ObjectiveSense::Minimize,
+                };
+            }
+            section = *s;
+            rest = &rest[keyword.len()..];
+        }
+
+        match section {
+            Section::Objective => {
+                objective.push_str(rest);
+                objective.push(' ');
+            }
+            Section::Constraints => {
+                constraints.push_str(rest);
+                constraints.push(' ');
+            }
+            Section::Bounds if !rest.trim().is_empty() => {
+                bounds.push((line_number, tokenize(rest).map_err(err)?));
+            }
+            Section::General | Section::Binary => {
+                let binary = section == Section::Binary;
+                integer.extend(rest.split_whitespace().map(|n| (n, binary)));
+            }
+            Section::Start | Section::End if !rest.is_empty() => {
+                return Err(err(format!("Unexpected '{}'", rest)));
+            }
+            _ => {}
+        }
+    }
+
+    if section != Section::End {
+        return Err(String::from("Missing End"));
+    }
+
+    // The objective's label is optional
+    let tokens = tokenize(&objective)?;
+    let tokens = match tokens.get(1) {
+        Some(Token::Colon) => &tokens[2..],
+        _ => &tokens[..],
+    };
+    let (terms, rest) = read_expression::<T>(tokens)?;
+    if !rest.is_empty() {
+        return Err(String::from("Unexpected sense in the objective"));
+    }
+    for (name, value) in terms {
+        let j = builder.variable(&name);
+        let variable = &mut builder.variables[j];
+        variable.objective = variable.objective.clone() + value;
+    }
+
+    let tokens = tokenize(&constraints)?;
+    let mut tokens = &tokens[..];
+    while !tokens.is_empty() {
+        let name = match tokens {
+            [Token::Name(name), Token::Colon, rest @ ..] => {
+                tokens = rest;
+                name.clone()
+            }
+            _ => constraint_name(builder.rows.len()),
+        };
+        let err = |e: &str| format!("constraint {}: {}", name, e);
+
+        let (terms, rest) =
+            read_expression::<T>(tokens).map_err(|e| err(&e))?;
+        let (sense, rhs, rest) = match rest {
+            [Token::Sense(sense), rest @ ..] => {
+                let (rhs, rest) = signed_number::<T>(rest)
+                    .ok_or_else(|| err("Expected a right hand side"))?;
+                (*sense, rhs.map_err(|e| err(&e))?, rest)
+            }
+            _ => return Err(err("Expected <=, >= or =")),
+        };
+
+        let i = builder.add_row(&name, sense)?;
+        builder.rows[i].rhs = rhs;
+        for (variable, value) in terms {
+            let j = builder.variable(&variable);
+            builder.add_coefficient(i, j, value);
+        }
+        tokens = rest;
+    }
+
+    for (line_number, tokens) in bounds {
+        bound(&mut builder, &tokens)
+            .map_err(|e| format!("line {}: {}", line_number + 1, e))?;
+    }
+
+    for (name, binary) in integer {
+        let j = builder.variable(name);
+        let variable = &mut builder.variables[j];
+        variable.integer = true;
+        if binary {
+            variable.lower = Some(T::zero());
+            variable.upper = Some(T::one());
+        }
+    }
+
+    Ok(builder.build())
+}
+
+/**
+ * Split a line into tokens. Numbers start with a digit or a decimal
+ * point, and can be fractions such as `1/3` as written by `write` in
+ * exact mode. Names run up to whitespace or an operator.
+ */
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut tokens = Vec::new();
+    let mut k = 0;
+
+    while k < chars.len() {
+        let start = k;
+        let c = chars[k];
+        k += 1;
+
+        match c {
+            c if c.is_whitespace() => {}
+            '+' | '-' => tokens.push(Token::Sign(c == '-')),
+            ':' => tokens.push(Token::Colon),
+            '<' | '>' | '=' => {
+                while k < chars.len() && "<>=".contains(chars[k]) {
+                    k += 1;
+                }
+                let op: String = chars[start..k].iter().collect();
+                let sense = match op.as_str() {
+                    "<" | "<=" | "=<" => ConstraintSense::Le,
+                    ">" | ">=" | "=>" => ConstraintSense::Ge,
+                    "=" => ConstraintSense::Eq,
+                    _ => return Err(format!("Unknown operator '{}'", op)),
+                };
+                tokens.push(Token::Sense(sense));
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                while k < chars.len()
+                    && (chars[k].is_ascii_digit() || chars[k] == '.')
+                {
+                    k += 1;
+                }
+
+                // An exponent, if the `e` is followed by digits
+                if k < chars.len() && (chars[k] == 'e' || chars[k] == 'E') {
+                    let mut end = k + 1;
+                    if end < chars.len() && "+-".contains(chars[end]) {
+                        end += 1;
+                    }
+                    if end < chars.len() && chars[end].is_ascii_digit() {
+                        k = end;
+                        while k < chars.len() && chars[k].is_ascii_digit() {
+                            k += 1;
+                        }
+                    }
+                }
+
+                // A denominator, if the `/` is followed by digits
+                if k + 1 < chars.len()
+                    && chars[k] == '/'
+                    && chars[k + 1].is_ascii_digit()
+                {
+                    k += 1;
+                    while k < chars.len() && chars[k].is_ascii_digit() {
+                        k += 1;
+                    }
+                }
+                tokens.push(Token::Number(chars[start..k].iter().collect()));
+            }
+            _ => {
+                while k < chars.len()
+                    && !chars[k].is_whitespace()
+                    && !"+-<>=:".contains(chars[k])
+                {
+                    k += 1;
+                }
+                tokens.push(Token::Name(chars[start..k].iter().collect()));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/**
+ * Read a linear expression up to a sense or the end of the tokens,
+ * returning its terms and the remaining tokens
+ */
+fn read_expression<T: Scalar>(
+    tokens: &[Token],
+) -> Result<(Terms<T>, &[Token]), String> {
+    let mut terms = Vec::new();
+    let mut tokens = tokens;
+
+    loop {
+        let mut negative = false;
+        while let [Token::Sign(sign), rest @ ..] = tokens {
+            negative ^= sign;
+            tokens = rest;
+        }
+
+        let (coefficient, rest) = match tokens {
+            [Token::Number(n), rest @ ..] => (T::parse(n)?, rest),
+            _ => (T::one(), tokens),
+        };
+
+        match rest {
+            [Token::Name(name), rest @ ..] => {
+                let value = match negative {
+                    true => -coefficient,
+                    false => coefficient,
+                };
+                terms.push((name.clone(), value));
+                tokens = rest;
+            }
+            [] | [Token::Sense(_), ..] if tokens.len() == rest.len() => {
+                return Ok((terms, rest));
+            }
+            [] | [Token::Sense(_), ..] => {
+                return Err(String::from("Constants aren't supported"));
+            }
+            [token, ..] => return Err(format!("Unexpected {:?}", token)),
+        }
+    }
+}
+
+/**
+ * A number with an optional sign, and the remaining tokens
+ */
+fn signed_number<T: Scalar>(
+    tokens: &[Token],
+) -> Option<(Result<T, String>, &[Token])> {
+    match tokens {
+        [Token::Sign(true), Token::Number(n), rest @ ..] => {
+            Some((T::parse(n).map(|v| -v), rest))
+        }
+        [Token::Sign(false), Token::Number(n), rest @ ..]
+        | [Token::Number(n), rest @ ..] => Some((T::parse(n), rest)),
+        _ => None,
+    }
+}
+
+/**
+ * A part of a bound: a variable, a sense, or a value which is `None`
+ * when infinite
+ */
+enum BoundPart<T> {
+    Variable(String),
+    Sense(ConstraintSense),
+    Value(Option<T>),
+    Free,
+}
+
+/**
+ * Apply a line of the bounds section, which is `x free`, `x >= l`,
+ * `l <= x`, `x = v` or `l <= x <= u` (or the same with `>=`)
+ */
+fn bound<T: Scalar>(
+    builder: &mut ModelBuilder<T>,
+    tokens: &[Token],
+) -> Result<(), String> {
+    let mut parts = Vec::new();
+    let mut tokens = tokens;
+
+    while !tokens.is_empty() {
+        let (negative, rest) = match tokens {
+            [Token::Sign(negative), rest @ ..] => (*negative, rest),
+            _ => (false, tokens),
+        };
+
+        let part = match rest.first() {
+            Some(Token::Name(name)) if name.eq_ignore_ascii_case("free") => {
+                BoundPart::Free
+            }
+            Some(Token::Name(name))
+                if parse_bound::<T>(name).is_ok_and(|(b, _)| b.is_none()) =>
+            {
+                BoundPart::Value(None)
+            }
+            Some(Token::Name(name)) if !negative => {
+                BoundPart::Variable(name.clone())
+            }
+            Some(Token::Number(n)) => {
+                let value = T::parse(n)?;
+                BoundPart::Value(Some(if negative { -value } else { value }))
+            }
+            Some(Token::Sense(sense)) if tokens.len() == rest.len() => {
+                BoundPart::Sense(*sense)
+            }
+            _ => return Err(String::from("Invalid bound")),
+        };
+        parts.push(part);
+        tokens = &rest[1..];
+    }
+
+    use BoundPart::*;
+    use ConstraintSense::*;
+
+    // Each bound as (variable, lower, upper), where `None` leaves the
+    // bound as it is
+    let (name, lower, upper) = match parts.as_slice() {
+        [Variable(x), Free] => (x, Some(None), Some(None)),
+        [Variable(x), Sense(Ge), Value(l)]
+        | [Value(l), Sense(Le), Variable(x)] => (x, Some(l.clone()), None),
+        [Variable(x), Sense(Le), Value(u)]
+        | [Value(u), Sense(Ge), Variable(x)] => (x, None, Some(u.clone())),
+        [Variable(x), Sense(Eq), Value(v)]
+        | [Value(v), Sense(Eq), Variable(x)] => {
+            (x, Some(v.clone()), Some(v.clone()))
+        }
+        [Value(l), Sense(Le), Variable(x), Sense(Le), Value(u)]
+        | [Value(u), Sense(Ge), Variable(x), Sense(Ge), Value(l)] => {
+            (x, Some(l.clone()), Some(u.clone()))
+        }
+        _ => return Err(String::from("Invalid bound")),
+    };
+
+    let j = builder.variable(name);
+    let variable = &mut builder.variables[j];
+    if let Some(lower) = lower {
+        variable.lower = lower;
+    }
+    if let Some(upper) = upper {
+        variable.upper = upper;
+    }
+
+    Ok(())
+}
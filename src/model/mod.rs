@@ -0,0 +1,583 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::output::{constraint_name, variable_name};
+use crate::parse::ParsedLP;
+use crate::scalar::{NumberFormat, Scalar};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+mod json;
+mod lp;
+mod mps;
+mod native;
+mod stats;
+
+pub use stats::{Bin, ScalingRatio, Stats, ValueRange};
+
+/**
+ * The file formats models can be read and written in
+ *
+ * - `Native`: the course format read by `parse::parse`
+ * - `Lp`: the CPLEX LP format
+ * - `Mps`: MPS, fixed or free
+ * - `Json`: the `Model` struct serialized as JSON
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModelFormat {
+    Native,
+    Lp,
+    Mps,
+    Json,
+}
+
+impl FromStr for ModelFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "native" => Ok(ModelFormat::Native),
+            "lp" => Ok(ModelFormat::Lp),
+            "mps" => Ok(ModelFormat::Mps),
+            "json" => Ok(ModelFormat::Json),
+            _ => Err(format!(
+                "Unknown model format '{}' (expected native, lp, mps or json)",
+                s
+            )),
+        }
+    }
+}
+
+impl ModelFormat {
+    /**
+     * The format of a file going by its extension, if it's `.lp`,
+     * `.mps` or `.json`
+     */
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = path.rsplit_once('.')?.1.to_lowercase();
+        match extension.as_str() {
+            "lp" => Some(ModelFormat::Lp),
+            "mps" => Some(ModelFormat::Mps),
+            "json" => Some(ModelFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ObjectiveSense {
+    Maximize,
+    Minimize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ConstraintSense {
+    #[serde(rename = "<=")]
+    Le,
+    #[serde(rename = ">=")]
+    Ge,
+    #[serde(rename = "=")]
+    Eq,
+}
+
+/**
+ * A variable with its objective coefficient and bounds, where a bound
+ * of `None` is infinite
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Variable<T = f64> {
+    pub name: String,
+    pub objective: T,
+    pub lower: Option<T>,
+    pub upper: Option<T>,
+    pub integer: bool,
+}
+
+/**
+ * A constraint `a^T x <= rhs`, `a^T x >= rhs` or `a^T x = rhs`, with a
+ * coefficient for every variable
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Constraint<T = f64> {
+    pub name: String,
+    pub coefficients: Vec<T>,
+    pub sense: ConstraintSense,
+    pub rhs: T,
+}
+
+/**
+ * A linear program in general form, with named variables and
+ * constraints, any constraint senses and any variable bounds. This is
+ * what models are read into and written from; `to_parsed` converts it
+ * into the standard form the solvers work with.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Model<T = f64> {
+    pub name: String,
+    pub sense: ObjectiveSense,
+    pub variables: Vec<Variable<T>>,
+    pub constraints: Vec<Constraint<T>>,
+}
+
+/**
+ * The sign a variable is restricted to, after its other bounds have
+ * been turned into constraints
+ */
+#[derive(Clone, Copy, PartialEq)]
+enum Sign {
+    NonNegative,
+    NonPositive,
+    Free,
+}
+
+impl<T: Scalar> Model<T> {
+    /**
+     * The model of a problem in the native format, with the variables
+     * and constraints named as in the results
+     */
+    pub fn from_parsed(lp: &ParsedLP<T>) -> Self {
+        let n = lp.n;
+
+        Model {
+            name: String::from("bblp"),
+            sense: ObjectiveSense::Maximize,
+            variables: (0..n)
+                .map(|j| Variable {
+                    name: variable_name(j),
+                    objective: lp.c[j].clone(),
+                    lower: Some(T::zero()),
+                    upper: None,
+                    integer: lp.integer.contains(&j),
+                })
+                .collect(),
+            constraints: (0..lp.A.nrows())
+                .map(|i| Constraint {
+                    name: constraint_name(i),
+                    coefficients: (0..n)
+                        .map(|j| lp.A[(i, j)].clone())
+                        .collect(),
+                    sense: ConstraintSense::Le,
+                    rhs: lp.b[i].clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /**
+     * Convert the model into the standard form `max c^T x` subject to
+     * `A0 x <= b` and `x >= 0`. Minimization objectives and `>=` rows
+     * are negated, equality rows are split in two, bounds other than
+     * `x >= 0` and `x <= 0` become constraints, nonpositive variables
+     * are negated and free variables are split into two nonnegative
+     * parts. The variables keep their order, with the negative parts
+     * of free variables added at the end.
+     */
+    pub fn to_parsed(&self) -> Result<ParsedLP<T>, String> {
+        let model = self.with_bound_constraints();
+        let n = model.variables.len();
+        let signs = self.signs();
+
+        // The standard form columns of each variable and their signs
+        let mut columns: Vec<(usize, T)> = (0..n)
+            .map(|j| match signs[j] {
+                Sign::NonPositive => (j, -T::one()),
+                _ => (j, T::one()),
+            })
+            .collect();
+        columns.extend(
+            (0..n)
+                .filter(|j| signs[*j] == Sign::Free)
+                .map(|j| (j, -T::one())),
+        );
+
+        let row = |coefficients: &[T], scale: &T| {
+            columns
+                .iter()
+                .map(|(j, sign)| {
+                    coefficients[*j].clone() * sign.clone() * scale.clone()
+                })
+                .collect::<Vec<T>>()
+        };
+
+        let objective_sign = match model.sense {
+            ObjectiveSense::Maximize => T::one(),
+            ObjectiveSense::Minimize => -T::one(),
+        };
+        let objective = model
+            .variables
+            .iter()
+            .map(|v| v.objective.clone())
+            .collect::<Vec<T>>();
+        let c = row(&objective, &objective_sign);
+
+        let mut A = Vec::new();
+        let mut b = Vec::new();
+        for constraint in &model.constraints {
+            let senses: &[T] = match constraint.sense {
+                ConstraintSense::Le => &[T::one()],
+                ConstraintSense::Ge => &[-T::one()],
+                ConstraintSense::Eq => &[T::one(), -T::one()],
+            };
+            for sign in senses {
+                A.push(row(&constraint.coefficients, sign));
+                b.push(constraint.rhs.clone() * sign.clone());
+            }
+        }
+
+        if A.is_empty() {
+            return Err(String::from("The model doesn't have any constraints"));
+        }
+
+        let integer = columns
+            .iter()
+            .enumerate()
+            .filter(|(_, (j, _))| model.variables[*j].integer)
+            .map(|(k, _)| k)
+            .collect();
+
+        ParsedLP::new(c, A, b, integer)
+    }
+
+    /**
+     * The LP dual of the model. Each constraint has a dual variable
+     * named `y_` followed by the constraint's name, and each variable
+     * has a dual constraint with the variable's name. For a maximization
+     * problem the dual variable of a `<=` row is nonnegative, of a `>=`
+     * row nonpositive and of an equality free, and the dual constraint
+     * of a nonnegative variable is `>=`, of a nonpositive one `<=` and
+     * of a free one an equality. Everything is flipped for minimization.
+     * Bounds other than the sign of a variable are treated as
+     * constraints.
+     */
+    pub fn dual(&self) -> Result<Model<T>, String> {
+        if self.variables.iter().any(|v| v.integer) {
+            return Err(String::from(
+                "Problems with integer variables don't have an LP dual",
+            ));
+        }
+
+        let model = self.with_bound_constraints();
+        let signs = self.signs();
+        let maximize = model.sense == ObjectiveSense::Maximize;
+
+        let variables = model
+            .constraints
+            .iter()
+            .map(|constraint| {
+                let sign = match (constraint.sense, maximize) {
+                    (ConstraintSense::Eq, _) => Sign::Free,
+                    (ConstraintSense::Le, true)
+                    | (ConstraintSense::Ge, false) => Sign::NonNegative,
+                    _ => Sign::NonPositive,
+                };
+
+                Variable {
+                    name: format!("y_{}", constraint.name),
+                    objective: constraint.rhs.clone(),
+                    lower: match sign {
+                        Sign::NonNegative => Some(T::zero()),
+                        _ => None,
+                    },
+                    upper: match sign {
+                        Sign::NonPositive => Some(T::zero()),
+                        _ => None,
+                    },
+                    integer: false,
+                }
+            })
+            .collect();
+
+        let constraints = model
+            .variables
+            .iter()
+            .enumerate()
+            .map(|(j, variable)| Constraint {
+                name: variable.name.clone(),
+                coefficients: model
+                    .constraints
+                    .iter()
+                    .map(|c| c.coefficients[j].clone())
+                    .collect(),
+                sense: match (signs[j], maximize) {
+                    (Sign::Free, _) => ConstraintSense::Eq,
+                    (Sign::NonNegative, true) | (Sign::NonPositive, false) => {
+                        ConstraintSense::Ge
+                    }
+                    _ => ConstraintSense::Le,
+                },
+                rhs: variable.objective.clone(),
+            })
+            .collect();
+
+        Ok(Model {
+            name: format!("{}_dual", model.name),
+            sense: match maximize {
+                true => ObjectiveSense::Minimize,
+                false => ObjectiveSense::Maximize,
+            },
+            variables,
+            constraints,
+        })
+    }
+
+    /**
+     * The sign each variable is restricted to by its bounds. Variables
+     * whose bounds allow both signs are free, and any bounds other than
+     * zero are handled by `with_bound_constraints`.
+     */
+    fn signs(&self) -> Vec<Sign> {
+        self.variables
+            .iter()
+            .map(|v| match (&v.lower, &v.upper) {
+                (Some(l), _) if *l >= T::zero() => Sign::NonNegative,
+                (_, Some(u)) if *u <= T::zero() => Sign::NonPositive,
+                _ => Sign::Free,
+            })
+            .collect()
+    }
+
+    /**
+     * The model with every bound that isn't just the sign of a variable
+     * added as a constraint named after the variable, with `_lo` or
+     * `_up` appended
+     */
+    fn with_bound_constraints(&self) -> Model<T> {
+        let mut model = self.clone();
+        let n = self.variables.len();
+        let signs = self.signs();
+
+        for (j, variable) in self.variables.iter().enumerate() {
+            let unit = (0..n)
+                .map(|k| if k == j { T::one() } else { T::zero() })
+                .collect::<Vec<T>>();
+            let bounds = [
+                (
+                    &variable.lower,
+                    ConstraintSense::Ge,
+                    "lo",
+                    Sign::NonNegative,
+                ),
+                (
+                    &variable.upper,
+                    ConstraintSense::Le,
+                    "up",
+                    Sign::NonPositive,
+                ),
+            ];
+
+            for (bound, sense, suffix, implied_by) in bounds {
+                match bound {
+                    Some(bound)
+                        if !(bound.is_zero() && signs[j] == implied_by) =>
+                    {
+                        model.constraints.push(Constraint {
+                            name: format!("{}_{}", variable.name, suffix),
+                            coefficients: unit.clone(),
+                            sense,
+                            rhs: bound.clone(),
+                        })
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        model
+    }
+}
+
+/**
+ * Read a model in `format`. Models in the native format get the same
+ * variable and constraint names as in the results.
+ */
+pub fn read_model<T: Scalar>(
+    contents: &str,
+    format: ModelFormat,
+) -> Result<Model<T>, String> {
+    match format {
+        ModelFormat::Native => native::read(contents),
+        ModelFormat::Lp => lp::read(contents),
+        ModelFormat::Mps => mps::read(contents),
+        ModelFormat::Json => json::read(contents),
+    }
+}
+
+/**
+ * Write the model in `format`. The native format only has the standard
+ * form, so the model is converted with `Model::to_parsed` first and the
+ * names are lost.
+ */
+pub fn write_model<T: Scalar>(
+    model: &Model<T>,
+    format: ModelFormat,
+) -> Result<String, String> {
+    match format {
+        ModelFormat::Native => Ok(native::write(&model.to_parsed()?)),
+        ModelFormat::Lp => Ok(lp::write(model)),
+        ModelFormat::Mps => Ok(mps::write(model)),
+        ModelFormat::Json => json::write(model),
+    }
+}
+
+/**
+ * Format a number for a model file, at full precision (as a fraction
+ * for exact types)
+ */
+fn number<T: Scalar>(value: &T) -> String {
+    value.format(NumberFormat::Exact)
+}
+
+/**
+ * A constraint being read, with its coefficients by variable index
+ */
+struct Row<T> {
+    name: String,
+    sense: ConstraintSense,
+    rhs: T,
+    terms: Vec<(usize, T)>,
+}
+
+/**
+ * Collects a model as it's read, looking variables and constraints up
+ * by name. Variables are added the first time they're seen, with the
+ * default bounds `0 <= x < inf`.
+ */
+struct ModelBuilder<T> {
+    name: String,
+    sense: ObjectiveSense,
+    variables: Vec<Variable<T>>,
+    variable_index: HashMap<String, usize>,
+    rows: Vec<Row<T>>,
+    row_index: HashMap<String, usize>,
+}
+
+impl<T: Scalar> ModelBuilder<T> {
+    fn new() -> Self {
+        ModelBuilder {
+            name: String::from("bblp"),
+            sense: ObjectiveSense::Minimize,
+            variables: Vec::new(),
+            variable_index: HashMap::new(),
+            rows: Vec::new(),
+            row_index: HashMap::new(),
+        }
+    }
+
+    /**
+     * The index of the variable, adding it if it's new
+     */
+    fn variable(&mut self, name: &str) -> usize {
+        if let Some(j) = self.variable_index.get(name) {
+            return *j;
+        }
+
+        self.variables.push(Variable {
+            name: String::from(name),
+            objective: T::zero(),
+            lower: Some(T::zero()),
+            upper: None,
+            integer: false,
+        });
+        self.variable_index
+            .insert(String::from(name), self.variables.len() - 1);
+        self.variables.len() - 1
+    }
+
+    /**
+     * Add a constraint with a zero right hand side, returning its index
+     */
+    fn add_row(
+        &mut self,
+        name: &str,
+        sense: ConstraintSense,
+    ) -> Result<usize, String> {
+        if self.row_index.contains_key(name) {
+            return Err(format!("Duplicate constraint '{}'", name));
+        }
+
+        self.rows.push(Row {
+            name: String::from(name),
+            sense,
+            rhs: T::zero(),
+            terms: Vec::new(),
+        });
+        self.row_index
+            .insert(String::from(name), self.rows.len() - 1);
+        Ok(self.rows.len() - 1)
+    }
+
+    fn row(&self, name: &str) -> Option<usize> {
+        self.row_index.get(name).copied()
+    }
+
+    /**
+     * Add `value` to the coefficient of variable `j` in row `i`
+     */
+    fn add_coefficient(&mut self, i: usize, j: usize, value: T) {
+        self.rows[i].terms.push((j, value));
+    }
+
+    fn build(self) -> Model<T> {
+        let n = self.variables.len();
+        let constraints = self
+            .rows
+            .into_iter()
+            .map(|row| {
+                let mut coefficients = vec![T::zero(); n];
+                for (j, value) in row.terms {
+                    coefficients[j] = coefficients[j].clone() + value;
+                }
+                Constraint {
+                    name: row.name,
+                    coefficients,
+                    sense: row.sense,
+                    rhs: row.rhs,
+                }
+            })
+            .collect();
+
+        Model {
+            name: self.name,
+            sense: self.sense,
+            variables: self.variables,
+            constraints,
+        }
+    }
+}
+
+/**
+ * Parse a bound, where `inf` and `infinity` (in any case, with either
+ * sign) are infinite. Returns `None` for infinite bounds, and whether
+ * the bound was negative.
+ */
+fn parse_bound<T: Scalar>(s: &str) -> Result<(Option<T>, bool), String> {
+    let lower = s.to_lowercase();
+    let (negative, unsigned) = match lower.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, lower.strip_prefix('+').unwrap_or(&lower)),
+    };
+
+    match unsigned {
+        "inf" | "infinity" => Ok((None, negative)),
+        _ => {
+            let value = T::parse(s)?;
+            let negative = value < T::zero();
+            Ok((Some(value), negative))
+        }
+    }
+}
@@ -0,0 +1,447 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::{
+    number, parse_bound, ConstraintSense, Model, ModelBuilder, ObjectiveSense,
+};
+use crate::scalar::Scalar;
+
+/**
+ * The section of the file being read
+ */
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Start,
+    ObjSense,
+    Rows,
+    Columns,
+    Rhs,
+    Ranges,
+    Bounds,
+    End,
+}
+
+/**
+ * Read a model in fixed or free MPS. Fields are split on whitespace, so
+ * names can't contain spaces. The first `N` row is the objective and
+ * any others are ignored. Problems are minimized unless `OBJSENSE` says
+ * otherwise. An `UP` bound below zero on a variable without a lower
+ * bound makes it unbounded below, as in most other readers. Ranged
+ * rows become two constraints (see `add_range`).
+ */
+pub fn read<T: Scalar>(contents: &str) -> Result<Model<T>, String> {
+    let mut builder = ModelBuilder::<T>::new();
+    let mut section = Section::Start;
+    let mut objective: Option<String> = None;
+    let mut free_rows = Vec::new();
+    let mut integer = false;
+    let mut lower_set = Vec::new();
+    let mut ranges = Vec::new();
+
+    for (line_number, line) in contents.lines().enumerate() {
+        let err =
+            |message: &str| format!("line {}: {}", line_number + 1, message);
+
+        if line.starts_with('*') || line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+
+        // Section headers start in the first column
+        if !line.starts_with(char::is_whitespace) {
+            section = match fields[0].to_uppercase().as_str() {
+                "NAME" => {
+                    builder.name = fields[1..].join(" ");
+                    Section::Start
+                }
+                "OBJSENSE" if fields.len() > 1 => {
+                    builder.sense =
+                        objective_sense(fields[1]).ok_or_else(|| {
+                            err(&format!(
+                                "Unknown objective sense '{}'",
+                                fields[1]
+                            ))
+                        })?;
+                    Section::Start
+                }
+                "OBJSENSE" => Section::ObjSense,
+                "ROWS" => Section::Rows,
+                "COLUMNS" => Section::Columns,
+                "RHS" => Section::Rhs,
+                "BOUNDS" => Section::Bounds,
+                "ENDATA" => Section::End,
+                "RANGES" => Section::Ranges,
+                other => {
+                    return Err(err(&format!("Unknown section '{}'", other)))
+                }
+            };
+            continue;
+        }
+
+        match section {
+            Section::ObjSense => {
+                builder.sense =
+                    objective_sense(fields[0]).ok_or_else(|| {
+                        err(&format!("Unknown objective sense '{}'", fields[0]))
+                    })?;
+            }
+            Section::Rows => {
+                let name = fields
+                    .get(1)
+                    .ok_or_else(|| err("Expected a row type and name"))?;
+                let sense = match fields[0].to_uppercase().as_str() {
+                    "N" if objective.is_none() => {
+                        objective = Some(String::from(*name));
+                        continue;
+                    }
+                    "N" => {
+                        free_rows.push(String::from(*name));
+                        continue;
+                    }
+                    "L" => ConstraintSense::Le,
+                    "G" => ConstraintSense::Ge,
+                    "E" => ConstraintSense::Eq,
+                    other => {
+                        return Err(err(&format!(
+                            "Unknown row type '{}'",
+                            other
+                        )))
+                    }
+                };
+                builder.add_row(name, sense).map_err(|e| err(&e))?;
+            }
+            Section::Columns => {
+                if fields.get(1) == Some(&"'MARKER'") {
+                    match fields.get(2) {
+                        Some(&"'INTORG'") => integer = true,
+                        Some(&"'INTEND'") => integer = false,
+                        _ => return Err(err("Unknown marker")),
+                    }
+                    continue;
+                }
+
+                if fields.len() != 3 && fields.len() != 5 {
+                    return Err(err(
+                        "Expected a column and one or two entries",
+                    ));
+                }
+
+                let j = builder.variable(fields[0]);
+                if integer {
+                    builder.variables[j].integer = true;
+                }
+
+                for entry in fields[1..].chunks(2) {
+                    let value = T::parse(entry[1]).map_err(|e| err(&e))?;
+                    if objective.as_deref() == Some(entry[0]) {
+                        let variable = &mut builder.variables[j];
+                        variable.objective = variable.objective.clone() + value;
+                    } else if let Some(i) = builder.row(entry[0]) {
+                        builder.add_coefficient(i, j, value);
+                    } else if !free_rows.iter().any(|r| r == entry[0]) {
+                        return Err(err(&format!(
+                            "Unknown row '{}'",
+                            entry[0]
+                        )));
+                    }
+                }
+            }
+            Section::Rhs => {
+                // The name of the right hand side vector is optional
+                let entries = match fields.len() {
+                    2 | 4 => &fields[..],
+                    3 | 5 => &fields[1..],
+                    _ => return Err(err("Expected one or two entries")),
+                };
+
+                for entry in entries.chunks(2) {
+                    let value = T::parse(entry[1]).map_err(|e| err(&e))?;
+                    if objective.as_deref() == Some(entry[0]) {
+                        if !value.is_zero() {
+                            return Err(err(
+                                "Objective constants aren't supported",
+                            ));
+                        }
+                    } else if let Some(i) = builder.row(entry[0]) {
+                        builder.rows[i].rhs = value;
+                    } else {
+                        return Err(err(&format!(
+                            "Unknown row '{}'",
+                            entry[0]
+                        )));
+                    }
+                }
+            }
+            Section::Ranges => {
+                // The name of the range vector is optional as well
+                let entries = match fields.len() {
+                    2 | 4 => &fields[..],
+                    3 | 5 => &fields[1..],
+                    _ => return Err(err("Expected one or two entries")),
+                };
+
+                for entry in entries.chunks(2) {
+                    let value = T::parse(entry[1]).map_err(|e| err(&e))?;
+                    let i = builder.row(entry[0]).ok_or_else(|| {
+                        err(&format!("Unknown row '{}'", entry[0]))
+                    })?;
+                    ranges.push((i, value));
+                }
+            }
+            Section::Bounds => {
+                let kind = fields[0].to_uppercase();
+                let has_value =
+                    !matches!(kind.as_str(), "FR" | "MI" | "PL" | "BV");
+
+                // The name of the bound vector is optional too
+                let (name, value) = match (has_value, fields.len()) {
+                    (true, 4) => (fields[2], Some(fields[3])),
+                    (true, 3) => (fields[1], Some(fields[2])),
+                    (false, 3) => (fields[2], None),
+                    (false, 2) => (fields[1], None),
+                    _ => {
+                        return Err(err(
+                            "Expected a bound type, column and value",
+                        ))
+                    }
+                };
+                let j = *builder.variable_index.get(name).ok_or_else(|| {
+                    err(&format!("Unknown column '{}'", name))
+                })?;
+                let (value, negative) = match value {
+                    Some(v) => parse_bound::<T>(v).map_err(|e| err(&e))?,
+                    None => (None, false),
+                };
+
+                let variable = &mut builder.variables[j];
+                match kind.as_str() {
+                    "UP" | "UI" => {
+                        if negative && !lower_set.contains(&j) {
+                            variable.lower = None;
+                        }
+                        variable.upper = value;
+                    }
+                    "LO" | "LI" => {
+                        variable.lower = value;
+                        lower_set.push(j);
+                    }
+                    "FX" => {
+                        variable.lower = value.clone();
+                        variable.upper = value;
+                    }
+                    "FR" => {
+                        variable.lower = None;
+                        variable.upper = None;
+                    }
+                    "MI" => variable.lower = None,
+                    "PL" => variable.upper = None,
+                    "BV" => {
+                        variable.lower = Some(T::zero());
+                        variable.upper = Some(T::one());
+                    }
+                    other => {
+                        return Err(err(&format!(
+                            "Unknown bound type '{}'",
+                            other
+                        )))
+                    }
+                }
+                if matches!(kind.as_str(), "UI" | "LI" | "BV") {
+                    variable.integer = true;
+                }
+            }
+            Section::Start | Section::End => {
+                return Err(err("Expected a section header"));
+            }
+        }
+    }
+
+    if objective.is_none() {
+        return Err(String::from("The model doesn't have an objective row"));
+    }
+
+    // The right hand sides can come after the ranges
+    for (i, range) in ranges {
+        add_range(&mut builder, i, range)?;
+    }
+
+    Ok(builder.build())
+}
+
+/**
+ * Apply a range `r` to row `i`, which limits it to `rhs - |r|` to `rhs`
+ * for an `L` row, `rhs` to `rhs + |r|` for a `G` row, and `rhs` to
+ * `rhs + r` for an `E` row. Constraints only have one sense, so the
+ * other side is added as a constraint named after the row with
+ * `_range` appended.
+ */
+fn add_range<T: Scalar>(
+    builder: &mut ModelBuilder<T>,
+    i: usize,
+    range: T,
+) -> Result<(), String> {
+    let row = &builder.rows[i];
+    let rhs = row.rhs.clone();
+    let (sense, other_sense, other_rhs) = match row.sense {
+        ConstraintSense::Le => {
+            (ConstraintSense::Le, ConstraintSense::Ge, rhs - range.abs())
+        }
+        ConstraintSense::Ge => {
+            (ConstraintSense::Ge, ConstraintSense::Le, rhs + range.abs())
+        }
+        ConstraintSense::Eq if range.is_zero() => return Ok(()),
+        ConstraintSense::Eq if range > T::zero() => {
+            (ConstraintSense::Ge, ConstraintSense::Le, rhs + range)
+        }
+        ConstraintSense::Eq => {
+            (ConstraintSense::Le, ConstraintSense::Ge, rhs + range)
+        }
+    };
+
+    let name = format!("{}_range", row.name);
+    let terms = row.terms.clone();
+    builder.rows[i].sense = sense;
+    let k = builder.add_row(&name, other_sense)?;
+    builder.rows[k].rhs = other_rhs;
+    builder.rows[k].terms = terms;
+    Ok(())
+}
+
+fn objective_sense(s: &str) -> Option<ObjectiveSense> {
+    match s.to_uppercase().as_str() {
+        "MAX" | "MAXIMIZE" => Some(ObjectiveSense::Maximize),
+        "MIN" | "MINIMIZE" => Some(ObjectiveSense::Minimize),
+        _ => None,
+    }
+}
+
+/**
+ * Write a model in free MPS, with the objective row named `obj`.
+ * Integer columns are wrapped in `MARKER` lines.
+ */
+pub fn write<T: Scalar>(model: &Model<T>) -> String {
+    let mut out = format!("NAME {}\n", model.name);
+    out.push_str(match model.sense {
+        ObjectiveSense::Maximize => "OBJSENSE\n    MAX\n",
+        ObjectiveSense::Minimize => "OBJSENSE\n    MIN\n",
+    });
+
+    out.push_str("ROWS\n N  obj\n");
+    for constraint in &model.constraints {
+        let sense = match constraint.sense {
+            ConstraintSense::Le => "L",
+            ConstraintSense::Ge => "G",
+            ConstraintSense::Eq => "E",
+        };
+        out.push_str(&format!(" {}  {}\n", sense, constraint.name));
+    }
+
+    out.push_str("COLUMNS\n");
+    let mut in_integer = false;
+    let mut markers = 0;
+    for (j, variable) in model.variables.iter().enumerate() {
+        if variable.integer != in_integer {
+            let kind = if variable.integer { "INTORG" } else { "INTEND" };
+            out.push_str(&format!(
+                "    MARKER{} 'MARKER' '{}'\n",
+                markers, kind
+            ));
+            markers += 1;
+            in_integer = variable.integer;
+        }
+
+        let entries = std::iter::once(("obj", &variable.objective)).chain(
+            model
+                .constraints
+                .iter()
+                .map(|c| (c.name.as_str(), &c.coefficients[j])),
+        );
+        let mut written = false;
+        for (row, value) in entries {
+            if !value.is_zero() {
+                out.push_str(&format!(
+                    "    {} {} {}\n",
+                    variable.name,
+                    row,
+                    number(value)
+                ));
+                written = true;
+            }
+        }
+
+        // Keep columns without any coefficients
+        if !written {
+            out.push_str(&format!("    {} obj 0\n", variable.name));
+        }
+    }
+    if in_integer {
+        out.push_str(&format!("    MARKER{} 'MARKER' 'INTEND'\n", markers));
+    }
+
+    out.push_str("RHS\n");
+    for constraint in &model.constraints {
+        if !constraint.rhs.is_zero() {
+            out.push_str(&format!(
+                "    RHS {} {}\n",
+                constraint.name,
+                number(&constraint.rhs)
+            ));
+        }
+    }
+
+    // Integer columns default to binary in some readers, so their
+    // bounds are always written
+    out.push_str("BOUNDS\n");
+    for variable in &model.variables {
+        let name = &variable.name;
+        let mut bound = |kind: &str, value: Option<&T>| {
+            let value =
+                value.map_or(String::new(), |v| format!(" {}", number(v)));
+            out.push_str(&format!(" {} BND {}{}\n", kind, name, value));
+        };
+
+        match (&variable.lower, &variable.upper) {
+            (None, None) => bound("FR", None),
+            (Some(l), Some(u)) if l == u => bound("FX", Some(l)),
+            (lower, upper) => {
+                match lower {
+                    None => bound("MI", None),
+                    // Readers drop the lower bound of a variable with a
+                    // negative upper bound unless it's given
+                    Some(l)
+                        if !l.is_zero()
+                            || variable.integer
+                            || upper
+                                .as_ref()
+                                .is_some_and(|u| *u < T::zero()) =>
+                    {
+                        bound("LO", Some(l))
+                    }
+                    _ => {}
+                }
+                match upper {
+                    Some(u) => bound("UP", Some(u)),
+                    None if variable.integer => bound("PL", None),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    out.push_str("ENDATA\n");
+    out
+}
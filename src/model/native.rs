@@ -0,0 +1,58 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::{number, Model};
+use crate::parse::{self, ParsedLP};
+use crate::scalar::Scalar;
+
+/**
+ * Read a problem in the native format
+ */
+pub fn read<T: Scalar>(contents: &str) -> Result<Model<T>, String> {
+    Ok(Model::from_parsed(&parse::parse(contents)?))
+}
+
+/**
+ * Write a problem in the native format: the objective coefficients,
+ * then a row of coefficients and the right hand side for each
+ * constraint, then the integer variables (1-indexed) if there are any
+ */
+pub fn write<T: Scalar>(lp: &ParsedLP<T>) -> String {
+    let n = lp.n;
+    let row = |values: &mut dyn Iterator<Item = &T>| {
+        values.map(number).collect::<Vec<String>>().join(" ")
+    };
+
+    let mut out = row(&mut lp.c.iter().take(n));
+    out.push('\n');
+
+    for i in 0..lp.A.nrows() {
+        let mut values = (0..n).map(|j| &lp.A[(i, j)]).chain([&lp.b[i]]);
+        out.push_str(&row(&mut values));
+        out.push('\n');
+    }
+
+    if !lp.integer.is_empty() {
+        let integer = lp
+            .integer
+            .iter()
+            .map(|j| (j + 1).to_string())
+            .collect::<Vec<String>>();
+        out.push_str(&format!("integer {}\n", integer.join(" ")));
+    }
+
+    out
+}
@@ -0,0 +1,288 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::{ConstraintSense, Model};
+use crate::scale::{ScaleFactors, Scaling};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/**
+ * Width of the longest bar in the coefficient histogram
+ */
+const HISTOGRAM_WIDTH: usize = 40;
+
+/**
+ * The scaling methods compared in the scaling estimate
+ */
+const SCALINGS: [(&str, Scaling); 4] = [
+    ("none", Scaling::None),
+    ("geometric", Scaling::Geometric),
+    ("equilibrate", Scaling::Equilibrate),
+    ("both", Scaling::Both),
+];
+
+/**
+ * The smallest and largest nonzero absolute values of a set of
+ * coefficients
+ */
+#[derive(Clone, Copy, Serialize)]
+pub struct ValueRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+/**
+ * The number of constraint coefficients with an absolute value in
+ * `[10^exponent, 10^(exponent + 1))`
+ */
+#[derive(Serialize)]
+pub struct Bin {
+    pub exponent: i32,
+    pub count: usize,
+}
+
+/**
+ * The largest ratio between nonzero constraint coefficients after
+ * scaling the standard form of the problem with `scaling`
+ */
+#[derive(Serialize)]
+pub struct ScalingRatio {
+    pub scaling: &'static str,
+    pub ratio: f64,
+}
+
+/**
+ * Statistics about the size, sparsity and numerics of a model, to
+ * decide how to solve it
+ */
+#[derive(Serialize)]
+pub struct Stats {
+    pub rows: usize,
+    pub le_rows: usize,
+    pub ge_rows: usize,
+    pub eq_rows: usize,
+    pub columns: usize,
+    pub integer_columns: usize,
+    pub free_columns: usize,
+    pub nonzeros: usize,
+    pub density: f64,
+    pub empty_rows: usize,
+    pub singleton_rows: usize,
+    pub empty_columns: usize,
+    pub singleton_columns: usize,
+    pub A: Option<ValueRange>,
+    pub b: Option<ValueRange>,
+    pub c: Option<ValueRange>,
+    pub histogram: Vec<Bin>,
+    pub scaling: Vec<ScalingRatio>,
+}
+
+impl Stats {
+    /**
+     * Compute the statistics of the model. The scaling estimate is
+     * for the standard form the solvers work with, and is left out if
+     * the model can't be converted to it.
+     */
+    pub fn compute(model: &Model) -> Self {
+        let m = model.constraints.len();
+        let n = model.variables.len();
+
+        let count = |sense| {
+            model
+                .constraints
+                .iter()
+                .filter(|c| c.sense == sense)
+                .count()
+        };
+        let row_counts: Vec<usize> = model
+            .constraints
+            .iter()
+            .map(|c| c.coefficients.iter().filter(|a| **a != 0.0).count())
+            .collect();
+        let column_counts: Vec<usize> = (0..n)
+            .map(|j| {
+                model
+                    .constraints
+                    .iter()
+                    .filter(|c| c.coefficients[j] != 0.0)
+                    .count()
+            })
+            .collect();
+        let nonzeros = row_counts.iter().sum();
+
+        let coefficients = model
+            .constraints
+            .iter()
+            .flat_map(|c| c.coefficients.iter().copied());
+
+        let mut histogram = BTreeMap::new();
+        for a in coefficients.clone().filter(|a| *a != 0.0) {
+            *histogram.entry(a.abs().log10().floor() as i32).or_insert(0) += 1;
+        }
+
+        let scaling = match model.to_parsed() {
+            Ok(lp) => SCALINGS
+                .iter()
+                .map(|(name, scaling)| ScalingRatio {
+                    scaling: name,
+                    ratio: ScaleFactors::compute(&lp.A, lp.n, *scaling)
+                        .ratio(&lp.A),
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        Stats {
+            rows: m,
+            le_rows: count(ConstraintSense::Le),
+            ge_rows: count(ConstraintSense::Ge),
+            eq_rows: count(ConstraintSense::Eq),
+            columns: n,
+            integer_columns: model
+                .variables
+                .iter()
+                .filter(|v| v.integer)
+                .count(),
+            free_columns: model
+                .variables
+                .iter()
+                .filter(|v| v.lower.is_none() && v.upper.is_none())
+                .count(),
+            nonzeros,
+            density: match m * n {
+                0 => 0.0,
+                size => nonzeros as f64 / size as f64,
+            },
+            empty_rows: row_counts.iter().filter(|k| **k == 0).count(),
+            singleton_rows: row_counts.iter().filter(|k| **k == 1).count(),
+            empty_columns: column_counts.iter().filter(|k| **k == 0).count(),
+            singleton_columns: column_counts
+                .iter()
+                .filter(|k| **k == 1)
+                .count(),
+            A: range(coefficients),
+            b: range(model.constraints.iter().map(|c| c.rhs)),
+            c: range(model.variables.iter().map(|v| v.objective)),
+            histogram: histogram
+                .into_iter()
+                .map(|(exponent, count)| Bin { exponent, count })
+                .collect(),
+            scaling,
+        }
+    }
+}
+
+/**
+ * The range of the nonzero absolute values, if there are any
+ */
+fn range(values: impl Iterator<Item = f64>) -> Option<ValueRange> {
+    values
+        .map(f64::abs)
+        .filter(|v| *v > 0.0)
+        .fold(None, |range, v| match range {
+            None => Some(ValueRange { min: v, max: v }),
+            Some(r) => Some(ValueRange {
+                min: r.min.min(v),
+                max: r.max.max(v),
+            }),
+        })
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "rows               {} ({} <=, {} >=, {} =)",
+            self.rows, self.le_rows, self.ge_rows, self.eq_rows
+        )?;
+        writeln!(
+            f,
+            "columns            {} ({} integer, {} free)",
+            self.columns, self.integer_columns, self.free_columns
+        )?;
+        writeln!(f, "nonzeros           {}", self.nonzeros)?;
+        writeln!(f, "density            {:.2}%", 100.0 * self.density)?;
+        writeln!(f, "empty rows         {}", self.empty_rows)?;
+        writeln!(f, "empty columns      {}", self.empty_columns)?;
+        writeln!(f, "singleton rows     {}", self.singleton_rows)?;
+        writeln!(f, "singleton columns  {}", self.singleton_columns)?;
+
+        writeln!(f, "\n   min |value|  max |value|  ratio")?;
+        for (name, range) in [("A", self.A), ("b", self.b), ("c", self.c)] {
+            match range {
+                Some(r) => writeln!(
+                    f,
+                    "{}  {:<11.3e}  {:<11.3e}  {:.3e}",
+                    name,
+                    r.min,
+                    r.max,
+                    r.max / r.min
+                )?,
+                None => writeln!(f, "{}  (all zero)", name)?,
+            }
+        }
+
+        if !self.histogram.is_empty() {
+            writeln!(f, "\ncoefficient magnitudes")?;
+            let most = self.histogram.iter().map(|b| b.count).max().unwrap();
+            let label = |e: i32| format!("[1e{}, 1e{})", e, e + 1);
+            let first = self.histogram[0].exponent;
+            let last = self.histogram[self.histogram.len() - 1].exponent;
+            let label_width =
+                (first..=last).map(|e| label(e).len()).max().unwrap();
+
+            // Decades between the smallest and largest coefficients are
+            // shown even when they're empty
+            for exponent in first..=last {
+                let count = self
+                    .histogram
+                    .iter()
+                    .find(|b| b.exponent == exponent)
+                    .map_or(0, |b| b.count);
+                let width = (count * HISTOGRAM_WIDTH).div_ceil(most);
+                writeln!(
+                    f,
+                    "{:<l$}  {:<w$}  {}",
+                    label(exponent),
+                    "#".repeat(width),
+                    count,
+                    l = label_width,
+                    w = HISTOGRAM_WIDTH
+                )?;
+            }
+        }
+
+        if !self.scaling.is_empty() {
+            writeln!(f, "\nscaling            max/min |a_ij|")?;
+            let best = self
+                .scaling
+                .iter()
+                .min_by(|a, b| a.ratio.partial_cmp(&b.ratio).unwrap())
+                .unwrap();
+            for s in &self.scaling {
+                let marker = if std::ptr::eq(s, best) {
+                    "  (best)"
+                } else {
+                    ""
+                };
+                writeln!(f, "{:<18} {:.3e}{}", s.scaling, s.ratio, marker)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::output::{constraint_name, variable_name};
+use crate::parse::ParsedLP;
+use crate::scalar::Scalar;
+use crate::solve::SolveResult;
+use std::fmt::Write;
+
+/**
+ * Write the optimal basis as an MPS basis file. Variables are at their
+ * lower bound of zero and slacks are basic unless listed, so each
+ * basic variable is paired with a constraint whose slack is nonbasic,
+ * i.e. the constraint is at its upper bound (`XU`).
+ */
+pub fn write<T: Scalar>(
+    lp: &ParsedLP<T>,
+    result: &SolveResult<T>,
+) -> Result<String, String> {
+    let m = lp.A.nrows();
+    let n = lp.A.ncols() - m;
+
+    if !lp.integer.is_empty() {
+        return Err(String::from(
+            "Problems with integer variables don't have an optimal basis",
+        ));
+    }
+
+    let solution = match result {
+        SolveResult::Optimal(solution) | SolveResult::Feasible(solution, _) => {
+            solution
+        }
+        _ => {
+            return Err(format!(
+                "There is no basis to write for an {} problem",
+                result.status()
+            ))
+        }
+    };
+
+    if solution.B.is_empty() {
+        return Err(String::from(
+            "Interior point solutions don't have a basis (try --crossover)",
+        ));
+    }
+
+    let basic: Vec<usize> =
+        solution.B.iter().copied().filter(|j| *j < n).collect();
+    let tight: Vec<usize> =
+        (0..m).filter(|i| !solution.B.contains(&(n + i))).collect();
+
+    let mut out = String::from("NAME          bblp\n");
+    for (j, i) in basic.into_iter().zip(tight) {
+        writeln!(out, " XU {:<8}  {}", variable_name(j), constraint_name(i))
+            .unwrap();
+    }
+    out.push_str("ENDATA\n");
+    Ok(out)
+}
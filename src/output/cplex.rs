@@ -0,0 +1,301 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::output::{
+    cant_check, constraint_name, is_basic, name_index, row_activities,
+    solution_number, variable_name, Precision, SolutionValues,
+};
+use crate::parse::ParsedLP;
+use crate::scalar::Scalar;
+use crate::solve::SolveResult;
+use std::fmt::Write;
+
+/**
+ * CPLEX's solution status codes (`CPX_STAT_*` and `CPXMIP_*`)
+ */
+const STATUS_OPTIMAL: u32 = 1;
+const STATUS_UNBOUNDED: u32 = 2;
+const STATUS_INFEASIBLE: u32 = 3;
+const STATUS_ABORT_USER: u32 = 13;
+const STATUS_INTEGER_OPTIMAL: u32 = 101;
+const STATUS_INTEGER_INFEASIBLE: u32 = 103;
+const STATUS_INTEGER_FEASIBLE: u32 = 127;
+
+/**
+ * Write the results in the format of a CPLEX XML solution file. The
+ * reduced costs are `c_j - A_j^T y` as CPLEX reports them, which is
+ * the negative of the `z_j` computed by the simplex routines. Integer
+ * solutions only have the values, as in CPLEX.
+ */
+pub fn write<T: Scalar>(
+    lp: &ParsedLP<T>,
+    result: &SolveResult<T>,
+    precision: &Precision,
+) -> String {
+    let (A, b) = (&lp.A, &lp.b);
+    let mip = !lp.integer.is_empty();
+    let (solution, status, status_string) = match (result, mip) {
+        (SolveResult::Optimal(s), false) => {
+            (Some(s), STATUS_OPTIMAL, "optimal")
+        }
+        (SolveResult::Optimal(s), true) => {
+            (Some(s), STATUS_INTEGER_OPTIMAL, "integer optimal solution")
+        }
+        (SolveResult::Feasible(s, _), _) => {
+            (Some(s), STATUS_INTEGER_FEASIBLE, "integer feasible")
+        }
+        (SolveResult::Infeasible, false) => {
+            (None, STATUS_INFEASIBLE, "infeasible")
+        }
+        (SolveResult::Infeasible, true) => {
+            (None, STATUS_INTEGER_INFEASIBLE, "integer infeasible")
+        }
+        (SolveResult::Unbounded, _) => (None, STATUS_UNBOUNDED, "unbounded"),
+        (SolveResult::Stopped, _) => (None, STATUS_ABORT_USER, "aborted"),
+    };
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "<?xml version = \"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>"
+    )
+    .unwrap();
+    writeln!(out, "<CPLEXSolution version=\"1.2\">").unwrap();
+    writeln!(out, " <header").unwrap();
+    writeln!(out, "   problemName=\"bblp\"").unwrap();
+
+    let solution = match solution {
+        Some(solution) => solution,
+        None => {
+            writeln!(out, "   solutionStatusValue=\"{}\"", status).unwrap();
+            writeln!(out, "   solutionStatusString=\"{}\"/>", status_string)
+                .unwrap();
+            writeln!(out, "</CPLEXSolution>").unwrap();
+            return out;
+        }
+    };
+
+    let (type_value, type_string) = match mip || solution.B.is_empty() {
+        true => (3, "primal"),
+        false => (1, "basic"),
+    };
+    writeln!(
+        out,
+        "   objectiveValue=\"{}\"",
+        solution_number(&solution.objective_value, precision.objective)
+    )
+    .unwrap();
+    writeln!(out, "   solutionTypeValue=\"{}\"", type_value).unwrap();
+    writeln!(out, "   solutionTypeString=\"{}\"", type_string).unwrap();
+    writeln!(out, "   solutionStatusValue=\"{}\"", status).unwrap();
+    writeln!(out, "   solutionStatusString=\"{}\"", status_string).unwrap();
+    writeln!(out, "   simplexIterations=\"{}\"/>", solution.pivots).unwrap();
+
+    let n = solution.variable_values.len();
+    let activities = row_activities(A, solution);
+    writeln!(out, " <linearConstraints>").unwrap();
+    for (i, activity) in activities.iter().enumerate() {
+        let slack = b[i].clone() - activity.clone();
+        write!(
+            out,
+            "  <constraint name=\"{}\" index=\"{}\"",
+            constraint_name(i),
+            i
+        )
+        .unwrap();
+        if !mip {
+            let status = basis_status(is_basic(solution, n + i, &slack));
+            write!(out, " status=\"{}\"", status).unwrap();
+        }
+        write!(
+            out,
+            " slack=\"{}\"",
+            solution_number(&slack, precision.variables)
+        )
+        .unwrap();
+        if !mip {
+            let dual = &solution.dual_values[i];
+            write!(out, " dual=\"{}\"", solution_number(dual, precision.duals))
+                .unwrap();
+        }
+        writeln!(out, "/>").unwrap();
+    }
+    writeln!(out, " </linearConstraints>").unwrap();
+
+    writeln!(out, " <variables>").unwrap();
+    for (j, value) in solution.variable_values.iter().enumerate() {
+        write!(
+            out,
+            "  <variable name=\"{}\" index=\"{}\"",
+            variable_name(j),
+            j
+        )
+        .unwrap();
+        if !mip {
+            let status = basis_status(is_basic(solution, j, value));
+            write!(out, " status=\"{}\"", status).unwrap();
+        }
+        write!(
+            out,
+            " value=\"{}\"",
+            solution_number(value, precision.variables)
+        )
+        .unwrap();
+        if !mip {
+            let reduced_cost = -solution.reduced_costs[j].clone();
+            write!(
+                out,
+                " reducedCost=\"{}\"",
+                solution_number(&reduced_cost, precision.duals)
+            )
+            .unwrap();
+        }
+        writeln!(out, "/>").unwrap();
+    }
+    writeln!(out, " </variables>").unwrap();
+    writeln!(out, "</CPLEXSolution>").unwrap();
+    out
+}
+
+/**
+ * CPLEX's name for whether a variable is basic or at its lower bound
+ */
+fn basis_status(basic: bool) -> &'static str {
+    match basic {
+        true => "BS",
+        false => "LL",
+    }
+}
+
+/**
+ * Read the variable values and constraint duals from a CPLEX XML
+ * solution file. Only the elements and attributes that are needed
+ * are looked at, so this isn't a full XML parser. Entries are placed
+ * by their `index` attribute, or by their `name` if it has no index.
+ */
+pub fn read<T: Scalar>(
+    contents: &str,
+    n: usize,
+    m: usize,
+) -> Result<SolutionValues<T>, String> {
+    let header = elements(contents, "header")
+        .into_iter()
+        .next()
+        .ok_or_else(|| String::from("CPLEX solution has no header"))?;
+    let status = attribute(header, "solutionStatusString").unwrap_or("");
+
+    let feasible = match attribute(header, "primalFeasible") {
+        Some(feasible) => feasible == "1",
+        None => status.contains("optimal") || status.contains("feasible"),
+    };
+    if !feasible || status.contains("infeasible") {
+        return Err(cant_check(status));
+    }
+
+    let x = read_entries(contents, "variable", "value", 'x', n)?
+        .ok_or_else(|| String::from("CPLEX solution has no variable values"))?;
+    let y = read_entries(contents, "constraint", "dual", 'c', m)?;
+
+    Ok(SolutionValues { x, y })
+}
+
+/**
+ * Read the attribute `value_name` of every `tag` element. Returns
+ * `None` if there are no `tag` elements or any of them is missing the
+ * attribute, e.g. the duals of a MIP solution.
+ */
+fn read_entries<T: Scalar>(
+    contents: &str,
+    tag: &str,
+    value_name: &str,
+    prefix: char,
+    len: usize,
+) -> Result<Option<Vec<T>>, String> {
+    let mut values: Vec<Option<T>> = vec![None; len];
+    let mut found = false;
+
+    for (k, element) in elements(contents, tag).into_iter().enumerate() {
+        found = true;
+        let index = attribute(element, "index")
+            .and_then(|i| i.parse::<usize>().ok())
+            .or_else(|| name_index(attribute(element, "name")?, prefix))
+            .unwrap_or(k);
+        let value = match attribute(element, value_name) {
+            Some(value) => T::parse(value)?,
+            None => return Ok(None),
+        };
+
+        match values.get_mut(index) {
+            Some(slot) => *slot = Some(value),
+            None => {
+                return Err(format!(
+                    "CPLEX solution has {} index {} but expected {}",
+                    tag, index, len
+                ))
+            }
+        }
+    }
+
+    if !found {
+        return Ok(None);
+    }
+
+    values
+        .into_iter()
+        .collect::<Option<Vec<T>>>()
+        .map(Some)
+        .ok_or_else(|| format!("CPLEX solution is missing {} values", tag))
+}
+
+/**
+ * The contents of each `<tag ...>` start tag
+ */
+fn elements<'a>(contents: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}", tag);
+    contents
+        .split(open.as_str())
+        .skip(1)
+        .filter_map(|rest| match rest.chars().next() {
+            Some(ch) if ch.is_whitespace() || ch == '/' || ch == '>' => {
+                rest.split('>').next()
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/**
+ * The value of `name="value"` in the contents of a start tag
+ */
+fn attribute<'a>(element: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = element;
+    while let Some(pos) = rest.find(name) {
+        let before = rest[..pos].chars().last();
+        let after = rest[pos + name.len()..].trim_start();
+        rest = &rest[pos + name.len()..];
+
+        if before.is_none_or(char::is_whitespace) {
+            if let Some(value) = after.strip_prefix('=') {
+                let value = value.trim_start();
+                let quote = value.chars().next()?;
+                let value = &value[1..];
+                return value.find(quote).map(|end| &value[..end]);
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,370 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::output::basis_variable_name;
+use crate::scalar::Scalar;
+use crate::solve::{Phase, State};
+use crate::util::col_view;
+use crate::{Matrix, Vector};
+use std::str::FromStr;
+
+/**
+ * How the state of the simplex method is shown by
+ * `--show-dictionaries`
+ *
+ * - `Dictionary`: each basic variable and the objective written in
+ *   terms of the nonbasic variables
+ * - `Tableau`: the rows of `A_B^-1 A` next to the basic values, with
+ *   the reduced costs in the objective row
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DictionaryLayout {
+    Dictionary,
+    Tableau,
+}
+
+impl FromStr for DictionaryLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dictionary" => Ok(DictionaryLayout::Dictionary),
+            "tableau" => Ok(DictionaryLayout::Tableau),
+            _ => Err(format!(
+                "Unknown dictionary layout '{}' (expected dictionary or \
+                 tableau)",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * The formats the dictionaries can be printed in
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DictionaryFormat {
+    Text,
+    Latex,
+}
+
+impl FromStr for DictionaryFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(DictionaryFormat::Text),
+            "latex" => Ok(DictionaryFormat::Latex),
+            _ => Err(format!(
+                "Unknown dictionary format '{}' (expected text or latex)",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * A row of the grid a dictionary is laid out in. Separators are drawn
+ * as a line of dashes in text and as `\hline` in LaTeX.
+ */
+enum Row {
+    Cells(Vec<String>),
+    Separator,
+}
+
+/**
+ * Show the dictionary or tableau for the basis in `state`. When
+ * `pivot` is given, the entering and leaving variables are marked and
+ * the values of the ratio test are shown: a column of `x_i / dx_i`
+ * for the primal simplex, or a row of `z_j / dz_j` for the dual.
+ * Returns `None` if the basis is singular.
+ */
+pub(crate) fn dictionary<T: Scalar>(
+    state: &State<T>,
+    phase: Phase,
+    pivot: Option<(usize, usize)>,
+    layout: DictionaryLayout,
+    format: DictionaryFormat,
+) -> Option<String> {
+    let n = state.N.len();
+    let mut B = state.B.to_vec();
+    let mut N = state.N.to_vec();
+    B.sort_unstable();
+    N.sort_unstable();
+
+    // Row i of `D = A_B^-1 A_N` has the coefficients of the
+    // nonbasic variables in the row of basic variable `B[i]`
+    let A_B = col_view(state.A, &B);
+    let columns = N
+        .iter()
+        .map(|j| T::solve(&A_B, &state.A.column(*j).clone_owned()))
+        .collect::<Option<Vec<Vector<T>>>>()?;
+    let D = Matrix::from_iterator(
+        B.len(),
+        N.len(),
+        columns.iter().flat_map(|column| column.iter().cloned()),
+    );
+
+    let (entering, leaving) = match pivot {
+        Some((entering, leaving)) => (Some(entering), Some(leaving)),
+        None => (None, None),
+    };
+
+    // The primal ratio test is down the entering column and the dual
+    // ratio test is along the leaving row (where dz_N = -D_i)
+    let ratio = |value: &T, delta: T| match delta > T::epsilon() {
+        true => Some(value.clone() / delta),
+        false => None,
+    };
+    let mut row_ratios = vec![None; B.len()];
+    let mut column_ratios = vec![None; N.len()];
+    match phase {
+        Phase::Primal | Phase::Auxiliary => {
+            if let Some(k) =
+                entering.and_then(|e| N.iter().position(|j| *j == e))
+            {
+                for (i, b) in B.iter().enumerate() {
+                    row_ratios[i] = ratio(&state.x[*b], D[(i, k)].clone());
+                }
+            }
+        }
+        Phase::Dual => {
+            if let Some(i) =
+                leaving.and_then(|l| B.iter().position(|b| *b == l))
+            {
+                for (k, j) in N.iter().enumerate() {
+                    column_ratios[k] = ratio(&state.z[*j], -D[(i, k)].clone());
+                }
+            }
+        }
+    }
+
+    let name = |j: usize| {
+        let name = match format {
+            DictionaryFormat::Text => basis_variable_name(j, n),
+            DictionaryFormat::Latex => latex_name(&basis_variable_name(j, n)),
+        };
+        match (Some(j) == entering || Some(j) == leaving, format) {
+            (false, _) => name,
+            (true, DictionaryFormat::Text) => format!("[{}]", name),
+            (true, DictionaryFormat::Latex) => format!("\\boxed{{{}}}", name),
+        }
+    };
+    let number = |value: &T| {
+        let value = value.format(T::DEFAULT_FORMAT);
+        match format {
+            DictionaryFormat::Text => value,
+            DictionaryFormat::Latex => latex_number(&value),
+        }
+    };
+    let objective = state
+        .B
+        .iter()
+        .map(|j| state.c[*j].clone() * state.x[*j].clone())
+        .fold(T::zero(), |sum, v| sum + v);
+    let has_row_ratios = row_ratios.iter().any(Option::is_some);
+    let has_column_ratios = column_ratios.iter().any(Option::is_some);
+
+    let mut rows = Vec::new();
+    match layout {
+        DictionaryLayout::Dictionary => {
+            let term = |coefficient: T, j: usize| {
+                let sign = if coefficient < T::zero() { "-" } else { "+" };
+                format!("{} {} {}", sign, number(&coefficient.abs()), name(j))
+            };
+            let zeta = match format {
+                DictionaryFormat::Text => String::from("zeta"),
+                DictionaryFormat::Latex => String::from("\\zeta"),
+            };
+
+            // The objective is `zeta* - z_N x_N`
+            let mut cells = vec![zeta, String::from("="), number(&objective)];
+            cells.extend(N.iter().map(|j| term(-state.z[*j].clone(), *j)));
+            rows.push(Row::Cells(cells));
+            rows.push(Row::Separator);
+
+            // Each basic variable is `x_i* - D_i x_N`
+            for (i, b) in B.iter().enumerate() {
+                let mut cells =
+                    vec![name(*b), String::from("="), number(&state.x[*b])];
+                cells.extend(
+                    N.iter()
+                        .enumerate()
+                        .map(|(k, j)| term(-D[(i, k)].clone(), *j)),
+                );
+                if has_row_ratios {
+                    cells.push(
+                        row_ratios[i].as_ref().map(number).unwrap_or_default(),
+                    );
+                }
+                rows.push(Row::Cells(cells));
+            }
+
+            if has_column_ratios {
+                rows.push(Row::Separator);
+                let mut cells =
+                    vec![String::from("ratio"), String::new(), String::new()];
+                cells.extend(
+                    column_ratios
+                        .iter()
+                        .map(|r| r.as_ref().map(number).unwrap_or_default()),
+                );
+                rows.push(Row::Cells(cells));
+            }
+        }
+        DictionaryLayout::Tableau => {
+            let all = (0..state.x.len()).collect::<Vec<usize>>();
+
+            let mut cells = vec![String::new()];
+            cells.extend(all.iter().map(|j| name(*j)));
+            cells.push(String::from("rhs"));
+            if has_row_ratios {
+                cells.push(String::from("ratio"));
+            }
+            rows.push(Row::Cells(cells));
+            rows.push(Row::Separator);
+
+            // The basic columns of `A_B^-1 A` form an identity
+            for (i, b) in B.iter().enumerate() {
+                let mut cells = vec![name(*b)];
+                cells.extend(all.iter().map(|j| {
+                    match N.iter().position(|k| k == j) {
+                        Some(k) => number(&D[(i, k)]),
+                        None if j == b => number(&T::one()),
+                        None => number(&T::zero()),
+                    }
+                }));
+                cells.push(number(&state.x[*b]));
+                if has_row_ratios {
+                    cells.push(
+                        row_ratios[i].as_ref().map(number).unwrap_or_default(),
+                    );
+                }
+                rows.push(Row::Cells(cells));
+            }
+
+            rows.push(Row::Separator);
+            let mut cells = vec![String::from("z")];
+            cells.extend(all.iter().map(|j| match N.contains(j) {
+                true => number(&state.z[*j]),
+                false => number(&T::zero()),
+            }));
+            cells.push(number(&objective));
+            rows.push(Row::Cells(cells));
+
+            if has_column_ratios {
+                let mut cells = vec![String::from("ratio")];
+                cells.extend(all.iter().map(|j| {
+                    match N.iter().position(|k| k == j) {
+                        Some(k) => column_ratios[k]
+                            .as_ref()
+                            .map(number)
+                            .unwrap_or_default(),
+                        None => String::new(),
+                    }
+                }));
+                rows.push(Row::Cells(cells));
+            }
+        }
+    }
+
+    Some(match format {
+        DictionaryFormat::Text => text_grid(&rows),
+        DictionaryFormat::Latex => latex_grid(&rows),
+    })
+}
+
+/**
+ * Lay the rows out as text with right aligned columns
+ */
+fn text_grid(rows: &[Row]) -> String {
+    let mut widths = Vec::new();
+    for row in rows {
+        if let Row::Cells(cells) = row {
+            for (k, cell) in cells.iter().enumerate() {
+                if k == widths.len() {
+                    widths.push(0);
+                }
+                widths[k] = widths[k].max(cell.chars().count());
+            }
+        }
+    }
+
+    let total = widths.iter().sum::<usize>() + widths.len().saturating_sub(1);
+    rows.iter()
+        .map(|row| match row {
+            Row::Separator => "-".repeat(total),
+            Row::Cells(cells) => cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:>1$}", cell, width))
+                .collect::<Vec<String>>()
+                .join(" ")
+                .trim_end()
+                .to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/**
+ * Lay the rows out as a LaTeX `array`
+ */
+fn latex_grid(rows: &[Row]) -> String {
+    let columns = rows
+        .iter()
+        .map(|row| match row {
+            Row::Cells(cells) => cells.len(),
+            Row::Separator => 0,
+        })
+        .max()
+        .unwrap_or(0);
+
+    let mut lines =
+        vec![format!("\\begin{{array}}{{{}}}", "r".repeat(columns))];
+    for row in rows {
+        lines.push(match row {
+            Row::Separator => String::from("\\hline"),
+            Row::Cells(cells) => format!("{} \\\\", cells.join(" & ")),
+        });
+    }
+    lines.push(String::from("\\end{array}"));
+
+    format!("\\[\n{}\n\\]", lines.join("\n"))
+}
+
+/**
+ * A variable name such as `x12` as `x_{12}`
+ */
+fn latex_name(name: &str) -> String {
+    let (letter, index) = name.split_at(1);
+    format!("{}_{{{}}}", letter, index)
+}
+
+/**
+ * A number with fractions such as `-5/4` as `-\frac{5}{4}`
+ */
+fn latex_number(value: &str) -> String {
+    let (sign, magnitude) = match value.strip_prefix('-') {
+        Some(magnitude) => ("-", magnitude),
+        None => ("", value),
+    };
+
+    match magnitude.split_once('/') {
+        Some((p, q)) => format!("{}\\frac{{{}}}{{{}}}", sign, p, q),
+        None => value.to_string(),
+    }
+}
@@ -0,0 +1,402 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::output::{
+    cant_check, constraint_name, is_basic, row_activities, variable_name,
+    Precision, SolutionValues,
+};
+use crate::parse::ParsedLP;
+use crate::scalar::{NumberFormat, Scalar};
+use crate::solve::SolveResult;
+use std::fmt::Write;
+
+/**
+ * Headers of the row and column tables for linear problems. The dashes
+ * mark the width of each column, which is how the reader finds the
+ * values.
+ */
+const LP_ROWS_HEADER: &str = "   No.   Row name   St   Activity     Lower \
+                              bound   Upper bound    Marginal";
+const LP_COLUMNS_HEADER: &str = "   No. Column name  St   Activity     Lower \
+                                 bound   Upper bound    Marginal";
+const LP_DASHES: &str = "------ ------------ -- ------------- ------------- \
+                         ------------- -------------";
+
+/**
+ * Headers of the tables for integer problems, which don't have the
+ * status or marginal columns
+ */
+const MIP_ROWS_HEADER: &str = "   No.   Row name        Activity     Lower \
+                               bound   Upper bound";
+const MIP_COLUMNS_HEADER: &str = "   No. Column name       Activity     \
+                                  Lower bound   Upper bound";
+const MIP_DASHES: &str = "------ ------------    ------------- \
+                          ------------- -------------";
+
+/**
+ * Longest name that fits in the name column. Longer names are
+ * printed on a line of their own, as GLPK does.
+ */
+const NAME_WIDTH: usize = 12;
+
+/**
+ * Significant figures printed by GLPK (`%.6g`)
+ */
+const GLPK_SIG_FIGS: usize = 6;
+
+/**
+ * Write the results in the format of GLPK's printable solution, using
+ * the layout of `glp_print_mip` for problems with integer variables
+ * and `glp_print_sol` otherwise. Values are printed like GLPK's `%.6g`
+ * unless `precision` says otherwise.
+ */
+pub fn write<T: Scalar>(
+    lp: &ParsedLP<T>,
+    result: &SolveResult<T>,
+    precision: &Precision,
+) -> String {
+    let (A, b) = (&lp.A, &lp.b);
+    let m = A.nrows();
+    let n = A.ncols() - m;
+    let non_zeros = A.columns(0, n).iter().filter(|a| !a.is_zero()).count();
+    let mip = !lp.integer.is_empty();
+
+    let (solution, status) = match (result, mip) {
+        (SolveResult::Optimal(solution), false) => (Some(solution), "OPTIMAL"),
+        (SolveResult::Optimal(solution), true) => {
+            (Some(solution), "INTEGER OPTIMAL")
+        }
+        (SolveResult::Feasible(solution, _), _) => {
+            (Some(solution), "INTEGER NON-OPTIMAL")
+        }
+        (SolveResult::Infeasible, false) => (None, "INFEASIBLE (FINAL)"),
+        (SolveResult::Infeasible, true) => (None, "INTEGER EMPTY"),
+        (SolveResult::Unbounded, _) => (None, "UNBOUNDED"),
+        (SolveResult::Stopped, _) => (None, "UNDEFINED"),
+    };
+
+    let mut out = String::new();
+    writeln!(out, "Problem:    ").unwrap();
+    writeln!(out, "Rows:       {}", m).unwrap();
+    match mip {
+        true => writeln!(
+            out,
+            "Columns:    {} ({} integer, 0 binary)",
+            n,
+            lp.integer.len()
+        ),
+        false => writeln!(out, "Columns:    {}", n),
+    }
+    .unwrap();
+    writeln!(out, "Non-zeros:  {}", non_zeros).unwrap();
+    writeln!(out, "Status:     {}", status).unwrap();
+
+    let solution = match solution {
+        Some(solution) => solution,
+        None => {
+            writeln!(out, "\nEnd of output").unwrap();
+            return out;
+        }
+    };
+
+    writeln!(
+        out,
+        "Objective:  obj = {} (MAXimum)\n",
+        number(&solution.objective_value, precision.objective)
+    )
+    .unwrap();
+
+    let (rows_header, columns_header, dashes) = match mip {
+        true => (MIP_ROWS_HEADER, MIP_COLUMNS_HEADER, MIP_DASHES),
+        false => (LP_ROWS_HEADER, LP_COLUMNS_HEADER, LP_DASHES),
+    };
+
+    // The marginal is left blank for basic variables, as in GLPK, and
+    // left out for integer solutions, which have no duals
+    let marginal = |basic: bool, value: Option<T>| match (mip, value) {
+        (true, _) | (_, None) => None,
+        (false, Some(value)) if basic && value.is_zero() => Some(String::new()),
+        (false, Some(value)) => Some(number(&value, precision.duals)),
+    };
+
+    writeln!(out, "{}\n{}", rows_header, dashes).unwrap();
+    let activities = row_activities(A, solution);
+    for (i, activity) in activities.iter().enumerate() {
+        let slack = b[i].clone() - activity.clone();
+        let basic = is_basic(solution, n + i, &slack);
+        let status = match (mip, basic) {
+            (true, _) => "",
+            (false, true) => "B",
+            (false, false) => "NU",
+        };
+
+        write_entry(
+            &mut out,
+            i,
+            &constraint_name(i),
+            status,
+            &[
+                Some(number(activity, precision.variables)),
+                Some(String::new()),
+                Some(number(&b[i], precision.variables)),
+                marginal(basic, solution.dual_values.get(i).cloned()),
+            ],
+        );
+    }
+
+    writeln!(out, "\n{}\n{}", columns_header, dashes).unwrap();
+    for (j, value) in solution.variable_values.iter().enumerate() {
+        let basic = is_basic(solution, j, value);
+        let status = match (mip, basic) {
+            (true, _) if lp.integer.contains(&j) => "*",
+            (true, _) => "",
+            (false, true) => "B",
+            (false, false) => "NL",
+        };
+
+        write_entry(
+            &mut out,
+            j,
+            &variable_name(j),
+            status,
+            &[
+                Some(number(value, precision.variables)),
+                Some(number(&T::zero(), precision.variables)),
+                Some(String::new()),
+                marginal(
+                    basic,
+                    solution.reduced_costs.get(j).map(|r| -r.clone()),
+                ),
+            ],
+        );
+    }
+
+    writeln!(out, "\nEnd of output").unwrap();
+    out
+}
+
+/**
+ * Write one line of the row or column table. The values are the
+ * activity, the lower and upper bounds and the marginal, which is
+ * `None` for integer problems.
+ */
+fn write_entry(
+    out: &mut String,
+    k: usize,
+    name: &str,
+    status: &str,
+    values: &[Option<String>],
+) {
+    write!(out, "{:>6} ", k + 1).unwrap();
+    match name.len() > NAME_WIDTH {
+        true => write!(out, "{}\n{:20}", name, "").unwrap(),
+        false => write!(out, "{:<12} ", name).unwrap(),
+    }
+    write!(out, "{:<2}", status).unwrap();
+    for value in values.iter().flatten() {
+        write!(out, " {:>13}", value).unwrap();
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+}
+
+/**
+ * Format a value in the given format, or like `%.6g` by default
+ */
+fn number<T: Scalar>(value: &T, format: Option<NumberFormat>) -> String {
+    if let Some(format) = format {
+        return value.format(format);
+    }
+
+    let value = value.to_f64();
+    if value == 0.0 {
+        return String::from("0");
+    }
+
+    // `%g` switches to scientific notation for large and small exponents
+    let scientific = format!("{:.*e}", GLPK_SIG_FIGS - 1, value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent = exponent.parse::<i32>().unwrap();
+    let trim = |s: &str| {
+        match s.contains('.') {
+            true => s.trim_end_matches('0').trim_end_matches('.'),
+            false => s,
+        }
+        .to_string()
+    };
+
+    if exponent < -4 || exponent >= GLPK_SIG_FIGS as i32 {
+        format!("{}e{}", trim(mantissa), exponent)
+    } else {
+        let decimals = (GLPK_SIG_FIGS as i32 - 1 - exponent) as usize;
+        trim(&format!("{:.*}", decimals, value))
+    }
+}
+
+/**
+ * Read the activities of the columns and the marginals of the rows from
+ * a GLPK printable solution. Solutions of integer problems don't have
+ * marginals, so there are no dual values in that case.
+ */
+pub fn read<T: Scalar>(
+    contents: &str,
+    n: usize,
+    m: usize,
+) -> Result<SolutionValues<T>, String> {
+    let status = contents
+        .lines()
+        .find_map(|l| l.strip_prefix("Status:"))
+        .map(str::trim)
+        .ok_or_else(|| String::from("GLPK solution has no status"))?;
+
+    if ![
+        "OPTIMAL",
+        "FEASIBLE",
+        "INTEGER OPTIMAL",
+        "INTEGER NON-OPTIMAL",
+    ]
+    .contains(&status)
+    {
+        return Err(cant_check(status));
+    }
+
+    let columns = read_table(contents, "Column name", n)?
+        .ok_or_else(|| String::from("GLPK solution has no column table"))?;
+    let rows = read_table(contents, "Row name", m)?;
+
+    let x = columns.into_iter().map(|(activity, _)| activity).collect();
+    let y = rows.and_then(|rows| {
+        rows.into_iter().map(|(_, marginal)| marginal).collect()
+    });
+
+    Ok(SolutionValues { x, y })
+}
+
+/**
+ * Read the activity and marginal of each entry of the table whose
+ * header contains `name_title`, ordered by number. The columns are
+ * found from the line of dashes under the header. A number wider than
+ * its column pushes the rest of the line to the right, which is tracked
+ * with `shift`.
+ */
+#[allow(clippy::type_complexity)]
+fn read_table<T: Scalar>(
+    contents: &str,
+    name_title: &str,
+    len: usize,
+) -> Result<Option<Vec<(T, Option<T>)>>, String> {
+    let mut lines = contents.lines();
+    let header = match lines.find(|l| l.contains(name_title)) {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+
+    let malformed = || format!("GLPK {} table is malformed", name_title);
+    let spans = word_spans(lines.next().unwrap_or(""));
+    let titles: Vec<String> = spans
+        .iter()
+        .map(|&(start, end)| {
+            let end = end.min(header.len());
+            header.get(start.min(end)..end).unwrap_or("").to_string()
+        })
+        .collect();
+
+    let number_col = *spans.first().ok_or_else(malformed)?;
+    let values_start = titles
+        .iter()
+        .position(|t| t.contains("Activity"))
+        .ok_or_else(malformed)?;
+    let marginal = titles.iter().position(|t| t.contains("Marginal"));
+    let value_spans = &spans[values_start..];
+    let start = value_spans[0].0;
+
+    let mut entries: Vec<Option<(T, Option<T>)>> = vec![None; len];
+    let mut lines = lines.take_while(|l| !l.trim().is_empty());
+    while let Some(line) = lines.next() {
+        // Long names are on a line of their own, with the values on
+        // the next line
+        let values = match word_spans(line).len() <= 2 {
+            true => lines.next().unwrap_or(""),
+            false => line,
+        };
+
+        let k = line
+            .get(number_col.0..number_col.1.min(line.len()))
+            .and_then(|s| s.trim().parse::<usize>().ok())
+            .filter(|k| *k >= 1 && *k <= len)
+            .ok_or_else(|| format!("Invalid GLPK table entry '{}'", line))?;
+
+        // GLPK prints tiny marginals as "< eps"
+        let values = values.replace("< eps", "    0");
+        let mut cells = vec![None; value_spans.len()];
+        let mut col = 0;
+        let mut shift = 0;
+        for (token_start, token_end) in word_spans(&values) {
+            if token_start < start {
+                continue;
+            }
+
+            // Skip over the blank columns that end before the token
+            while value_spans
+                .get(col)
+                .is_some_and(|(_, end)| token_start >= end + shift)
+            {
+                col += 1;
+            }
+
+            let (_, span_end) = *value_spans.get(col).ok_or_else(malformed)?;
+            shift += token_end.saturating_sub(span_end + shift);
+            cells[col] = Some(&values[token_start..token_end]);
+            col += 1;
+        }
+
+        let activity = T::parse(cells[0].ok_or_else(malformed)?)?;
+        let marginal = match marginal.map(|col| cells[col - values_start]) {
+            None => None,
+            Some(None) => Some(T::zero()),
+            Some(Some(s)) => Some(T::parse(s)?),
+        };
+
+        entries[k - 1] = Some((activity, marginal));
+    }
+
+    entries
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+        .map(Some)
+        .ok_or_else(|| format!("GLPK {} table is missing entries", name_title))
+}
+
+/**
+ * The start and end of each run of non-whitespace characters
+ */
+fn word_spans(line: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+
+    for (k, ch) in line.char_indices().chain(Some((line.len(), ' '))) {
+        match (ch.is_whitespace(), start) {
+            (false, None) => start = Some(k),
+            (true, Some(s)) => {
+                spans.push((s, k));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    spans
+}
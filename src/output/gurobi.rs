@@ -0,0 +1,112 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::output::{
+    name_index, solution_number, variable_name, Precision, SolutionValues,
+};
+use crate::scalar::Scalar;
+use crate::solve::SolveResult;
+use std::fmt::Write;
+
+/**
+ * Write the results in the format of a Gurobi `.sol` file, which only
+ * has the variable values. Gurobi doesn't write a file when there is
+ * no solution, so in that case only the comment lines are written.
+ */
+pub fn write<T: Scalar>(
+    result: &SolveResult<T>,
+    precision: &Precision,
+) -> String {
+    let mut out = String::from("# Solution for model bblp\n");
+
+    let solution = match result {
+        SolveResult::Optimal(solution) | SolveResult::Feasible(solution, _) => {
+            solution
+        }
+        _ => {
+            writeln!(out, "# Status = {}", result.status()).unwrap();
+            return out;
+        }
+    };
+
+    writeln!(
+        out,
+        "# Objective value = {}",
+        solution_number(&solution.objective_value, precision.objective)
+    )
+    .unwrap();
+    for (j, value) in solution.variable_values.iter().enumerate() {
+        writeln!(
+            out,
+            "{} {}",
+            variable_name(j),
+            solution_number(value, precision.variables)
+        )
+        .unwrap();
+    }
+
+    out
+}
+
+/**
+ * Read the variable values from a Gurobi `.sol` file. The values are
+ * placed by their `x1, x2, ...` names if every name has that form,
+ * otherwise they're taken in the order they appear.
+ */
+pub fn read<T: Scalar>(
+    contents: &str,
+    n: usize,
+) -> Result<SolutionValues<T>, String> {
+    let entries = contents
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(
+            |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [name, value] => Ok((name, T::parse(value)?)),
+                _ => Err(format!("Invalid Gurobi solution line '{}'", line)),
+            },
+        )
+        .collect::<Result<Vec<(&str, T)>, String>>()?;
+
+    if entries.is_empty() {
+        return Err(String::from("Solution file has no variable values"));
+    }
+
+    let indices = entries
+        .iter()
+        .map(|(name, _)| name_index(name, 'x'))
+        .collect::<Option<Vec<usize>>>();
+
+    let x = match indices {
+        Some(indices) => {
+            let mut x: Vec<Option<T>> = vec![None; n];
+            for (j, (_, value)) in indices.into_iter().zip(entries) {
+                match x.get_mut(j) {
+                    Some(slot) => *slot = Some(value),
+                    None => return Err(format!("Unknown variable x{}", j + 1)),
+                }
+            }
+
+            x.into_iter().collect::<Option<Vec<T>>>().ok_or_else(|| {
+                String::from("Gurobi solution is missing variable values")
+            })?
+        }
+        None => entries.into_iter().map(|(_, value)| value).collect(),
+    };
+
+    Ok(SolutionValues { x, y: None })
+}
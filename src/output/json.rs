@@ -0,0 +1,164 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::output::{
+    basis_variable_name, constraint_name, variable_name, Precision,
+};
+use crate::scalar::{NumberFormat, Scalar};
+use crate::solve::{Solution, SolveResult};
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+
+/**
+ * The JSON document printed by `--output json`. The solution fields
+ * are left out when the problem is infeasible or unbounded.
+ */
+#[derive(Serialize)]
+struct JsonResult {
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gap: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    objective: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    variables: Option<Vec<JsonVariable>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    constraints: Option<Vec<JsonConstraint>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pivots: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    basis: Option<Vec<String>>,
+    time: f64,
+}
+
+#[derive(Serialize)]
+struct JsonVariable {
+    name: String,
+    value: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reduced_cost: Option<Value>,
+}
+
+#[derive(Serialize)]
+struct JsonConstraint {
+    name: String,
+    dual: Value,
+}
+
+/**
+ * Format the results as JSON. The variables are named `x1, x2, ...`
+ * and the constraints `c1, c2, ...` in the order they appear in the
+ * input, and the slack variable of constraint `ci` is named `wi` in
+ * the basis. Integer solutions have no duals or reduced costs, so
+ * those are left out. `elapsed` is reported in seconds.
+ */
+pub fn json<T: Scalar>(
+    result: &SolveResult<T>,
+    precision: &Precision,
+    elapsed: Duration,
+) -> String {
+    let (solution, gap) = match result {
+        SolveResult::Optimal(solution) => (Some(solution), None),
+        SolveResult::Feasible(solution, gap) => (Some(solution), Some(*gap)),
+        _ => (None, None),
+    };
+
+    let document = JsonResult {
+        status: result.status(),
+        gap,
+        objective: solution
+            .map(|s| json_number(&s.objective_value, precision.objective)),
+        variables: solution.map(|s| variables(s, precision)),
+        constraints: solution
+            .filter(|s| !s.dual_values.is_empty())
+            .map(|s| constraints(s, precision)),
+        pivots: solution.map(|s| s.pivots),
+        basis: solution.map(basis),
+        time: elapsed.as_secs_f64(),
+    };
+
+    serde_json::to_string_pretty(&document)
+        .expect("Solutions can always be serialized")
+}
+
+/**
+ * Convert a value to JSON in the given format. Exact fractions are
+ * kept as strings, everything else is written as a number with the
+ * digits that the format would print.
+ */
+fn json_number<T: Scalar>(value: &T, format: Option<NumberFormat>) -> Value {
+    let format = match format {
+        Some(format) => format,
+        None => {
+            return serde_json::to_value(value)
+                .expect("Numbers can always be serialized")
+        }
+    };
+
+    let formatted = value.format(format);
+    match format {
+        NumberFormat::Exact if T::EXACT => Value::String(formatted),
+        _ => formatted
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map_or(Value::Null, Value::Number),
+    }
+}
+
+fn variables<T: Scalar>(
+    solution: &Solution<T>,
+    precision: &Precision,
+) -> Vec<JsonVariable> {
+    solution
+        .variable_values
+        .iter()
+        .enumerate()
+        .map(|(j, value)| JsonVariable {
+            name: variable_name(j),
+            value: json_number(value, precision.variables),
+            reduced_cost: solution
+                .reduced_costs
+                .get(j)
+                .map(|z| json_number(z, precision.duals)),
+        })
+        .collect()
+}
+
+fn constraints<T: Scalar>(
+    solution: &Solution<T>,
+    precision: &Precision,
+) -> Vec<JsonConstraint> {
+    solution
+        .dual_values
+        .iter()
+        .enumerate()
+        .map(|(i, dual)| JsonConstraint {
+            name: constraint_name(i),
+            dual: json_number(dual, precision.duals),
+        })
+        .collect()
+}
+
+fn basis<T>(solution: &Solution<T>) -> Vec<String> {
+    let n = solution.variable_values.len();
+    solution
+        .B
+        .iter()
+        .map(|&j| basis_variable_name(j, n))
+        .collect()
+}
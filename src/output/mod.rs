@@ -0,0 +1,426 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::parse::ParsedLP;
+use crate::scalar::{NumberFormat, Scalar};
+use crate::solve::{Solution, SolveResult};
+use crate::util::mul;
+use crate::{Matrix, Vector};
+use std::str::FromStr;
+
+mod basis;
+mod cplex;
+mod dictionary;
+mod glpk;
+mod gurobi;
+mod json;
+mod plot;
+
+pub(crate) use dictionary::dictionary;
+pub use dictionary::{DictionaryFormat, DictionaryLayout};
+pub use json::*;
+pub use plot::*;
+
+/**
+ * The formats the results can be printed in
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "Unknown output format '{}' (expected text or json)",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * The number format for each field of the results. Fields without a
+ * format use the default for the number type in the text output, and
+ * are printed at full precision in the JSON output and solution files.
+ * `duals` covers both the dual values and the reduced costs.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Precision {
+    pub objective: Option<NumberFormat>,
+    pub variables: Option<NumberFormat>,
+    pub duals: Option<NumberFormat>,
+}
+
+/**
+ * Parses a comma separated list of `[field=]format`, where the field is
+ * `objective`, `variables` or `duals` and a format without a field
+ * applies to all of them, e.g. `12` or `full,variables=fixed:4`.
+ */
+impl FromStr for Precision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut precision = Precision::default();
+
+        for item in s.split(',') {
+            match item.split_once('=') {
+                None => {
+                    let format = Some(item.parse()?);
+                    precision = Precision {
+                        objective: format,
+                        variables: format,
+                        duals: format,
+                    };
+                }
+                Some(("objective", f)) => {
+                    precision.objective = Some(f.parse()?)
+                }
+                Some(("variables", f)) => {
+                    precision.variables = Some(f.parse()?)
+                }
+                Some(("duals", f)) => precision.duals = Some(f.parse()?),
+                Some((field, _)) => {
+                    return Err(format!(
+                        "Unknown precision field '{}' (expected objective, \
+                         variables or duals)",
+                        field
+                    ))
+                }
+            }
+        }
+
+        Ok(precision)
+    }
+}
+
+/**
+ * Format the results in the plain text format: the status, objective
+ * value and variable values on separate lines. With `debug` the number
+ * of pivots and the basis condition estimate are printed first.
+ */
+pub fn text<T: Scalar>(
+    result: &SolveResult<T>,
+    precision: &Precision,
+    debug: bool,
+) -> String {
+    let (solution, status) = match result {
+        SolveResult::Optimal(solution) => (solution, String::from("optimal")),
+        SolveResult::Feasible(solution, gap) => {
+            (solution, format!("feasible (gap {:.2}%)", gap))
+        }
+        _ => return String::from(result.status()),
+    };
+
+    let header = match (debug, solution.condition) {
+        (false, _) => String::new(),
+        (true, Some(condition)) => format!(
+            "{} pivots (basis condition estimate {:.1e})\n",
+            solution.pivots, condition
+        ),
+        (true, None) => format!("{} pivots\n", solution.pivots),
+    };
+
+    let objective_format = precision.objective.unwrap_or(T::DEFAULT_FORMAT);
+    let variables_format = precision.variables.unwrap_or(T::DEFAULT_FORMAT);
+    let x_vals = solution
+        .variable_values
+        .iter()
+        .map(|v| v.format(variables_format))
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    format!(
+        "{}{}\n{}\n{}",
+        header,
+        status,
+        solution.objective_value.format(objective_format),
+        x_vals
+    )
+}
+
+/**
+ * The solution file formats of other solvers that the results can be
+ * written in with `--solution-format`
+ *
+ * - `Glpk`: GLPK's printable solution (`glpsol -o`)
+ * - `Cplex`: CPLEX's XML `.sol` file
+ * - `Gurobi`: Gurobi's `.sol` text file, with only the variable values
+ * - `MpsBasis`: the optimal basis as an MPS basis (`.bas`) file
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SolutionFormat {
+    Glpk,
+    Cplex,
+    Gurobi,
+    MpsBasis,
+}
+
+impl FromStr for SolutionFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "glpk" => Ok(SolutionFormat::Glpk),
+            "cplex" => Ok(SolutionFormat::Cplex),
+            "gurobi" => Ok(SolutionFormat::Gurobi),
+            "mps-basis" => Ok(SolutionFormat::MpsBasis),
+            _ => Err(format!(
+                "Unknown solution format '{}' (expected glpk, cplex, gurobi \
+                 or mps-basis)",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * The values read back from a solution file. `y` is `None` if the file
+ * doesn't have any dual values.
+ */
+pub struct SolutionValues<T = f64> {
+    pub x: Vec<T>,
+    pub y: Option<Vec<T>>,
+}
+
+/**
+ * Write the results for the problem in the format of another solver.
+ * Problems with integer variables are written the way the other solver
+ * writes integer solutions, without dual values. Numbers are written at
+ * full precision unless `precision` says otherwise, except in the GLPK
+ * format which rounds them the way GLPK does.
+ */
+pub fn write_solution<T: Scalar>(
+    format: SolutionFormat,
+    lp: &ParsedLP<T>,
+    result: &SolveResult<T>,
+    precision: &Precision,
+) -> Result<String, String> {
+    match format {
+        SolutionFormat::Glpk => Ok(glpk::write(lp, result, precision)),
+        SolutionFormat::Cplex => Ok(cplex::write(lp, result, precision)),
+        SolutionFormat::Gurobi => Ok(gurobi::write(result, precision)),
+        SolutionFormat::MpsBasis => basis::write(lp, result),
+    }
+}
+
+/**
+ * Read the values from a solution file for a problem with `n` variables
+ * and `m` constraints. The format is detected from the contents, and can
+ * be the results printed by `bblp` or any of the `SolutionFormat`s
+ * other than `MpsBasis`. Only optimal and feasible solutions can be read.
+ */
+pub fn read_solution<T: Scalar>(
+    contents: &str,
+    n: usize,
+    m: usize,
+) -> Result<SolutionValues<T>, String> {
+    let first = contents.trim_start();
+    let first_word = first.split_whitespace().next();
+
+    let values = if first.starts_with("<?xml") || first.starts_with("<CPLEX") {
+        cplex::read(contents, n, m)?
+    } else if first.starts_with("Problem:") {
+        glpk::read(contents, n, m)?
+    } else if is_basis_file(contents) {
+        return Err(String::from(
+            "The solution is an mps-basis file, which only has the final \
+             basis and no values to check. Write the solution with \
+             --solution-format glpk, cplex or gurobi instead",
+        ));
+    } else if first.starts_with('#') || first_word.is_some_and(is_name) {
+        gurobi::read(contents, n)?
+    } else {
+        read_text(contents, n, m)?
+    };
+
+    if values.x.len() != n {
+        return Err(format!(
+            "Expected {} variable values but found {}",
+            n,
+            values.x.len()
+        ));
+    }
+
+    match &values.y {
+        Some(y) if y.len() != m => {
+            Err(format!("Expected {} dual values but found {}", m, y.len()))
+        }
+        _ => Ok(values),
+    }
+}
+
+/**
+ * Whether `contents` is a solution written with
+ * `--solution-format mps-basis`, which only has the final basis
+ */
+pub fn is_basis_file(contents: &str) -> bool {
+    contents.split_whitespace().next() == Some("NAME")
+}
+
+/**
+ * Read the results in the plain text format: the status, the objective
+ * value, the variable values and then optionally the dual values on a
+ * 4th line
+ */
+fn read_text<T: Scalar>(
+    contents: &str,
+    n: usize,
+    m: usize,
+) -> Result<SolutionValues<T>, String> {
+    let mut lines = contents.lines().filter(|l| !l.trim().is_empty());
+
+    match lines.next().and_then(|l| l.split_whitespace().next()) {
+        Some("optimal") | Some("feasible") => {}
+        Some(status) => return Err(cant_check(status)),
+        None => return Err(String::from("Solution file is empty")),
+    }
+
+    // The objective value isn't needed, it's recomputed from the values
+    lines.next();
+
+    let mut read_values = |len: usize, name: &str| {
+        let values = match lines.next() {
+            Some(line) => line
+                .split_whitespace()
+                .map(T::parse)
+                .collect::<Result<Vec<T>, String>>()?,
+            None => return Ok(None),
+        };
+
+        if values.len() != len {
+            return Err(format!(
+                "Expected {} {} values but found {}",
+                len,
+                name,
+                values.len()
+            ));
+        }
+
+        Ok(Some(values))
+    };
+
+    let x = read_values(n, "variable")?
+        .ok_or_else(|| String::from("Solution file has no variable values"))?;
+    let y = read_values(m, "dual")?;
+
+    Ok(SolutionValues { x, y })
+}
+
+/**
+ * The name of variable `j` (0-indexed) in the output
+ */
+pub(crate) fn variable_name(j: usize) -> String {
+    format!("x{}", j + 1)
+}
+
+/**
+ * The name of column `j` (0-indexed) of the problem in standard form,
+ * where the slack of constraint `i` is named `w{i+1}`
+ */
+pub(crate) fn basis_variable_name(j: usize, n: usize) -> String {
+    match j < n {
+        true => variable_name(j),
+        false => format!("w{}", j - n + 1),
+    }
+}
+
+/**
+ * The column named `name` by `basis_variable_name` in a problem with
+ * `n` variables and `m` constraints
+ */
+pub(crate) fn basis_variable_index(
+    name: &str,
+    n: usize,
+    m: usize,
+) -> Option<usize> {
+    match name_index(name, 'x') {
+        Some(j) if j < n => Some(j),
+        Some(_) => None,
+        None => name_index(name, 'w').filter(|i| *i < m).map(|i| n + i),
+    }
+}
+
+/**
+ * The name of constraint `i` (0-indexed) in the output
+ */
+pub(crate) fn constraint_name(i: usize) -> String {
+    format!("c{}", i + 1)
+}
+
+/**
+ * The 0-indexed number of a variable or constraint named by
+ * `variable_name` or `constraint_name`
+ */
+fn name_index(name: &str, prefix: char) -> Option<usize> {
+    name.strip_prefix(prefix)?
+        .parse::<usize>()
+        .ok()
+        .filter(|k| *k >= 1)
+        .map(|k| k - 1)
+}
+
+/**
+ * Whether a word is a name rather than a number or a `bblp` status
+ */
+fn is_name(word: &str) -> bool {
+    !["optimal", "feasible", "infeasible", "unbounded"].contains(&word)
+        && word.parse::<f64>().is_err()
+}
+
+fn cant_check(status: &str) -> String {
+    format!("Can't check a solution with status {}", status)
+}
+
+/**
+ * Format a number for a solution file, at full precision by default
+ */
+fn solution_number<T: Scalar>(
+    value: &T,
+    format: Option<NumberFormat>,
+) -> String {
+    value.format(format.unwrap_or(NumberFormat::RoundTrip))
+}
+
+/**
+ * The values `A0 x` of the left hand side of each constraint
+ */
+fn row_activities<T: Scalar>(
+    A: &Matrix<T>,
+    solution: &Solution<T>,
+) -> Vector<T> {
+    let n = solution.variable_values.len();
+    let x = Vector::from_iterator(n, solution.variable_values.iter().cloned());
+    mul(&A.columns(0, n).clone_owned(), &x)
+}
+
+/**
+ * Whether variable `j` (including slacks) is basic. Interior point
+ * solutions don't have a basis, so the variables which are away from
+ * zero are reported as basic instead.
+ */
+fn is_basic<T: Scalar>(solution: &Solution<T>, j: usize, value: &T) -> bool {
+    match solution.B.is_empty() {
+        true => value.abs() > T::epsilon(),
+        false => solution.B.contains(&j),
+    }
+}
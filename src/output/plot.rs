@@ -0,0 +1,620 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::output::constraint_name;
+use crate::parse::ParsedLP;
+use crate::solve::{Phase, SolveResult};
+use crate::util::col_view;
+use crate::Matrix;
+use std::fmt::Write;
+
+/**
+ * Size of the SVG image, and the margin around the plot inside it
+ */
+const SIZE: f64 = 640.0;
+const MARGIN: f64 = 50.0;
+
+/**
+ * Two points within this distance are the same vertex
+ */
+const VERTEX_TOLERANCE: f64 = 1e-7;
+
+/**
+ * A half-space `a^T x <= b`. `constraint` is the row of the problem
+ * it comes from, or `None` for the bounds.
+ */
+struct HalfSpace {
+    a: Vec<f64>,
+    b: f64,
+    constraint: Option<usize>,
+}
+
+/**
+ * A vertex of a polytope along with the half-spaces which are tight
+ * at it
+ */
+struct Vertex {
+    x: Vec<f64>,
+    tight: Vec<usize>,
+}
+
+/**
+ * The bases visited by the simplex method, starting from the slack
+ * basis, given the phases and bases passed to the pivot callback.
+ * The auxiliary problem has `b = 0`, so only its final basis is kept
+ * since that is where the dual simplex starts from.
+ */
+pub fn visited_bases(
+    pivots: &[(Phase, Vec<usize>)],
+    result: &SolveResult,
+    n: usize,
+    m: usize,
+) -> Vec<Vec<usize>> {
+    let mut bases = vec![(n..n + m).collect::<Vec<usize>>()];
+
+    for (k, (phase, B)) in pivots.iter().enumerate() {
+        let last_auxiliary = *phase == Phase::Auxiliary
+            && pivots.get(k + 1).is_none_or(|p| p.0 != Phase::Auxiliary);
+        if *phase != Phase::Auxiliary || last_auxiliary {
+            bases.push(B.clone());
+        }
+    }
+
+    if let SolveResult::Optimal(solution) = result {
+        if bases.last() != Some(&solution.B) {
+            bases.push(solution.B.clone());
+        }
+    }
+
+    bases
+}
+
+/**
+ * Check that the problem has 2 or 3 variables, so it can be plotted
+ */
+pub fn plottable<T>(lp: &ParsedLP<T>) -> Result<(), String> {
+    match lp.n {
+        2 | 3 => Ok(()),
+        d => Err(format!(
+            "Only problems with 2 or 3 variables can be plotted, not {}",
+            d
+        )),
+    }
+}
+
+/**
+ * Draw the problem as an SVG image, along with the path through the
+ * vertices of the `bases` visited by the simplex method. Problems with
+ * two variables show the constraint half-planes, the feasible polygon
+ * and the objective direction. Problems with three variables show an
+ * isometric projection of the feasible polytope instead.
+ */
+pub fn plot(lp: &ParsedLP, bases: &[Vec<usize>]) -> Result<String, String> {
+    plottable(lp)?;
+    let d = lp.n;
+
+    let path = bases
+        .iter()
+        .map(|B| {
+            col_view(&lp.A, B)
+                .lu()
+                .solve(&lp.b)
+                .map(|x_B| basic_point(B, x_B.as_slice(), d))
+                .ok_or_else(|| String::from("A basis on the path is singular"))
+        })
+        .collect::<Result<Vec<Vec<f64>>, String>>()?;
+    let path = dedup(path);
+
+    // The box the plot covers holds every vertex of the problem and
+    // every point of the path, and cuts off unbounded regions
+    let mut constraints = constraint_half_spaces(&lp.A, lp.b.as_slice(), d);
+    constraints
+        .extend(bound_half_spaces(&vec![0.0; d], &vec![f64::INFINITY; d]));
+    let vertices = vertices(&constraints, d);
+
+    let mut lo = vec![0.0; d];
+    let mut hi = vec![1.0; d];
+    for x in vertices.iter().map(|v| &v.x).chain(path.iter()) {
+        for j in 0..d {
+            lo[j] = f64::min(lo[j], x[j]);
+            hi[j] = f64::max(hi[j], x[j]);
+        }
+    }
+    for j in 0..d {
+        let pad = 0.2 * (hi[j] - lo[j]);
+        hi[j] += pad;
+        if lo[j] < 0.0 {
+            lo[j] -= pad;
+        }
+    }
+
+    let c = lp.c.rows(0, d).iter().copied().collect::<Vec<f64>>();
+    let svg = match d {
+        2 => plot_2d(lp, &lo, &hi, &c, &path),
+        _ => plot_3d(lp, &lo, &hi, &c, &path),
+    };
+
+    Ok(svg)
+}
+
+/**
+ * The values of the first `d` variables at a basic solution
+ */
+fn basic_point(B: &[usize], x_B: &[f64], d: usize) -> Vec<f64> {
+    let mut x = vec![0.0; d];
+    for (i, j) in B.iter().enumerate() {
+        if *j < d {
+            x[*j] = x_B[i];
+        }
+    }
+    x
+}
+
+/**
+ * Remove repeated points from the path, which come from degenerate
+ * pivots
+ */
+fn dedup(path: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+    let mut deduped: Vec<Vec<f64>> = Vec::new();
+    for x in path {
+        if deduped.last().is_none_or(|last| distance(last, &x) > 1e-9) {
+            deduped.push(x);
+        }
+    }
+    deduped
+}
+
+fn distance(x: &[f64], y: &[f64]) -> f64 {
+    x.iter()
+        .zip(y)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+/**
+ * The constraints `A0 x <= b` of the problem as half-spaces
+ */
+fn constraint_half_spaces(A: &Matrix, b: &[f64], d: usize) -> Vec<HalfSpace> {
+    (0..A.nrows())
+        .map(|i| HalfSpace {
+            a: (0..d).map(|j| A[(i, j)]).collect(),
+            b: b[i],
+            constraint: Some(i),
+        })
+        .collect()
+}
+
+/**
+ * The bounds `lo <= x <= hi` as half-spaces, leaving out infinite ones
+ */
+fn bound_half_spaces(lo: &[f64], hi: &[f64]) -> Vec<HalfSpace> {
+    let d = lo.len();
+    let unit = |j: usize, sign: f64| {
+        (0..d).map(|k| if k == j { sign } else { 0.0 }).collect()
+    };
+
+    let mut half_spaces = Vec::new();
+    for j in 0..d {
+        half_spaces.push(HalfSpace {
+            a: unit(j, -1.0),
+            b: -lo[j],
+            constraint: None,
+        });
+        if hi[j].is_finite() {
+            half_spaces.push(HalfSpace {
+                a: unit(j, 1.0),
+                b: hi[j],
+                constraint: None,
+            });
+        }
+    }
+    half_spaces
+}
+
+/**
+ * The vertices of the polytope cut out by the half-spaces, found by
+ * intersecting every `d` of their boundaries
+ */
+fn vertices(half_spaces: &[HalfSpace], d: usize) -> Vec<Vertex> {
+    let mut vertices: Vec<Vertex> = Vec::new();
+
+    for subset in subsets(half_spaces.len(), d) {
+        let M = Matrix::from_fn(d, d, |r, k| half_spaces[subset[r]].a[k]);
+        let rhs = crate::Vector::from_iterator(
+            d,
+            subset.iter().map(|h| half_spaces[*h].b),
+        );
+        let x = match M.lu().solve(&rhs) {
+            Some(x) if x.iter().all(|v| v.is_finite()) => {
+                x.iter().copied().collect::<Vec<f64>>()
+            }
+            _ => continue,
+        };
+
+        let slack = |h: &HalfSpace| {
+            h.b - h.a.iter().zip(&x).map(|(a, x)| a * x).sum::<f64>()
+        };
+        let tolerance = |h: &HalfSpace| VERTEX_TOLERANCE * (1.0 + h.b.abs());
+        if half_spaces.iter().any(|h| slack(h) < -tolerance(h)) {
+            continue;
+        }
+        if vertices
+            .iter()
+            .any(|v| distance(&v.x, &x) < VERTEX_TOLERANCE)
+        {
+            continue;
+        }
+
+        let tight = (0..half_spaces.len())
+            .filter(|h| {
+                slack(&half_spaces[*h]).abs() <= tolerance(&half_spaces[*h])
+            })
+            .collect();
+        vertices.push(Vertex { x, tight });
+    }
+
+    vertices
+}
+
+/**
+ * Every subset of `k` of the numbers `0..n`, in increasing order
+ */
+fn subsets(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut subsets = Vec::new();
+    let mut subset: Vec<usize> = (0..k).collect();
+    if k > n {
+        return subsets;
+    }
+
+    loop {
+        subsets.push(subset.clone());
+
+        // Advance the last index which can still be increased
+        let i = match (0..k).rev().find(|i| subset[*i] < n - k + i) {
+            Some(i) => i,
+            None => return subsets,
+        };
+        subset[i] += 1;
+        for r in i + 1..k {
+            subset[r] = subset[r - 1] + 1;
+        }
+    }
+}
+
+/**
+ * Order points of a convex polygon around their centroid
+ */
+fn order_around_centroid(points: &mut [(f64, f64)]) {
+    let count = points.len() as f64;
+    let cx = points.iter().map(|p| p.0).sum::<f64>() / count;
+    let cy = points.iter().map(|p| p.1).sum::<f64>() / count;
+    points.sort_by(|p, q| {
+        let angle = |p: &(f64, f64)| (p.1 - cy).atan2(p.0 - cx);
+        angle(p).partial_cmp(&angle(q)).unwrap()
+    });
+}
+
+/**
+ * Maps points in the plane of the plot to SVG coordinates, keeping
+ * the aspect ratio and flipping the vertical axis
+ */
+struct Canvas {
+    min: (f64, f64),
+    scale: f64,
+    out: String,
+}
+
+impl Canvas {
+    fn new(points: &[(f64, f64)]) -> Self {
+        let min_u = points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+        let max_u = points.iter().map(|p| p.0).fold(-f64::INFINITY, f64::max);
+        let min_v = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        let max_v = points.iter().map(|p| p.1).fold(-f64::INFINITY, f64::max);
+        let span = f64::max(max_u - min_u, max_v - min_v).max(1e-9);
+
+        let mut out = String::new();
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" \
+             height=\"{0}\" viewBox=\"0 0 {0} {0}\" font-family=\"sans-serif\" \
+             font-size=\"12\">",
+            SIZE
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" \
+             refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" \
+             orient=\"auto\"><path d=\"M 0 0 L 10 5 L 0 10 z\" \
+             fill=\"#2e7d32\"/></marker></defs>"
+        )
+        .unwrap();
+        writeln!(out, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>")
+            .unwrap();
+
+        Canvas {
+            min: (min_u, min_v),
+            scale: (SIZE - 2.0 * MARGIN) / span,
+            out,
+        }
+    }
+
+    fn map(&self, p: (f64, f64)) -> (f64, f64) {
+        (
+            MARGIN + (p.0 - self.min.0) * self.scale,
+            SIZE - MARGIN - (p.1 - self.min.1) * self.scale,
+        )
+    }
+
+    fn polygon(&mut self, points: &[(f64, f64)], style: &str) {
+        let points = points
+            .iter()
+            .map(|p| {
+                let (x, y) = self.map(*p);
+                format!("{:.2},{:.2}", x, y)
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        writeln!(self.out, "<polygon points=\"{}\" {}/>", points, style)
+            .unwrap();
+    }
+
+    fn line(&mut self, p: (f64, f64), q: (f64, f64), style: &str) {
+        let (p, q) = (self.map(p), self.map(q));
+        writeln!(
+            self.out,
+            "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" {}/>",
+            p.0, p.1, q.0, q.1, style
+        )
+        .unwrap();
+    }
+
+    fn text(&mut self, p: (f64, f64), text: &str, style: &str) {
+        let (x, y) = self.map(p);
+        writeln!(
+            self.out,
+            "<text x=\"{:.2}\" y=\"{:.2}\" {}>{}</text>",
+            x + 4.0,
+            y - 4.0,
+            style,
+            text
+        )
+        .unwrap();
+    }
+
+    /**
+     * Draw the path through the vertices visited by the simplex
+     * method, numbering each vertex
+     */
+    fn path(&mut self, path: &[(f64, f64)]) {
+        let points = path
+            .iter()
+            .map(|p| {
+                let (x, y) = self.map(*p);
+                format!("{:.2},{:.2}", x, y)
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        writeln!(
+            self.out,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"#c62828\" \
+             stroke-width=\"2\"/>",
+            points
+        )
+        .unwrap();
+
+        for (k, p) in path.iter().enumerate() {
+            let (x, y) = self.map(*p);
+            writeln!(
+                self.out,
+                "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"4\" fill=\"#c62828\"/>",
+                x, y
+            )
+            .unwrap();
+            self.text(*p, &k.to_string(), "fill=\"#c62828\"");
+        }
+    }
+
+    /**
+     * Draw an arrow from `from` in the direction of the objective
+     */
+    fn objective(&mut self, from: (f64, f64), direction: (f64, f64)) {
+        let length = (direction.0.powi(2) + direction.1.powi(2)).sqrt();
+        if length == 0.0 {
+            return;
+        }
+
+        let size = 0.15 * (SIZE - 2.0 * MARGIN) / self.scale / length;
+        let to = (from.0 + direction.0 * size, from.1 + direction.1 * size);
+        self.line(
+            from,
+            to,
+            "stroke=\"#2e7d32\" stroke-width=\"2\" marker-end=\"url(#arrow)\"",
+        );
+        self.text(to, "c", "fill=\"#2e7d32\"");
+    }
+
+    fn finish(mut self) -> String {
+        self.out.push_str("</svg>\n");
+        self.out
+    }
+}
+
+fn plot_2d(
+    lp: &ParsedLP,
+    lo: &[f64],
+    hi: &[f64],
+    c: &[f64],
+    path: &[Vec<f64>],
+) -> String {
+    let corners = [
+        (lo[0], lo[1]),
+        (hi[0], lo[1]),
+        (hi[0], hi[1]),
+        (lo[0], hi[1]),
+    ];
+    let mut canvas = Canvas::new(&corners);
+    let bounds = || bound_half_spaces(lo, hi);
+    let polygon = |half_spaces: &[HalfSpace]| {
+        let mut points = vertices(half_spaces, 2)
+            .iter()
+            .map(|v| (v.x[0], v.x[1]))
+            .collect::<Vec<(f64, f64)>>();
+        order_around_centroid(&mut points);
+        points
+    };
+
+    // Each constraint's half-plane, and its boundary line
+    let constraints = constraint_half_spaces(&lp.A, lp.b.as_slice(), 2);
+    for h in &constraints {
+        let mut half_spaces = bounds();
+        let i = h.constraint.unwrap();
+        half_spaces.push(HalfSpace {
+            a: h.a.clone(),
+            b: h.b,
+            constraint: Some(i),
+        });
+
+        let half_plane = vertices(&half_spaces, 2);
+        let on_line = half_plane
+            .iter()
+            .filter(|v| v.tight.contains(&(half_spaces.len() - 1)))
+            .map(|v| (v.x[0], v.x[1]))
+            .collect::<Vec<(f64, f64)>>();
+
+        let points = polygon(&half_spaces);
+        if points.len() >= 3 {
+            canvas.polygon(&points, "fill=\"#9e9e9e\" fill-opacity=\"0.08\"");
+        }
+        if let [p, .., q] = on_line.as_slice() {
+            canvas.line(*p, *q, "stroke=\"#616161\" stroke-width=\"1\"");
+            canvas.text(*q, &constraint_name(i), "fill=\"#616161\"");
+        }
+    }
+
+    // The axes
+    canvas.line((lo[0], 0.0), (hi[0], 0.0), "stroke=\"black\"");
+    canvas.line((0.0, lo[1]), (0.0, hi[1]), "stroke=\"black\"");
+    canvas.text((hi[0], 0.0), "x1", "");
+    canvas.text((0.0, hi[1]), "x2", "");
+
+    // The feasible region
+    let mut half_spaces = constraints;
+    half_spaces.extend(bound_half_spaces(&[0.0, 0.0], hi));
+    let feasible = polygon(&half_spaces);
+    if feasible.len() >= 3 {
+        canvas.polygon(
+            &feasible,
+            "fill=\"#1e88e5\" fill-opacity=\"0.3\" stroke=\"#1565c0\" \
+             stroke-width=\"2\"",
+        );
+    }
+
+    let from = centroid(&feasible)
+        .unwrap_or(((lo[0] + hi[0]) / 2.0, (lo[1] + hi[1]) / 2.0));
+    canvas.objective(from, (c[0], c[1]));
+    canvas.path(&path.iter().map(|x| (x[0], x[1])).collect::<Vec<_>>());
+    canvas.finish()
+}
+
+fn plot_3d(
+    lp: &ParsedLP,
+    lo: &[f64],
+    hi: &[f64],
+    c: &[f64],
+    path: &[Vec<f64>],
+) -> String {
+    let project = |x: &[f64]| {
+        let (sin, cos) = std::f64::consts::FRAC_PI_6.sin_cos();
+        ((x[0] - x[1]) * cos, (x[0] + x[1]) * sin + x[2])
+    };
+
+    let corners = (0..8)
+        .map(|k| {
+            project(&[
+                if k & 1 == 0 { lo[0] } else { hi[0] },
+                if k & 2 == 0 { lo[1] } else { hi[1] },
+                if k & 4 == 0 { lo[2] } else { hi[2] },
+            ])
+        })
+        .collect::<Vec<(f64, f64)>>();
+    let mut canvas = Canvas::new(&corners);
+
+    // The axes
+    let origin = project(&[0.0, 0.0, 0.0]);
+    for (j, name) in ["x1", "x2", "x3"].iter().enumerate() {
+        let mut end = [0.0; 3];
+        end[j] = hi[j];
+        canvas.line(origin, project(&end), "stroke=\"black\"");
+        canvas.text(project(&end), name, "");
+    }
+
+    // The faces of the feasible polytope. Faces on the box around
+    // the plot are where the region is unbounded, and are dashed.
+    let mut half_spaces = constraint_half_spaces(&lp.A, lp.b.as_slice(), 3);
+    half_spaces.extend(bound_half_spaces(&[0.0; 3], hi));
+    let vertices = vertices(&half_spaces, 3);
+    let mut all_points = Vec::new();
+    for (h, half_space) in half_spaces.iter().enumerate() {
+        let mut face = vertices
+            .iter()
+            .filter(|v| v.tight.contains(&h))
+            .map(|v| project(&v.x))
+            .collect::<Vec<(f64, f64)>>();
+        if face.len() < 3 {
+            continue;
+        }
+
+        order_around_centroid(&mut face);
+        let on_box = half_space.constraint.is_none()
+            && half_space.a.iter().any(|a| *a > 0.0);
+        let style = match on_box {
+            true => {
+                "fill=\"#1e88e5\" fill-opacity=\"0.08\" stroke=\"#1565c0\" \
+                 stroke-dasharray=\"4 3\""
+            }
+            false => {
+                "fill=\"#1e88e5\" fill-opacity=\"0.15\" stroke=\"#1565c0\" \
+                 stroke-width=\"1.5\""
+            }
+        };
+        canvas.polygon(&face, style);
+        all_points.extend(face);
+    }
+
+    let from = centroid(&all_points).unwrap_or(project(&[
+        (lo[0] + hi[0]) / 2.0,
+        (lo[1] + hi[1]) / 2.0,
+        (lo[2] + hi[2]) / 2.0,
+    ]));
+    let direction = project(c);
+    canvas.objective(from, (direction.0, direction.1));
+    canvas.path(&path.iter().map(|x| project(x)).collect::<Vec<_>>());
+    canvas.finish()
+}
+
+fn centroid(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if points.is_empty() {
+        return None;
+    }
+
+    let count = points.len() as f64;
+    Some((
+        points.iter().map(|p| p.0).sum::<f64>() / count,
+        points.iter().map(|p| p.1).sum::<f64>() / count,
+    ))
+}
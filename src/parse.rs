@@ -14,6 +14,7 @@
  * along with bblp. If not, see <https://www.gnu.org/licenses/>.
  */
 
+use crate::scalar::Scalar;
 use crate::{Matrix, Vector};
 use std::fs::File;
 use std::io::prelude::*;
@@ -26,7 +27,7 @@ pub fn read_file(path: &str) -> Result<String, String> {
     let path = Path::new(path);
     let display = path.display();
 
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Err(why) => return Err(format!("couldn't open {}: {}", display, why)),
         Ok(file) => file,
     };
@@ -42,19 +43,25 @@ pub fn read_file(path: &str) -> Result<String, String> {
  * Represents a parsed linear program which can be solved
  * by `solve_primal` or `solve_dual`
  */
-pub struct ParsedLP {
-    pub A: Matrix,
-    pub b: Vector,
-    pub c: Vector,
+pub struct ParsedLP<T = f64> {
+    pub A: Matrix<T>,
+    pub b: Vector<T>,
+    pub c: Vector<T>,
     pub n: usize,
-    pub m: usize,
+    pub integer: Vec<usize>,
 }
 
+/**
+ * Keywords which start the section listing the (1-indexed)
+ * variables that must take integer values
+ */
+const INTEGER_KEYWORDS: [&str; 2] = ["general", "integer"];
+
 /**
  * Parse the contents of a file into the relevant matrices and vectors
  * needed to solve it with the Revised Simplex Method
  */
-pub fn parse(file_contents: &str) -> Result<ParsedLP, String> {
+pub fn parse<T: Scalar>(file_contents: &str) -> Result<ParsedLP<T>, String> {
     let mut lines = file_contents.lines().filter(|c| !c.trim().is_empty());
     let c = lines.next();
 
@@ -62,47 +69,133 @@ pub fn parse(file_contents: &str) -> Result<ParsedLP, String> {
         return Err(String::from("Not enough lines in input file"));
     }
 
-    let c: Vec<f64> = c
-        .unwrap()
-        .split_whitespace()
-        .map(|val| val.parse::<f64>().unwrap())
-        .map(|val| if val == -0.0 { 0.0 } else { val })
-        .collect();
-
-    let A: Vec<Vec<f64>> = lines
-        .map(|l| {
-            l.split_whitespace()
-                .map(|val| val.parse::<f64>().unwrap())
-                .map(|val| if val == -0.0 { 0.0 } else { val })
-                .collect()
-        })
-        .collect();
+    let c: Vec<T> = parse_row(c.unwrap())?;
+
+    let lines: Vec<&str> = lines.collect();
+    let section = lines.iter().position(|l| {
+        l.split_whitespace()
+            .next()
+            .is_some_and(|w| INTEGER_KEYWORDS.contains(&w))
+    });
+    let (lines, integer_lines) = lines.split_at(section.unwrap_or(lines.len()));
 
-    let n = A[0].len();
+    let A: Vec<Vec<T>> = lines
+        .iter()
+        .map(|l| parse_row(l))
+        .collect::<Result<_, _>>()?;
+
+    let n = A.first().map_or(0, |row| row.len());
     let m = A.len();
 
     if n == 0 || m == 0 {
         return Err(String::from("Not enough rows/cols for matrix A"));
     }
 
-    let A: Vec<f64> = A.into_iter().flatten().collect();
-    let A = Matrix::from_row_slice(m, n, &A);
+    if A.iter().any(|row| row.len() != n) {
+        return Err(String::from("Rows of matrix A have different lengths"));
+    }
 
-    let b = A.column(n - 1).clone_owned();
-    let n = n - 1;
+    let integer = parse_integer_section(integer_lines, n - 1)?;
+    let (A, b): (Vec<Vec<T>>, Vec<T>) = A
+        .into_iter()
+        .map(|mut row| {
+            let b = row.pop().unwrap();
+            (row, b)
+        })
+        .unzip();
 
-    // We will only insert m - 1 columnns into A since we can re-use the column that
-    // was copied to `b` and no longer needed
-    let mut A = A.insert_columns(n, m - 1, 0.0);
+    ParsedLP::new(c, A, b, integer)
+}
 
-    let I = Matrix::identity(m, m);
+impl<T: Scalar> ParsedLP<T> {
+    /**
+     * The problem `max c^T x` subject to `A0 x <= b` and `x >= 0`, given
+     * the rows of `A0`. The variables in `integer` (0-indexed) must take
+     * integer values.
+     */
+    pub fn new(
+        c: Vec<T>,
+        A: Vec<Vec<T>>,
+        b: Vec<T>,
+        integer: Vec<usize>,
+    ) -> Result<Self, String> {
+        let n = c.len();
+        let m = A.len();
+
+        if A.iter().any(|row| row.len() != n) || b.len() != m {
+            return Err(String::from(
+                "The objective and constraints have different lengths",
+            ));
+        }
+
+        // Build [A | I] column by column (with `from_iterator` since the
+        // other nalgebra constructors aren't sound for `Rational`)
+        let A = Matrix::from_iterator(
+            m,
+            n + m,
+            (0..n + m).flat_map(|j| {
+                let A = &A;
+                (0..m).map(move |i| match j {
+                    j if j < n => A[i][j].clone(),
+                    j if j - n == i => T::one(),
+                    _ => T::zero(),
+                })
+            }),
+        );
+
+        let c = Vector::from_iterator(
+            n + m,
+            c.into_iter().chain((0..m).map(|_| T::zero())),
+        );
+
+        Ok(ParsedLP {
+            A,
+            b: Vector::from_iterator(m, b),
+            c,
+            n,
+            integer,
+        })
+    }
+}
 
-    I.column_iter()
-        .enumerate()
-        .for_each(|(i, val)| A.set_column(n + i, &val));
+/**
+ * Parse a whitespace separated row of numbers. Negative
+ * zeros are replaced with positive zeros.
+ */
+fn parse_row<T: Scalar>(line: &str) -> Result<Vec<T>, String> {
+    line.split_whitespace()
+        .map(|val| T::parse(val))
+        .map(|val| val.map(|v| if v.is_zero() { T::zero() } else { v }))
+        .collect()
+}
 
-    let c_len = c.len();
-    let c = Vector::from_vec(c).insert_rows(c_len, m, 0.0);
+/**
+ * Parse the variable numbers from the integer section. The section
+ * keyword may be followed by variable numbers on the same line.
+ */
+fn parse_integer_section(
+    lines: &[&str],
+    n: usize,
+) -> Result<Vec<usize>, String> {
+    let mut integer: Vec<usize> = Vec::new();
+
+    for word in lines.iter().flat_map(|l| l.split_whitespace()) {
+        if INTEGER_KEYWORDS.contains(&word) {
+            continue;
+        }
+
+        match word.parse::<usize>() {
+            Ok(j) if j >= 1 && j <= n => integer.push(j - 1),
+            _ => {
+                return Err(format!(
+                    "Invalid integer variable '{}' (expected 1 to {})",
+                    word, n
+                ))
+            }
+        }
+    }
 
-    Ok(ParsedLP { A, b, c, n, m })
+    integer.sort_unstable();
+    integer.dedup();
+    Ok(integer)
 }
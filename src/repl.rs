@@ -0,0 +1,397 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::output::{
+    basis_variable_index, basis_variable_name, dictionary, DictionaryFormat,
+    DictionaryLayout,
+};
+use crate::parse::ParsedLP;
+use crate::scalar::Scalar;
+use crate::solve::{Phase, State};
+use crate::util::{
+    col_view, row_view, select_entering, select_leaving, tr_mul, transpose,
+    write_view,
+};
+use crate::Vector;
+use std::io::{BufRead, Write};
+
+const HELP: &str = "\
+Commands:
+  step                    make the next pivot
+  choose-entering <var>   choose the entering variable of the next pivot
+  choose-leaving <var>    choose the leaving variable of the next pivot
+  undo                    undo the last pivot
+  dictionary              print the dictionary, marking the next pivot
+  layout <layout>         show the dictionary or the tableau
+  format <format>         print the dictionary as text or latex
+  phase [<phase>]         show or switch the phase: primal, dual or aux
+  help                    print this message
+  quit                    exit
+
+Variables are named x1, x2, ... and the slack of constraint i is wi.";
+
+/**
+ * What the next pivot of the current phase is, or how the phase ended
+ * if there are no more pivots
+ */
+enum Next {
+    Pivot(usize, usize),
+    Done(&'static str),
+}
+
+/**
+ * The state of a step-through session: the basis, the phase being
+ * solved, the pivots chosen by the user and the bases before each
+ * pivot so that they can be undone
+ */
+pub struct Session<'a, T> {
+    lp: &'a ParsedLP<T>,
+    zero: Vector<T>,
+    B: Vec<usize>,
+    N: Vec<usize>,
+    phase: Phase,
+    entering: Option<usize>,
+    leaving: Option<usize>,
+    history: Vec<(Vec<usize>, Vec<usize>, Phase)>,
+    layout: DictionaryLayout,
+    format: DictionaryFormat,
+}
+
+impl<'a, T: Scalar> Session<'a, T> {
+    /**
+     * Start from the slack basis in the phase `solve::simplex` would
+     * start with
+     */
+    pub fn new(lp: &'a ParsedLP<T>) -> Self {
+        let m = lp.A.nrows();
+        let n = lp.A.ncols() - m;
+
+        let phase = if !lp.b.iter().any(|v| *v < -T::epsilon()) {
+            Phase::Primal
+        } else if !lp.c.iter().any(|v| *v > T::epsilon()) {
+            Phase::Dual
+        } else {
+            Phase::Auxiliary
+        };
+
+        Session {
+            lp,
+            zero: Vector::zeros(m),
+            B: (n..n + m).collect(),
+            N: (0..n).collect(),
+            phase,
+            entering: None,
+            leaving: None,
+            history: Vec::new(),
+            layout: DictionaryLayout::Dictionary,
+            format: DictionaryFormat::Text,
+        }
+    }
+
+    /**
+     * Run a command, returning what should be printed for it
+     */
+    pub fn execute(&mut self, command: &str) -> Result<String, String> {
+        let words = command.split_whitespace().collect::<Vec<&str>>();
+
+        match words.as_slice() {
+            [] => Ok(String::new()),
+            ["help"] => Ok(String::from(HELP)),
+            ["step"] => self.step(),
+            ["choose-entering", name] => {
+                let j = self.variable(name)?;
+                if !self.N.contains(&j) {
+                    return Err(format!("{} is already basic", name));
+                }
+                self.entering = Some(j);
+                self.show()
+            }
+            ["choose-leaving", name] => {
+                let j = self.variable(name)?;
+                if !self.B.contains(&j) {
+                    return Err(format!("{} isn't basic", name));
+                }
+                self.leaving = Some(j);
+                self.show()
+            }
+            ["undo"] => match self.history.pop() {
+                Some((B, N, phase)) => {
+                    self.B = B;
+                    self.N = N;
+                    self.phase = phase;
+                    self.entering = None;
+                    self.leaving = None;
+                    self.show()
+                }
+                None => Err(String::from("There are no pivots to undo")),
+            },
+            ["dictionary"] => self.show(),
+            ["layout", layout] => {
+                self.layout = layout.parse()?;
+                self.show()
+            }
+            ["format", format] => {
+                self.format = format.parse()?;
+                self.show()
+            }
+            ["phase"] => Ok(format!("{} phase", self.phase.name())),
+            ["phase", phase] => {
+                self.phase = phase.parse()?;
+                self.entering = None;
+                self.leaving = None;
+                self.show()
+            }
+            _ => Err(format!(
+                "Unknown command '{}' (type help for a list of commands)",
+                command.trim()
+            )),
+        }
+    }
+
+    /**
+     * Make the next pivot and show the new dictionary
+     */
+    fn step(&mut self) -> Result<String, String> {
+        let (x, z) = self.values()?;
+
+        let (entering, leaving) = match self.next(&x, &z)? {
+            Next::Pivot(entering, leaving) => (entering, leaving),
+            Next::Done(status) => return self.done(status, &x),
+        };
+
+        self.history
+            .push((self.B.clone(), self.N.clone(), self.phase));
+        let i_idx = self.B.iter().position(|j| *j == leaving).unwrap();
+        let j_idx = self.N.iter().position(|j| *j == entering).unwrap();
+        self.B[i_idx] = entering;
+        self.N[j_idx] = leaving;
+        self.entering = None;
+        self.leaving = None;
+
+        let n = self.N.len();
+        Ok(format!(
+            "{} enters, {} leaves\n\n{}",
+            basis_variable_name(entering, n),
+            basis_variable_name(leaving, n),
+            self.show()?
+        ))
+    }
+
+    /**
+     * Describe how the current phase ended
+     */
+    fn done(&self, status: &str, x: &Vector<T>) -> Result<String, String> {
+        let objective = self
+            .B
+            .iter()
+            .map(|j| self.lp.c[*j].clone() * x[*j].clone())
+            .fold(T::zero(), |sum, v| sum + v);
+
+        Ok(match (status, self.phase) {
+            ("optimal", Phase::Auxiliary) => String::from(
+                "The auxiliary problem is optimal, continue with `phase dual`",
+            ),
+            ("optimal", _) => {
+                format!("optimal\n{}", objective.format(T::DEFAULT_FORMAT))
+            }
+            (status, _) => String::from(status),
+        })
+    }
+
+    /**
+     * Show the dictionary, marking the next pivot if there is one
+     */
+    fn show(&self) -> Result<String, String> {
+        let (x, z) = self.values()?;
+        let pivot = match self.next(&x, &z) {
+            Ok(Next::Pivot(entering, leaving)) => Some((entering, leaving)),
+            _ => None,
+        };
+
+        let state = State {
+            A: &self.lp.A,
+            c: &self.lp.c,
+            x: &x,
+            z: &z,
+            B: &self.B,
+            N: &self.N,
+        };
+        let heading = format!("{} phase", self.phase.name());
+        dictionary(&state, self.phase, pivot, self.layout, self.format)
+            .map(|shown| format!("{}\n{}", heading, shown))
+            .ok_or_else(|| String::from("The basis is singular"))
+    }
+
+    /**
+     * The values `x` and reduced costs `z` of all the variables for
+     * the current basis. The auxiliary problem has `b = 0`.
+     */
+    fn values(&self) -> Result<(Vector<T>, Vector<T>), String> {
+        let (A, c) = (&self.lp.A, &self.lp.c);
+        let b = match self.phase {
+            Phase::Auxiliary => &self.zero,
+            _ => &self.lp.b,
+        };
+        let size = self.B.len() + self.N.len();
+
+        let A_B = col_view(A, &self.B);
+        let x_B = T::solve(&A_B, b)
+            .ok_or_else(|| String::from("The basis is singular"))?;
+        let mut x = Vector::zeros(size);
+        write_view(&mut x, &x_B, &self.B);
+
+        let v = T::solve(&transpose(&A_B), &row_view(c, &self.B))
+            .ok_or_else(|| String::from("The basis is singular"))?;
+        let z_N = tr_mul(&col_view(A, &self.N), &v) - row_view(c, &self.N);
+        let mut z = Vector::zeros(size);
+        write_view(&mut z, &z_N, &self.N);
+
+        Ok((x, z))
+    }
+
+    /**
+     * The next pivot of the current phase, using the user's choices
+     * where they have made them and the usual rules otherwise
+     */
+    fn next(&self, x: &Vector<T>, z: &Vector<T>) -> Result<Next, String> {
+        let A = &self.lp.A;
+        let n = self.N.len();
+        let A_B = col_view(A, &self.B);
+        let name = |j: usize| basis_variable_name(j, n);
+
+        match self.phase {
+            Phase::Primal | Phase::Auxiliary => {
+                if self.B.iter().any(|j| x[*j] < -T::epsilon()) {
+                    return Err(String::from(
+                        "The basis isn't primal feasible, try `phase dual`",
+                    ));
+                }
+
+                let j = match self.entering {
+                    Some(j) => j,
+                    None => match select_entering(&self.N, z) {
+                        Some((j, _)) => j,
+                        None => return Ok(Next::Done("optimal")),
+                    },
+                };
+
+                let delta_x_B = T::solve(&A_B, &A.column(j).clone_owned())
+                    .ok_or_else(|| String::from("The basis is singular"))?;
+                let mut delta_x = Vector::zeros(x.len());
+                write_view(&mut delta_x, &delta_x_B, &self.B);
+
+                match self.leaving {
+                    Some(i) if delta_x[i] > T::epsilon() => {
+                        Ok(Next::Pivot(j, i))
+                    }
+                    Some(i) => Err(format!(
+                        "{} can't leave, it doesn't decrease as {} increases",
+                        name(i),
+                        name(j)
+                    )),
+                    None => match select_leaving(&self.B, x, &delta_x) {
+                        Some((_, i, _)) => Ok(Next::Pivot(j, i)),
+                        None => Ok(Next::Done("unbounded")),
+                    },
+                }
+            }
+            Phase::Dual => {
+                if self.N.iter().any(|j| z[*j] < -T::epsilon()) {
+                    return Err(String::from(
+                        "The basis isn't dual feasible, try `phase primal` \
+                         or `phase aux`",
+                    ));
+                }
+
+                let i = match self.leaving {
+                    Some(i) => i,
+                    None => match select_entering(&self.B, x) {
+                        Some((i, _)) => i,
+                        None => return Ok(Next::Done("optimal")),
+                    },
+                };
+
+                let i_idx = self.B.iter().position(|j| *j == i).unwrap();
+                let mut u = Vector::zeros(self.B.len());
+                u[i_idx] = T::one();
+                let v = T::solve(&transpose(&A_B), &u)
+                    .ok_or_else(|| String::from("The basis is singular"))?;
+                let delta_z_N = -tr_mul(&col_view(A, &self.N), &v);
+                let mut delta_z = Vector::zeros(z.len());
+                write_view(&mut delta_z, &delta_z_N, &self.N);
+
+                match self.entering {
+                    Some(j) if delta_z[j] > T::epsilon() => {
+                        Ok(Next::Pivot(j, i))
+                    }
+                    Some(j) => Err(format!(
+                        "{} can't enter, its reduced cost doesn't decrease \
+                         as {} leaves",
+                        name(j),
+                        name(i)
+                    )),
+                    None => match select_leaving(&self.N, z, &delta_z) {
+                        Some((_, j, _)) => Ok(Next::Pivot(j, i)),
+                        None => Ok(Next::Done("infeasible")),
+                    },
+                }
+            }
+        }
+    }
+
+    /**
+     * The column of a variable named by the user
+     */
+    fn variable(&self, name: &str) -> Result<usize, String> {
+        let m = self.B.len();
+        basis_variable_index(name, self.N.len(), m)
+            .ok_or_else(|| format!("Unknown variable '{}'", name))
+    }
+}
+
+/**
+ * Run a step-through session for the problem, reading commands from
+ * `input` until it ends or the user quits
+ */
+pub fn run<T: Scalar>(
+    lp: &ParsedLP<T>,
+    input: impl BufRead,
+    output: &mut impl Write,
+) -> Result<(), String> {
+    let mut session = Session::new(lp);
+    let mut write = |text: &str| {
+        output
+            .write_all(text.as_bytes())
+            .and_then(|_| output.flush())
+            .map_err(|e| e.to_string())
+    };
+
+    write(&format!("{}\n\n> ", session.show()?))?;
+    for line in input.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if ["quit", "exit"].contains(&line.trim()) {
+            return Ok(());
+        }
+
+        match session.execute(&line) {
+            Ok(text) if text.is_empty() => write("> ")?,
+            Ok(text) => write(&format!("{}\n\n> ", text))?,
+            Err(e) => write(&format!("Error: {}\n\n> ", e))?,
+        }
+    }
+
+    write("\n")
+}
@@ -0,0 +1,261 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::scalar::{
+    format_f64, NumberFormat, Rational, Scalar, PRINT_SIG_FIGS,
+};
+use num_rational::BigRational;
+use num_traits::{One, ToPrimitive, Zero};
+use serde::{Serialize, Serializer};
+use std::cmp::Ordering;
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign,
+};
+
+/**
+ * Comparison epsilon for `DoubleDouble`, see `crate::EPSILON`
+ */
+const DD_EPSILON: f64 = 1e-18;
+
+/**
+ * Perturbation amount for `DoubleDouble`, see `crate::PERTURB_AMT`
+ */
+const DD_PERTURB_AMT: f64 = 1e-18;
+
+/**
+ * Residual tolerance for `DoubleDouble`, see `Scalar::residual_tolerance`
+ */
+const DD_RESIDUAL_TOLERANCE: f64 = 1e-28;
+
+/**
+ * A double-double number, represented as the unevaluated sum of two
+ * `f64`s with `|lo| <= ulp(hi) / 2`. This gives about 32 significant
+ * digits while staying `Copy` and much faster than `Rational`. The
+ * algorithms are from Hida, Li and Bailey's QD library.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DoubleDouble {
+    pub hi: f64,
+    pub lo: f64,
+}
+
+/**
+ * Compute `a + b` along with the rounding error of the sum
+ */
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let bb = s - a;
+    (s, (a - (s - bb)) + (b - bb))
+}
+
+/**
+ * `two_sum` for when `|a| >= |b|` is already known
+ */
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    (s, b - (s - a))
+}
+
+/**
+ * Compute `a * b` along with the rounding error of the product
+ */
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    let p = a * b;
+    (p, a.mul_add(b, -p))
+}
+
+impl DoubleDouble {
+    pub fn new(hi: f64, lo: f64) -> Self {
+        let (hi, lo) = quick_two_sum(hi, lo);
+        DoubleDouble { hi, lo }
+    }
+
+    /**
+     * Round an exact rational to the nearest double-double
+     */
+    pub fn from_rational(value: &Rational) -> Self {
+        let hi = value.to_f64();
+        let rest = BigRational::from_float(hi)
+            .map(|hi| &value.0 - hi)
+            .and_then(|rest| rest.to_f64())
+            .unwrap_or(0.0);
+        DoubleDouble::new(hi, rest)
+    }
+}
+
+impl From<f64> for DoubleDouble {
+    fn from(value: f64) -> Self {
+        DoubleDouble { hi: value, lo: 0.0 }
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn add(self, rhs: DoubleDouble) -> DoubleDouble {
+        let (s, e) = two_sum(self.hi, rhs.hi);
+        let (t, f) = two_sum(self.lo, rhs.lo);
+        let (s, e) = quick_two_sum(s, e + t);
+        DoubleDouble::new(s, e + f)
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn sub(self, rhs: DoubleDouble) -> DoubleDouble {
+        self + -rhs
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn mul(self, rhs: DoubleDouble) -> DoubleDouble {
+        let (p, e) = two_prod(self.hi, rhs.hi);
+        DoubleDouble::new(p, e + (self.hi * rhs.lo + self.lo * rhs.hi))
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = DoubleDouble;
+
+    /**
+     * Long division, with each quotient digit
+     * computed from the leading `f64`s
+     */
+    fn div(self, rhs: DoubleDouble) -> DoubleDouble {
+        let q1 = self.hi / rhs.hi;
+        let r = self - rhs * q1.into();
+        let q2 = r.hi / rhs.hi;
+        let r = r - rhs * q2.into();
+        let q3 = r.hi / rhs.hi;
+        DoubleDouble::new(q1, q2) + q3.into()
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn neg(self) -> DoubleDouble {
+        DoubleDouble {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl AddAssign for DoubleDouble {
+    fn add_assign(&mut self, rhs: DoubleDouble) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for DoubleDouble {
+    fn sub_assign(&mut self, rhs: DoubleDouble) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign for DoubleDouble {
+    fn mul_assign(&mut self, rhs: DoubleDouble) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign for DoubleDouble {
+    fn div_assign(&mut self, rhs: DoubleDouble) {
+        *self = *self / rhs;
+    }
+}
+
+impl PartialOrd for DoubleDouble {
+    fn partial_cmp(&self, other: &DoubleDouble) -> Option<Ordering> {
+        match self.hi.partial_cmp(&other.hi)? {
+            Ordering::Equal => self.lo.partial_cmp(&other.lo),
+            ordering => Some(ordering),
+        }
+    }
+}
+
+impl Zero for DoubleDouble {
+    fn zero() -> Self {
+        DoubleDouble::from(0.0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.hi == 0.0
+    }
+}
+
+impl One for DoubleDouble {
+    fn one() -> Self {
+        DoubleDouble::from(1.0)
+    }
+}
+
+/**
+ * Serialized as the nearest `f64`
+ */
+impl Serialize for DoubleDouble {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_f64())
+    }
+}
+
+impl Scalar for DoubleDouble {
+    const EXACT: bool = false;
+    const DEFAULT_FORMAT: NumberFormat = NumberFormat::SigFigs(PRINT_SIG_FIGS);
+
+    fn epsilon() -> Self {
+        DD_EPSILON.into()
+    }
+
+    fn perturb_amt() -> Self {
+        DD_PERTURB_AMT.into()
+    }
+
+    fn residual_tolerance() -> f64 {
+        DD_RESIDUAL_TOLERANCE
+    }
+
+    /**
+     * Parse the number exactly and then round it, since
+     * parsing it as an `f64` would lose the low digits
+     */
+    fn parse(s: &str) -> Result<Self, String> {
+        Rational::parse(s).map(|value| DoubleDouble::from_rational(&value))
+    }
+
+    fn to_f64(&self) -> f64 {
+        self.hi + self.lo
+    }
+
+    fn format(&self, format: NumberFormat) -> String {
+        format_f64(self.to_f64(), format)
+    }
+
+    fn abs(&self) -> Self {
+        if self.hi < 0.0 {
+            -*self
+        } else {
+            *self
+        }
+    }
+}
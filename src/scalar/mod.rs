@@ -0,0 +1,302 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::util::round_sig_figs;
+use crate::{Matrix, Vector, EPSILON, PERTURB_AMT};
+use na::{ClosedAdd, ClosedDiv, ClosedMul, ClosedSub};
+use num_traits::{One, Zero};
+use serde::Serialize;
+use std::ops::Neg;
+use std::str::FromStr;
+
+mod double_double;
+mod rational;
+
+pub use double_double::*;
+pub use rational::*;
+
+/**
+ * Number of significant figures to print in the results by default.
+ * (not the number of sig figs used in the calculations)
+ */
+pub const PRINT_SIG_FIGS: u32 = 7;
+
+/**
+ * How to print a number in the results
+ *
+ * - `SigFigs(d)`: rounded to `d` significant figures, the course format
+ * - `RoundTrip`: the shortest decimal that reads back as the same `f64`
+ * - `Fixed(d)`: `d` digits after the decimal point
+ * - `Scientific(d)`: scientific notation with `d` digits after the point
+ * - `Exact`: an exact fraction for exact types, `RoundTrip` otherwise
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NumberFormat {
+    SigFigs(u32),
+    RoundTrip,
+    Fixed(usize),
+    Scientific(usize),
+    Exact,
+}
+
+/**
+ * Parses `N` (significant figures), `full`, `fixed:N`, `sci:N` or `exact`
+ */
+impl FromStr for NumberFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = |d: &str| {
+            d.parse::<usize>()
+                .map_err(|_| format!("Invalid number of digits '{}'", d))
+        };
+
+        match s.split_once(':') {
+            None if s == "full" => Ok(NumberFormat::RoundTrip),
+            None if s == "exact" => Ok(NumberFormat::Exact),
+            None => match digits(s)? {
+                0 => Err(String::from("Precision must be at least 1")),
+                d => Ok(NumberFormat::SigFigs(d as u32)),
+            },
+            Some(("fixed", d)) => Ok(NumberFormat::Fixed(digits(d)?)),
+            Some(("sci", d)) => Ok(NumberFormat::Scientific(digits(d)?)),
+            Some(_) => Err(format!(
+                "Unknown number format '{}' (expected N, full, fixed:N, \
+                 sci:N or exact)",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * Format a floating point value. There is no exact format for floats,
+ * so `Exact` falls back to `RoundTrip`.
+ */
+pub fn format_f64(value: f64, format: NumberFormat) -> String {
+    let formatted = match format {
+        NumberFormat::SigFigs(d) => format!("{}", round_sig_figs(value, d)),
+        NumberFormat::RoundTrip | NumberFormat::Exact => format!("{}", value),
+        NumberFormat::Fixed(d) => format!("{:.*}", d, value),
+        NumberFormat::Scientific(d) => format!("{:.*e}", d, value),
+    };
+
+    // Don't print values that round to zero as "-0"
+    match formatted.strip_prefix('-') {
+        Some(rest) if formatted.parse::<f64>() == Ok(0.0) => rest.to_string(),
+        _ => formatted,
+    }
+}
+
+/**
+ * Comparison epsilon for `f32`. `crate::EPSILON` is below the
+ * precision of an `f32` so a larger value is needed.
+ */
+const F32_EPSILON: f32 = 1e-5;
+
+/**
+ * Perturbation amount for `f32`, see `crate::PERTURB_AMT`
+ */
+const F32_PERTURB_AMT: f32 = 1e-5;
+
+/**
+ * Residual tolerances for the floating point types. These leave a few
+ * digits of room above the unit roundoff for the growth in an LU
+ * factorization.
+ */
+const F32_RESIDUAL_TOLERANCE: f64 = 1e-5;
+const F64_RESIDUAL_TOLERANCE: f64 = 1e-12;
+
+/**
+ * The number type that the simplex routines are computed with.
+ * Implemented for `f32`, `f64`, `DoubleDouble` and `Rational`, in
+ * order of increasing precision (and decreasing speed).
+ */
+pub trait Scalar:
+    na::Scalar
+    + PartialOrd
+    + Zero
+    + One
+    + Neg<Output = Self>
+    + ClosedAdd
+    + ClosedSub
+    + ClosedMul
+    + ClosedDiv
+    + Serialize
+{
+    /**
+     * Whether arithmetic with this type is exact. Exact types don't
+     * need any of the floating point workarounds in the solvers.
+     */
+    const EXACT: bool;
+
+    /**
+     * Comparison epsilon, see `crate::EPSILON`
+     */
+    fn epsilon() -> Self;
+
+    /**
+     * Perturbation amount, see `crate::PERTURB_AMT`
+     */
+    fn perturb_amt() -> Self;
+
+    /**
+     * Largest relative residual accepted from a linear solve
+     * before it is refined (see `solve::solve_refined`)
+     */
+    fn residual_tolerance() -> f64;
+
+    /**
+     * Parse a number from the input file
+     */
+    fn parse(s: &str) -> Result<Self, String>;
+
+    fn to_f64(&self) -> f64;
+
+    /**
+     * The format used for the results when no `--precision` is given
+     */
+    const DEFAULT_FORMAT: NumberFormat;
+
+    /**
+     * Format a number for the results
+     */
+    fn format(&self, format: NumberFormat) -> String;
+
+    fn abs(&self) -> Self;
+
+    /**
+     * Solve the square system `M * x = rhs`, returning `None` if `M` is
+     * singular. The default implementation is Gaussian elimination with
+     * partial pivoting.
+     */
+    fn solve(M: &Matrix<Self>, rhs: &Vector<Self>) -> Option<Vector<Self>> {
+        let n = M.nrows();
+        let mut M = M.clone_owned();
+        let mut x = rhs.clone_owned();
+
+        for k in 0..n {
+            let p = (k..n)
+                .max_by(|a, b| {
+                    M[(*a, k)].abs().partial_cmp(&M[(*b, k)].abs()).unwrap()
+                })
+                .filter(|p| !M[(*p, k)].is_zero())?;
+
+            M.swap_rows(k, p);
+            x.swap_rows(k, p);
+
+            for i in k + 1..n {
+                if M[(i, k)].is_zero() {
+                    continue;
+                }
+
+                let factor = M[(i, k)].clone() / M[(k, k)].clone();
+                for j in k..n {
+                    let delta = factor.clone() * M[(k, j)].clone();
+                    M[(i, j)] -= delta;
+                }
+                let delta = factor * x[k].clone();
+                x[i] -= delta;
+            }
+        }
+
+        for k in (0..n).rev() {
+            let mut sum = x[k].clone();
+            for j in k + 1..n {
+                sum -= M[(k, j)].clone() * x[j].clone();
+            }
+            x[k] = sum / M[(k, k)].clone();
+        }
+
+        Some(x)
+    }
+}
+
+impl Scalar for f64 {
+    const EXACT: bool = false;
+    const DEFAULT_FORMAT: NumberFormat = NumberFormat::SigFigs(PRINT_SIG_FIGS);
+
+    fn epsilon() -> Self {
+        EPSILON
+    }
+
+    fn perturb_amt() -> Self {
+        PERTURB_AMT
+    }
+
+    fn residual_tolerance() -> f64 {
+        F64_RESIDUAL_TOLERANCE
+    }
+
+    fn parse(s: &str) -> Result<Self, String> {
+        s.parse::<f64>()
+            .map_err(|e| format!("Invalid number '{}': {}", s, e))
+    }
+
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+
+    fn format(&self, format: NumberFormat) -> String {
+        format_f64(*self, format)
+    }
+
+    fn abs(&self) -> Self {
+        f64::abs(*self)
+    }
+
+    fn solve(M: &Matrix<Self>, rhs: &Vector<Self>) -> Option<Vector<Self>> {
+        M.clone_owned().lu().solve(rhs)
+    }
+}
+
+impl Scalar for f32 {
+    const EXACT: bool = false;
+    const DEFAULT_FORMAT: NumberFormat = NumberFormat::SigFigs(PRINT_SIG_FIGS);
+
+    fn epsilon() -> Self {
+        F32_EPSILON
+    }
+
+    fn perturb_amt() -> Self {
+        F32_PERTURB_AMT
+    }
+
+    fn residual_tolerance() -> f64 {
+        F32_RESIDUAL_TOLERANCE
+    }
+
+    fn parse(s: &str) -> Result<Self, String> {
+        s.parse::<f32>()
+            .map_err(|e| format!("Invalid number '{}': {}", s, e))
+    }
+
+    fn to_f64(&self) -> f64 {
+        f64::from(*self)
+    }
+
+    fn format(&self, format: NumberFormat) -> String {
+        format_f64(self.to_f64(), format)
+    }
+
+    fn abs(&self) -> Self {
+        f32::abs(*self)
+    }
+
+    fn solve(M: &Matrix<Self>, rhs: &Vector<Self>) -> Option<Vector<Self>> {
+        M.clone_owned().lu().solve(rhs)
+    }
+}
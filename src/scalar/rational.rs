@@ -0,0 +1,187 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::scalar::{format_f64, NumberFormat, Scalar};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign,
+};
+
+/**
+ * Arbitrary-precision rational number used by the exact solver.
+ * `BigRational` isn't `Copy` so it doesn't get nalgebra's blanket
+ * `Scalar` impl, hence the wrapper.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rational(pub BigRational);
+
+impl na::Scalar for Rational {}
+
+macro_rules! rational_op {
+    ($trait:ident, $fn:ident, $assign_trait:ident, $assign_fn:ident) => {
+        impl $trait for Rational {
+            type Output = Rational;
+
+            fn $fn(self, rhs: Rational) -> Rational {
+                Rational(self.0.$fn(rhs.0))
+            }
+        }
+
+        impl $assign_trait for Rational {
+            fn $assign_fn(&mut self, rhs: Rational) {
+                self.0.$assign_fn(rhs.0);
+            }
+        }
+    };
+}
+
+rational_op!(Add, add, AddAssign, add_assign);
+rational_op!(Sub, sub, SubAssign, sub_assign);
+rational_op!(Mul, mul, MulAssign, mul_assign);
+rational_op!(Div, div, DivAssign, div_assign);
+
+impl Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        Rational(-self.0)
+    }
+}
+
+impl Zero for Rational {
+    fn zero() -> Self {
+        Rational(BigRational::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+}
+
+impl One for Rational {
+    fn one() -> Self {
+        Rational(BigRational::one())
+    }
+}
+
+/**
+ * Serialized as a string such as `"5/4"` so no precision is lost
+ */
+impl Serialize for Rational {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Scalar for Rational {
+    const EXACT: bool = true;
+    const DEFAULT_FORMAT: NumberFormat = NumberFormat::Exact;
+
+    fn epsilon() -> Self {
+        Rational::zero()
+    }
+
+    /**
+     * The same perturbation amount as `f64`, represented exactly.
+     * Exact arithmetic can still cycle so the perturbation is still
+     * needed, but the solvers recompute the final solution from the
+     * unperturbed problem.
+     */
+    fn perturb_amt() -> Self {
+        Rational(BigRational::new(BigInt::one(), BigInt::from(1_000_000_000)))
+    }
+
+    /**
+     * Solves are exact so there is never a residual
+     */
+    fn residual_tolerance() -> f64 {
+        0.0
+    }
+
+    /**
+     * Parse a decimal number such as `-12.5` or `3e-2`
+     * (or a fraction such as `1/3`) into an exact rational
+     */
+    fn parse(s: &str) -> Result<Self, String> {
+        let err = || format!("Invalid number '{}'", s);
+
+        if let Some((num, den)) = s.split_once('/') {
+            let num = num.parse::<BigInt>().map_err(|_| err())?;
+            let den = den.parse::<BigInt>().map_err(|_| err())?;
+            if den.is_zero() {
+                return Err(err());
+            }
+            return Ok(Rational(BigRational::new(num, den)));
+        }
+
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(idx) => {
+                (&s[..idx], s[idx + 1..].parse::<i32>().map_err(|_| err())?)
+            }
+            None => (s, 0),
+        };
+
+        let (int_part, frac_part) =
+            mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits = format!("{}{}", int_part, frac_part);
+        if digits.is_empty() || digits == "-" || digits == "+" {
+            return Err(err());
+        }
+
+        let num = digits.parse::<BigInt>().map_err(|_| err())?;
+        let exponent = exponent - frac_part.len() as i32;
+        let pow =
+            BigRational::from_integer(BigInt::from(10)).pow(exponent.abs());
+
+        Ok(Rational(if exponent >= 0 {
+            BigRational::from_integer(num) * pow
+        } else {
+            BigRational::from_integer(num) / pow
+        }))
+    }
+
+    fn to_f64(&self) -> f64 {
+        ToPrimitive::to_f64(&self.0).unwrap_or(f64::NAN)
+    }
+
+    /**
+     * Prints exact fractions by default, the other formats go
+     * through the nearest `f64`
+     */
+    fn format(&self, format: NumberFormat) -> String {
+        match format {
+            NumberFormat::Exact => format!("{}", self),
+            _ => format_f64(self.to_f64(), format),
+        }
+    }
+
+    fn abs(&self) -> Self {
+        Rational(Signed::abs(&self.0))
+    }
+}
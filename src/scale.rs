@@ -0,0 +1,270 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::solve::{Solution, SolveResult};
+use crate::{Matrix, Vector};
+use std::str::FromStr;
+
+/**
+ * Maximum number of geometric mean passes. The scaling usually
+ * converges within a handful of passes, this is just a safety net.
+ */
+const GEOMETRIC_MAX_PASSES: usize = 15;
+
+/**
+ * Stop the geometric mean passes once a pass fails to improve
+ * the max/min coefficient ratio by at least this factor.
+ */
+const GEOMETRIC_MIN_IMPROVEMENT: f64 = 0.9;
+
+/**
+ * The scaling methods that can be applied to the
+ * constraint matrix before solving
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scaling {
+    None,
+    Geometric,
+    Equilibrate,
+    Both,
+}
+
+impl FromStr for Scaling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Scaling::None),
+            "geometric" => Ok(Scaling::Geometric),
+            "equilibrate" => Ok(Scaling::Equilibrate),
+            "both" => Ok(Scaling::Both),
+            _ => Err(format!(
+                "Unknown scaling method '{}' (expected none, geometric, \
+                 equilibrate or both)",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * Row and column scale factors for a linear program. The scaled
+ * problem is `R A S`, `R b` and `S c` where `R` and `S` are the
+ * diagonal matrices formed from `row` and `col`.
+ */
+pub struct ScaleFactors {
+    row: Vec<f64>,
+    col: Vec<f64>,
+}
+
+impl ScaleFactors {
+    /**
+     * Compute the scale factors for the first `n` (structural) columns
+     * of `A`. The remaining columns are the slack identity and are
+     * scaled by the reciprocal of their row factor so they stay an
+     * identity after scaling.
+     */
+    pub fn compute(A: &Matrix, n: usize, scaling: Scaling) -> Self {
+        let mut factors = ScaleFactors {
+            row: vec![1.0; A.nrows()],
+            col: vec![1.0; n],
+        };
+
+        if scaling == Scaling::Geometric || scaling == Scaling::Both {
+            factors.geometric(A);
+        }
+
+        if scaling == Scaling::Equilibrate || scaling == Scaling::Both {
+            factors.equilibrate(A);
+        }
+
+        // Rounding the factors to powers of two means that applying
+        // and undoing the scaling does not introduce any rounding error
+        factors.row.iter_mut().for_each(|r| *r = round_pow2(*r));
+        factors.col.iter_mut().for_each(|s| *s = round_pow2(*s));
+        factors
+    }
+
+    /**
+     * Returns the scale factor of column `j`, including slack columns
+     */
+    fn col_factor(&self, j: usize) -> f64 {
+        match self.col.get(j) {
+            Some(s) => *s,
+            None => 1.0 / self.row[j - self.col.len()],
+        }
+    }
+
+    /**
+     * The scaled value of the structural coefficient `A[(i, j)]`
+     */
+    fn scaled(&self, A: &Matrix, i: usize, j: usize) -> f64 {
+        (A[(i, j)] * self.row[i] * self.col[j]).abs()
+    }
+
+    /**
+     * Repeatedly divide each row and then each column by the geometric
+     * mean of its largest and smallest nonzero coefficient.
+     */
+    fn geometric(&mut self, A: &Matrix) {
+        let m = self.row.len();
+        let n = self.col.len();
+        let mut prev_ratio = self.ratio(A);
+
+        for _ in 0..GEOMETRIC_MAX_PASSES {
+            for i in 0..m {
+                if let Some((min, max)) =
+                    min_max((0..n).map(|j| self.scaled(A, i, j)))
+                {
+                    self.row[i] /= (min * max).sqrt();
+                }
+            }
+
+            for j in 0..n {
+                if let Some((min, max)) =
+                    min_max((0..m).map(|i| self.scaled(A, i, j)))
+                {
+                    self.col[j] /= (min * max).sqrt();
+                }
+            }
+
+            let ratio = self.ratio(A);
+            if ratio > GEOMETRIC_MIN_IMPROVEMENT * prev_ratio {
+                break;
+            }
+            prev_ratio = ratio;
+        }
+    }
+
+    /**
+     * Divide each row and then each column by its largest
+     * absolute coefficient.
+     */
+    fn equilibrate(&mut self, A: &Matrix) {
+        let m = self.row.len();
+        let n = self.col.len();
+
+        for i in 0..m {
+            if let Some((_, max)) =
+                min_max((0..n).map(|j| self.scaled(A, i, j)))
+            {
+                self.row[i] /= max;
+            }
+        }
+
+        for j in 0..n {
+            if let Some((_, max)) =
+                min_max((0..m).map(|i| self.scaled(A, i, j)))
+            {
+                self.col[j] /= max;
+            }
+        }
+    }
+
+    /**
+     * Ratio between the largest and smallest nonzero
+     * structural coefficients of the scaled matrix
+     */
+    pub fn ratio(&self, A: &Matrix) -> f64 {
+        let n = self.col.len();
+        let values = (0..self.row.len())
+            .flat_map(|i| (0..n).map(move |j| (i, j)))
+            .map(|(i, j)| self.scaled(A, i, j));
+
+        match min_max(values) {
+            Some((min, max)) => max / min,
+            None => 1.0,
+        }
+    }
+
+    /**
+     * Build the scaled `A`, `b` and `c`
+     */
+    pub fn apply(
+        &self,
+        A: &Matrix,
+        b: &Vector,
+        c: &Vector,
+    ) -> (Matrix, Vector, Vector) {
+        let A = Matrix::from_fn(A.nrows(), A.ncols(), |i, j| {
+            A[(i, j)] * self.row[i] * self.col_factor(j)
+        });
+        let b = Vector::from_fn(b.len(), |i, _| b[i] * self.row[i]);
+        let c = Vector::from_fn(c.len(), |j, _| c[j] * self.col_factor(j));
+        (A, b, c)
+    }
+
+    /**
+     * Map a solution of the scaled problem back to the original
+     * problem. The objective value is unaffected by scaling.
+     */
+    pub fn unscale(&self, result: SolveResult) -> SolveResult {
+        match result {
+            SolveResult::Optimal(solution) => {
+                SolveResult::Optimal(self.unscale_solution(solution))
+            }
+            SolveResult::Feasible(solution, gap) => {
+                SolveResult::Feasible(self.unscale_solution(solution), gap)
+            }
+            other => other,
+        }
+    }
+
+    fn unscale_solution(&self, mut solution: Solution) -> Solution {
+        solution
+            .variable_values
+            .iter_mut()
+            .zip(self.col.iter())
+            .for_each(|(x, s)| *x *= s);
+        solution
+            .dual_values
+            .iter_mut()
+            .zip(self.row.iter())
+            .for_each(|(y, r)| *y *= r);
+        solution
+            .reduced_costs
+            .iter_mut()
+            .zip(self.col.iter())
+            .for_each(|(z, s)| *z /= s);
+        solution
+    }
+
+    /**
+     * Leave the given columns unscaled. Integer variables must keep
+     * their original scale so that integrality is preserved.
+     */
+    pub fn exclude(&mut self, cols: &[usize]) {
+        cols.iter().for_each(|j| self.col[*j] = 1.0);
+    }
+}
+
+/**
+ * Smallest and largest value from a list of absolute
+ * values, ignoring zeros
+ */
+fn min_max(values: impl Iterator<Item = f64>) -> Option<(f64, f64)> {
+    values.filter(|v| *v > 0.0).fold(None, |acc, v| match acc {
+        None => Some((v, v)),
+        Some((min, max)) => Some((min.min(v), max.max(v))),
+    })
+}
+
+/**
+ * Round a positive value to the nearest power of two
+ */
+fn round_pow2(value: f64) -> f64 {
+    2_f64.powi(value.log2().round() as i32)
+}
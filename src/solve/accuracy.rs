@@ -0,0 +1,147 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::scalar::Scalar;
+use crate::util::{mul, norm_inf, transpose};
+use crate::{Matrix, Vector};
+
+/**
+ * Maximum number of iterative refinement steps for a single solve
+ */
+const REFINEMENT_MAX_STEPS: usize = 3;
+
+/**
+ * Maximum number of iterations of Hager's condition estimator. It
+ * almost always converges in 2 or 3.
+ */
+const CONDITION_MAX_ITERATIONS: usize = 5;
+
+/**
+ * Solve `M * x = rhs` and check the residual. If the relative residual
+ * is above the tolerance for `T` a few steps of iterative refinement
+ * are applied, solving `M * d = rhs - M * x` and setting `x = x + d`.
+ * Along with the solution, returns whether the residual ended up
+ * within the tolerance.
+ */
+pub fn solve_refined<T: Scalar>(
+    M: &Matrix<T>,
+    rhs: &Vector<T>,
+) -> Option<(Vector<T>, bool)> {
+    let mut x = T::solve(M, rhs)?;
+    if T::EXACT {
+        return Some((x, true));
+    }
+
+    for _ in 0..REFINEMENT_MAX_STEPS {
+        if relative_residual(M, &x, rhs) <= T::residual_tolerance() {
+            return Some((x, true));
+        }
+
+        let r = rhs.clone_owned() - mul(M, &x);
+        x += T::solve(M, &r)?;
+    }
+
+    let accurate = relative_residual(M, &x, rhs) <= T::residual_tolerance();
+    Some((x, accurate))
+}
+
+/**
+ * Compute `||rhs - M * x|| / (||M|| * ||x|| + ||rhs||)` using the
+ * infinity norm. This is small whenever `x` is the exact solution
+ * of a nearby system, regardless of how well conditioned `M` is.
+ */
+pub fn relative_residual<T: Scalar>(
+    M: &Matrix<T>,
+    x: &Vector<T>,
+    rhs: &Vector<T>,
+) -> f64 {
+    let r = rhs.clone_owned() - mul(M, x);
+    let M_norm = M
+        .row_iter()
+        .map(|row| row.iter().map(|v| v.to_f64().abs()).sum::<f64>())
+        .fold(0.0, f64::max);
+    let scale = M_norm * norm_inf(x) + norm_inf(rhs);
+
+    if scale == 0.0 {
+        0.0
+    } else {
+        norm_inf(&r) / scale
+    }
+}
+
+/**
+ * Estimate the 1-norm condition number `||M|| * ||M^-1||` using
+ * Hager's method, which finds a lower bound on `||M^-1||` from a
+ * few solves with `M` and `M^T` instead of forming the inverse.
+ * Returns `None` if `M` is singular.
+ */
+pub fn condition_estimate<T: Scalar>(M: &Matrix<T>) -> Option<f64> {
+    let n = M.nrows();
+    if n == 0 {
+        return Some(0.0);
+    }
+
+    let M_T = transpose(M);
+    let n_T = (0..n).fold(T::zero(), |acc, _| acc + T::one());
+    let mut x = Vector::from_element(n, T::one() / n_T);
+    let mut inv_norm = 0.0;
+
+    for _ in 0..CONDITION_MAX_ITERATIONS {
+        let y = T::solve(M, &x)?;
+        inv_norm = y.iter().map(|v| v.to_f64().abs()).sum();
+
+        let signs = Vector::from_iterator(
+            n,
+            y.iter()
+                .map(|v| if *v < T::zero() { -T::one() } else { T::one() }),
+        );
+        let z = T::solve(&M_T, &signs)?;
+
+        let (j, z_j) = z
+            .iter()
+            .map(|v| v.to_f64().abs())
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())?;
+
+        if z_j <= z.dot(&x).to_f64() {
+            break;
+        }
+
+        x = Vector::zeros(n);
+        x[j] = T::one();
+    }
+
+    let M_norm = M
+        .column_iter()
+        .map(|col| col.iter().map(|v| v.to_f64().abs()).sum::<f64>())
+        .fold(0.0, f64::max);
+    Some(M_norm * inv_norm)
+}
+
+/**
+ * Error message for when the solves with the basis `A_B` are
+ * no longer accurate enough to trust
+ */
+pub fn lost_accuracy<T: Scalar>(A_B: &Matrix<T>) -> String {
+    match condition_estimate(A_B) {
+        Some(condition) => format!(
+            "Lost numerical accuracy in the basis \
+             (condition number estimate {:.1e})",
+            condition
+        ),
+        None => String::from("Lost numerical accuracy in the basis"),
+    }
+}
@@ -0,0 +1,256 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::solve::{
+    dual, gomory, simplex, Cuts, Monitor, Solution, SolveResult,
+};
+use crate::util::add_rows;
+use crate::{Matrix, Vector};
+use std::time::{Duration, Instant};
+
+/**
+ * A value within this distance of an integer is considered integral
+ */
+pub const INTEGRALITY_TOLERANCE: f64 = 1e-6;
+
+/**
+ * Nodes whose relaxation can't beat the incumbent by at least
+ * this much are pruned
+ */
+const PRUNE_TOLERANCE: f64 = 1e-9;
+
+/**
+ * Limits on the branch and bound search. When a limit is hit the
+ * best integer solution found so far is reported along with its gap.
+ */
+#[derive(Default)]
+pub struct Limits {
+    pub nodes: Option<usize>,
+    pub time: Option<Duration>,
+}
+
+/**
+ * A bound on a single variable added by branching. Bounds are added
+ * to the problem as extra rows, `x_j <= value` when `upper` is set
+ * and `x_j >= value` otherwise.
+ */
+#[derive(Clone)]
+struct Branch {
+    var: usize,
+    upper: bool,
+    value: f64,
+}
+
+/**
+ * A node of the search tree along with the optimal
+ * solution of its LP relaxation
+ */
+struct Node {
+    branches: Vec<Branch>,
+    relaxation: Solution,
+}
+
+/**
+ * Solve the linear program with the variables in `integer` restricted
+ * to integer values. Nodes are explored depth first, and each child is
+ * warm started with the dual simplex from its parent's optimal basis.
+ * When `cuts` is `GomoryBranch` rounds of Gomory cuts are added to the
+ * root relaxation before branching.
+ */
+#[allow(clippy::too_many_arguments)]
+pub fn branch_and_bound(
+    A: &Matrix,
+    b: &Vector,
+    c: &Vector,
+    integer: &[usize],
+    limits: &Limits,
+    cuts: Cuts,
+    no_perturb: bool,
+    monitor: &mut Monitor,
+) -> Result<SolveResult, String> {
+    let root = match simplex(A, b, c, no_perturb, monitor)? {
+        SolveResult::Optimal(solution) => solution,
+        other => return Ok(other),
+    };
+
+    let (A, b, c, root) = if cuts == Cuts::GomoryBranch {
+        let cut = gomory(A, b, c, integer, root, monitor)?;
+        match cut.result {
+            SolveResult::Optimal(root) => (cut.A, cut.b, cut.c, root),
+            other => return Ok(other),
+        }
+    } else {
+        (A.clone_owned(), b.clone_owned(), c.clone_owned(), root)
+    };
+
+    search(&A, &b, &c, root, integer, limits, monitor)
+}
+
+/**
+ * Run the branch and bound search from the optimal solution `root` of
+ * the relaxation `A`, `b`, `c`, which may have rows added to the
+ * original problem
+ */
+pub(crate) fn search(
+    A: &Matrix,
+    b: &Vector,
+    c: &Vector,
+    root: Solution,
+    integer: &[usize],
+    limits: &Limits,
+    monitor: &mut Monitor,
+) -> Result<SolveResult, String> {
+    let start = Instant::now();
+    let mut pivots = root.pivots;
+    let mut nodes = 0;
+    let mut incumbent: Option<Solution> = None;
+    let mut stack = vec![Node {
+        branches: Vec::new(),
+        relaxation: root,
+    }];
+
+    while let Some(node) = stack.pop() {
+        let limit_hit = limits.nodes.is_some_and(|l| nodes >= l)
+            || limits.time.is_some_and(|l| start.elapsed() >= l);
+
+        if limit_hit {
+            stack.push(node);
+            break;
+        }
+
+        nodes += 1;
+
+        if let Some(inc) = &incumbent {
+            if node.relaxation.objective_value
+                <= inc.objective_value + PRUNE_TOLERANCE
+            {
+                continue;
+            }
+        }
+
+        let j = match most_fractional(&node.relaxation, integer) {
+            Some(j) => j,
+            None => {
+                incumbent = Some(node.relaxation);
+                continue;
+            }
+        };
+
+        // Push the down branch last so it is explored first
+        let value = node.relaxation.variable_values[j];
+        for (upper, value) in &[(false, value.ceil()), (true, value.floor())] {
+            let mut branches = node.branches.clone();
+            branches.push(Branch {
+                var: j,
+                upper: *upper,
+                value: *value,
+            });
+
+            let (A, b, c) = with_branches(A, b, c, &branches);
+
+            // The parent basis plus the new slack is dual feasible
+            let mut B = node.relaxation.B.clone();
+            B.push(A.ncols() - 1);
+            let N = node.relaxation.N.clone();
+
+            match dual(&A, &b, &c, B, N, true, monitor)? {
+                SolveResult::Optimal(relaxation) => {
+                    pivots += relaxation.pivots;
+                    stack.push(Node {
+                        branches,
+                        relaxation,
+                    });
+                }
+                SolveResult::Stopped => return Ok(SolveResult::Stopped),
+                _ => {}
+            }
+        }
+    }
+
+    let mut incumbent = match incumbent {
+        Some(inc) => inc,
+        None if stack.is_empty() => return Ok(SolveResult::Infeasible),
+        // A limit was reached before an integer solution was found
+        None => return Ok(SolveResult::Stopped),
+    };
+
+    incumbent.pivots = pivots;
+    clear_duals(&mut incumbent);
+
+    if stack.is_empty() {
+        return Ok(SolveResult::Optimal(incumbent));
+    }
+
+    let bound = stack
+        .iter()
+        .map(|node| node.relaxation.objective_value)
+        .fold(incumbent.objective_value, f64::max);
+    // Relative to the incumbent, falling back to the absolute gap when
+    // the incumbent is close to zero
+    let gap = 100.0 * (bound - incumbent.objective_value)
+        / incumbent.objective_value.abs().max(1.0);
+
+    Ok(SolveResult::Feasible(incumbent, gap))
+}
+
+/**
+ * Select the integer variable whose value is furthest from
+ * an integer, or `None` if the solution is integral
+ */
+fn most_fractional(solution: &Solution, integer: &[usize]) -> Option<usize> {
+    integer
+        .iter()
+        .map(|j| {
+            let v = solution.variable_values[*j];
+            (*j, (v - v.round()).abs())
+        })
+        .filter(|(_, frac)| *frac > INTEGRALITY_TOLERANCE)
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(j, _)| j)
+}
+
+/**
+ * Add a bound row to the problem for each branch
+ */
+fn with_branches(
+    A: &Matrix,
+    b: &Vector,
+    c: &Vector,
+    branches: &[Branch],
+) -> (Matrix, Vector, Vector) {
+    let k = branches.len();
+    let mut rows = Matrix::zeros(k, A.ncols());
+    let mut rhs = Vector::zeros(k);
+
+    branches.iter().enumerate().for_each(|(i, branch)| {
+        let sign = if branch.upper { 1.0 } else { -1.0 };
+        rows[(i, branch.var)] = sign;
+        rhs[i] = sign * branch.value;
+    });
+
+    add_rows(A, b, c, &rows, &rhs)
+}
+
+/**
+ * Remove the dual values and reduced costs from an integer solution.
+ * They come from the LP at the node where the solution was found,
+ * whose branching rows and cuts aren't part of the original problem,
+ * so they don't mean anything for the integer problem.
+ */
+pub(crate) fn clear_duals(solution: &mut Solution) {
+    solution.dual_values.clear();
+    solution.reduced_costs.clear();
+}
@@ -0,0 +1,235 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::solve::{dual, primal, simplex, Monitor, Solution, SolveResult};
+use crate::util::{col_view, row_view, select_leaving, write_view};
+use crate::{Matrix, Vector, EPSILON};
+
+/**
+ * Interior point values smaller than this are treated as
+ * already sitting at their bound of zero.
+ */
+const CROSSOVER_ZERO: f64 = 1e-7;
+
+/**
+ * Convert an interior point solution into an optimal basic solution.
+ *
+ * A basis is first guessed from the columns with the largest values
+ * in the interior solution. Every nonbasic variable which is still
+ * away from its bound is then pushed to zero, pivoting it into the
+ * basis whenever a basic variable reaches zero first. Then every
+ * basic variable with a nonzero reduced cost has it pushed to zero in
+ * the same way, pivoting out of the basis whenever a nonbasic reduced
+ * cost reaches zero first. The resulting basis is finished off with
+ * the primal simplex if it's primal feasible and the dual simplex if
+ * it's dual feasible, which normally only needs a few pivots. If
+ * numerical trouble leaves it neither, the problem is solved again
+ * from the slack basis.
+ */
+pub fn crossover(
+    A: &Matrix,
+    b: &Vector,
+    c: &Vector,
+    solution: &Solution,
+    no_perturb: bool,
+    monitor: &mut Monitor,
+) -> Result<SolveResult, String> {
+    let m = A.nrows();
+    let n = A.ncols() - m;
+
+    // Recover the slack values from the structural ones
+    let mut x = Vector::zeros(n + m);
+    solution
+        .variable_values
+        .iter()
+        .enumerate()
+        .for_each(|(j, v)| x[j] = v.max(0.0));
+    let slacks = b - A.columns(0, n) * x.rows(0, n);
+    slacks
+        .iter()
+        .enumerate()
+        .for_each(|(i, v)| x[n + i] = v.max(0.0));
+
+    let mut B = initial_basis(A, &x);
+    let mut N: Vec<usize> = (0..n + m).filter(|j| !B.contains(j)).collect();
+
+    // Push each superbasic variable to zero
+    let mut pushes = 0;
+    let superbasic: Vec<usize> = N
+        .iter()
+        .copied()
+        .filter(|j| x[*j] > CROSSOVER_ZERO)
+        .collect();
+
+    for j in superbasic {
+        // Compute delta_x_B by solving A_B * delta_x_B = Aj. Decreasing
+        // x_j by t increases x_B by t * delta_x_B
+        let delta_x_B = col_view(A, &B)
+            .lu()
+            .solve(&A.column(j))
+            .ok_or_else(|| String::from("Failed to solve for delta_x_B"))?;
+
+        let mut delta_x = Vector::zeros(n + m);
+        write_view(&mut delta_x, &(-&delta_x_B), &B);
+
+        let t = match select_leaving(&B, &x, &delta_x) {
+            Some((t, i, i_idx)) if t < x[j] => {
+                let j_idx = N.iter().position(|v| *v == j).unwrap();
+                B[i_idx] = j;
+                N[j_idx] = i;
+                pushes += 1;
+                t
+            }
+            _ => x[j],
+        };
+
+        let x_B = row_view(&x, &B) + t * delta_x_B;
+        write_view(&mut x, &x_B, &B);
+        x[j] -= t;
+        x.iter_mut().for_each(|v| *v = v.max(0.0));
+    }
+
+    // Push each basic variable's reduced cost to zero, starting from the
+    // interior reduced costs z = A^T v - c
+    let v = Vector::from_column_slice(&solution.dual_values);
+    let mut z = A.transpose() * v - c;
+    z.iter_mut().for_each(|v| *v = v.max(0.0));
+    let dual_superbasic: Vec<usize> = B
+        .iter()
+        .copied()
+        .filter(|j| z[*j] > CROSSOVER_ZERO)
+        .collect();
+
+    for j in dual_superbasic {
+        // Moving v by -t * dv where A_B^T * dv = e_j decreases z_j by t,
+        // leaves the other basic reduced costs at zero and decreases
+        // z_N by t * A_N^T * dv
+        let j_idx = B.iter().position(|v| *v == j).unwrap();
+        let mut e = Vector::zeros(m);
+        e[j_idx] = 1.0;
+        let dv = col_view(A, &B)
+            .transpose()
+            .lu()
+            .solve(&e)
+            .ok_or_else(|| String::from("Failed to solve for dv"))?;
+        let delta_z = A.transpose() * dv;
+
+        let entering = N
+            .iter()
+            .enumerate()
+            .filter(|(_, k)| delta_z[**k] > EPSILON)
+            .map(|(k_idx, k)| (z[*k] / delta_z[*k], k_idx, *k))
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let t = match entering {
+            Some((t, k_idx, k)) if t < z[j] => {
+                B[j_idx] = k;
+                N[k_idx] = j;
+                pushes += 1;
+                t
+            }
+            _ => z[j],
+        };
+
+        z -= t * delta_z;
+        z.iter_mut().for_each(|v| *v = v.max(0.0));
+    }
+
+    let A_B = col_view(A, &B);
+    let x_B = A_B
+        .clone()
+        .lu()
+        .solve(b)
+        .ok_or_else(|| String::from("Crossover basis is singular"))?;
+
+    let result = if !(x_B.min() < -EPSILON) {
+        primal(A, b, c, B, N, no_perturb, monitor)?
+    } else if dual_feasible(A, c, &A_B, &B, &N)? {
+        dual(A, b, c, B, N, no_perturb, monitor)?
+    } else {
+        return simplex(A, b, c, no_perturb, monitor);
+    };
+
+    Ok(match result {
+        SolveResult::Optimal(mut s) => {
+            s.pivots += pushes;
+            SolveResult::Optimal(s)
+        }
+        other => other,
+    })
+}
+
+/**
+ * Whether the basis `B` has nonnegative reduced costs
+ */
+fn dual_feasible(
+    A: &Matrix,
+    c: &Vector,
+    A_B: &Matrix,
+    B: &[usize],
+    N: &[usize],
+) -> Result<bool, String> {
+    let v = A_B
+        .transpose()
+        .lu()
+        .solve(&row_view(c, B))
+        .ok_or_else(|| String::from("Crossover basis is singular"))?;
+    let z_N = col_view(A, N).transpose() * v - row_view(c, N);
+
+    Ok(!(z_N.min() < -EPSILON))
+}
+
+/**
+ * Choose a starting basis from the linearly independent columns
+ * with the largest values in `x`, completing it with slack columns
+ * when there are not enough of them.
+ */
+fn initial_basis(A: &Matrix, x: &Vector) -> Vec<usize> {
+    let m = A.nrows();
+    let n = A.ncols() - m;
+
+    let mut candidates: Vec<usize> =
+        (0..n + m).filter(|j| x[*j] > CROSSOVER_ZERO).collect();
+    candidates.sort_by(|a, b| x[*b].partial_cmp(&x[*a]).unwrap());
+    candidates.extend(n..n + m);
+
+    // Orthonormal basis for the span of the chosen columns, used to
+    // reject candidates which are dependent on the ones before them.
+    let mut Q: Vec<Vector> = Vec::with_capacity(m);
+    let mut B = Vec::with_capacity(m);
+
+    for j in candidates {
+        if B.len() == m {
+            break;
+        }
+
+        if B.contains(&j) {
+            continue;
+        }
+
+        let col = A.column(j).clone_owned();
+        let mut residual = col.clone();
+        Q.iter().for_each(|q| residual -= q * q.dot(&residual));
+
+        let norm = residual.norm();
+        if norm > EPSILON.sqrt() * col.norm() {
+            Q.push(residual / norm);
+            B.push(j);
+        }
+    }
+
+    B
+}
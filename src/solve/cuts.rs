@@ -0,0 +1,269 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::solve::bb::{clear_duals, search};
+use crate::solve::{
+    dual, simplex, Limits, Monitor, Solution, SolveResult,
+    INTEGRALITY_TOLERANCE,
+};
+use crate::util::{add_rows, col_view};
+use crate::{Matrix, Vector};
+use std::str::FromStr;
+
+/**
+ * Maximum number of rounds of cuts to add
+ */
+const GOMORY_MAX_ROUNDS: usize = 20;
+
+/**
+ * Rows whose basic variable has a fractional part closer than this
+ * to 0 or 1 produce cuts with huge coefficients, so they are skipped.
+ */
+const GOMORY_MIN_FRACTION: f64 = 0.005;
+
+/**
+ * Stop adding cuts once a round improves the bound by less than this
+ */
+const GOMORY_MIN_IMPROVEMENT: f64 = 1e-7;
+
+/**
+ * How cutting planes are used for problems with integer variables.
+ * `Gomory` uses rounds of Gomory cuts on their own without any
+ * branching, and `GomoryBranch` adds the rounds of cuts at the root
+ * before running branch and bound.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cuts {
+    None,
+    Gomory,
+    GomoryBranch,
+}
+
+impl FromStr for Cuts {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Cuts::None),
+            "gomory" => Ok(Cuts::Gomory),
+            "gomory-bb" => Ok(Cuts::GomoryBranch),
+            _ => Err(format!(
+                "Unknown cut mode '{}' (expected none, gomory or gomory-bb)",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * A linear program with cuts added to it, along with the
+ * result of re-optimizing it
+ */
+pub struct CutProblem {
+    pub A: Matrix,
+    pub b: Vector,
+    pub c: Vector,
+    pub result: SolveResult,
+}
+
+/**
+ * Solve the problem with Gomory cutting planes. If the cuts stall
+ * before the relaxation becomes integral, branch and bound finishes
+ * the search from the problem with the cuts added.
+ */
+pub fn cutting_planes(
+    A: &Matrix,
+    b: &Vector,
+    c: &Vector,
+    integer: &[usize],
+    limits: &Limits,
+    no_perturb: bool,
+    monitor: &mut Monitor,
+) -> Result<SolveResult, String> {
+    let relaxation = match simplex(A, b, c, no_perturb, monitor)? {
+        SolveResult::Optimal(solution) => solution,
+        other => return Ok(other),
+    };
+
+    let cut = gomory(A, b, c, integer, relaxation, monitor)?;
+    let mut solution = match cut.result {
+        SolveResult::Optimal(solution) => solution,
+        other => return Ok(other),
+    };
+
+    if !is_integral(&solution, integer) {
+        return search(
+            &cut.A, &cut.b, &cut.c, solution, integer, limits, monitor,
+        );
+    }
+
+    clear_duals(&mut solution);
+    Ok(SolveResult::Optimal(solution))
+}
+
+/**
+ * Add rounds of Gomory mixed-integer cuts to the problem, starting from
+ * the optimal solution of its LP relaxation. Each round adds one cut
+ * for every integer variable with a fractional value and re-optimizes
+ * with the dual simplex from the previous optimal basis.
+ */
+pub fn gomory(
+    A: &Matrix,
+    b: &Vector,
+    c: &Vector,
+    integer: &[usize],
+    relaxation: Solution,
+    monitor: &mut Monitor,
+) -> Result<CutProblem, String> {
+    let mut A = A.clone_owned();
+    let mut b = b.clone_owned();
+    let mut c = c.clone_owned();
+    let mut relaxation = relaxation;
+    let mut pivots = relaxation.pivots;
+
+    for _ in 0..GOMORY_MAX_ROUNDS {
+        let (rows, rhs) = gomory_cuts(&A, &b, integer, &relaxation)?;
+        if rows.nrows() == 0 {
+            break;
+        }
+
+        let (A_cut, b_cut, c_cut) = add_rows(&A, &b, &c, &rows, &rhs);
+        A = A_cut;
+        b = b_cut;
+        c = c_cut;
+
+        // The previous basis plus the new slacks is dual feasible
+        let mut B = relaxation.B.clone();
+        B.extend(A.ncols() - rows.nrows()..A.ncols());
+        let N = relaxation.N.clone();
+
+        let next = match dual(&A, &b, &c, B, N, true, monitor)? {
+            SolveResult::Optimal(solution) => solution,
+            other => {
+                return Ok(CutProblem {
+                    A,
+                    b,
+                    c,
+                    result: other,
+                })
+            }
+        };
+
+        let improvement = relaxation.objective_value - next.objective_value;
+        pivots += next.pivots;
+        relaxation = next;
+
+        if improvement < GOMORY_MIN_IMPROVEMENT {
+            break;
+        }
+    }
+
+    relaxation.pivots = pivots;
+    Ok(CutProblem {
+        A,
+        b,
+        c,
+        result: SolveResult::Optimal(relaxation),
+    })
+}
+
+/**
+ * Generate a Gomory mixed-integer cut from each tableau row whose basic
+ * variable is integer and fractional. For a row
+ *
+ *   x_i + sum_{j in N} a_j x_j = f0 (mod 1)
+ *
+ * with `f_j` the fractional part of `a_j`, the cut is
+ *
+ *   sum_{j integer, f_j <= f0} f_j / f0 x_j
+ *   + sum_{j integer, f_j > f0} (1 - f_j) / (1 - f0) x_j
+ *   + sum_{j continuous, a_j > 0} a_j / f0 x_j
+ *   - sum_{j continuous, a_j < 0} a_j / (1 - f0) x_j >= 1
+ *
+ * which is returned as a `<=` row by negating both sides. Slack
+ * variables are treated as continuous.
+ */
+fn gomory_cuts(
+    A: &Matrix,
+    b: &Vector,
+    integer: &[usize],
+    relaxation: &Solution,
+) -> Result<(Matrix, Vector), String> {
+    let B = &relaxation.B;
+    let N = &relaxation.N;
+    let A_B = col_view(A, B);
+
+    let x_B = A_B
+        .clone()
+        .lu()
+        .solve(b)
+        .ok_or_else(|| String::from("Failed to solve for x_B"))?;
+    let A_B_T = A_B.transpose().lu();
+
+    let mut cuts: Vec<Vector> = Vec::new();
+
+    for (r, var) in B.iter().enumerate() {
+        if !integer.contains(var) {
+            continue;
+        }
+
+        let f0 = x_B[r] - x_B[r].floor();
+        if !(GOMORY_MIN_FRACTION..=1.0 - GOMORY_MIN_FRACTION).contains(&f0) {
+            continue;
+        }
+
+        // Row r of the tableau is e_r^T A_B^-1 A_N, computed by
+        // solving A_B^T * u = e_r
+        let mut e_r = Vector::zeros(B.len());
+        e_r[r] = 1.0;
+        let u = A_B_T
+            .solve(&e_r)
+            .ok_or_else(|| String::from("Failed to solve for tableau row"))?;
+
+        let mut cut = Vector::zeros(A.ncols());
+        N.iter().for_each(|j| {
+            let a_j = A.column(*j).dot(&u);
+            cut[*j] = if integer.contains(j) {
+                let f_j = a_j - a_j.floor();
+                if f_j <= f0 {
+                    f_j / f0
+                } else {
+                    (1.0 - f_j) / (1.0 - f0)
+                }
+            } else if a_j > 0.0 {
+                a_j / f0
+            } else {
+                -a_j / (1.0 - f0)
+            };
+        });
+
+        cuts.push(-cut);
+    }
+
+    let rows = Matrix::from_fn(cuts.len(), A.ncols(), |i, j| cuts[i][j]);
+    let rhs = Vector::from_element(cuts.len(), -1.0);
+    Ok((rows, rhs))
+}
+
+/**
+ * Whether all of the integer variables have integer values
+ */
+pub fn is_integral(solution: &Solution, integer: &[usize]) -> bool {
+    integer.iter().all(|j| {
+        let v = solution.variable_values[*j];
+        (v - v.round()).abs() <= INTEGRALITY_TOLERANCE
+    })
+}
@@ -14,24 +14,35 @@
  * along with bblp. If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::solve::{Solution, SolveResult};
+use crate::check::debug_check;
+use crate::scalar::Scalar;
+use crate::solve::{
+    condition_estimate, lost_accuracy, solve_refined, Control, Monitor, Phase,
+    Solution, SolveResult, State, DEGENERATE_PIVOTS_BEFORE_BLAND, MAX_PIVOTS,
+};
 use crate::util::{
-    col_view, perturb, row_view, select_entering, select_leaving, write_view,
+    col_view, perturb, row_view, select_entering, select_entering_bland,
+    select_leaving, select_leaving_bland, select_leaving_stable, tr_mul,
+    transpose, write_view,
 };
-use crate::{Matrix, Vector, EPSILON};
+use crate::{Matrix, Vector};
 
-pub fn dual(
-    A: &Matrix,
-    b: &Vector,
-    c: &Vector,
+pub fn dual<T: Scalar>(
+    A: &Matrix<T>,
+    b: &Vector<T>,
+    c: &Vector<T>,
     B: Vec<usize>,
     N: Vec<usize>,
     no_perturb: bool,
-) -> Result<SolveResult, String> {
+    monitor: &mut Monitor<T>,
+) -> Result<SolveResult<T>, String> {
     let mut B = B;
     let mut N = N;
     let n = N.len();
     let m = B.len();
+    let unperturbed_b = b;
+    let phase = Phase::Dual;
+    monitor.start(phase);
 
     // Perturb the `b` vector if that setting is enabled. I've been
     // doing a bit more reading on perturbation since I originally implemented
@@ -52,44 +63,138 @@ pub fn dual(
 
     // Compute z by solving A_B^T * v = c_B then setting z_N = A_N^T * v - c_N
     let mut z = Vector::zeros(m + n);
-    let v = A_B
-        .transpose()
-        .lu()
-        .solve(&c_B)
+    monitor.factorized(phase);
+    let (v, _) = solve_refined(&transpose(&A_B), &c_B)
         .ok_or_else(|| String::from("Failed to solve for v"))?;
-    let z_N = A_N.transpose() * v - c_N;
+    let z_N = tr_mul(&A_N, &v) - c_N;
     write_view(&mut z, &z_N, &N);
 
-    if z_N.min() < -EPSILON {
+    if z_N.iter().any(|v| *v < -T::epsilon()) {
         return Err(String::from("Initial basis is not feasible."));
     }
 
     let mut pivots = 0;
+    let mut stable = false;
+    let mut degenerate_pivots = 0;
+    let mut last_pivot = None;
     loop {
+        if pivots >= MAX_PIVOTS {
+            return Err(format!(
+                "Dual simplex did not finish within {} pivots",
+                MAX_PIVOTS
+            ));
+        }
+        let bland = degenerate_pivots >= DEGENERATE_PIVOTS_BEFORE_BLAND;
+
         let z_B = row_view(&z, &B);
-        let z_N = row_view(&z, &N);
+        let mut z_N = row_view(&z, &N);
         let A_B = col_view(A, &B);
         let A_N = col_view(A, &N);
         let c_B = row_view(c, &B);
+        let c_N = row_view(c, &N);
 
         // Compute x_B by solving A_B * x_B = b
         let mut x = Vector::zeros(m + n);
-        let x_B = col_view(A, &B)
-            .lu()
-            .solve(&b)
+        monitor.factorized(phase);
+        let (x_B, mut accurate) = solve_refined(&A_B, &b)
             .ok_or_else(|| String::from("Failed to solve for x_B"))?;
         write_view(&mut x, &x_B, &B);
 
+        // z_N is updated in place after each pivot, which accumulates
+        // rounding error. Refactor it from scratch once it drifts from
+        // A_N^T * v - c_N where A_B^T * v = c_B.
+        if !T::EXACT {
+            monitor.factorized(phase);
+            let (v, v_accurate) = solve_refined(&transpose(&A_B), &c_B)
+                .ok_or_else(|| String::from("Failed to solve for v"))?;
+            accurate &= v_accurate;
+
+            let refactored = tr_mul(&A_N, &v) - c_N;
+            let drift = (z_N.clone() - refactored.clone())
+                .iter()
+                .map(|d| d.to_f64().abs())
+                .fold(0.0, f64::max);
+            let scale = refactored
+                .iter()
+                .map(|z| z.to_f64().abs())
+                .fold(1.0, f64::max);
+
+            if drift > T::residual_tolerance() * scale {
+                z_N = refactored;
+                write_view(&mut z, &z_N, &N);
+            }
+        }
+
+        if let Some((entering, leaving)) = last_pivot {
+            let control = monitor.after_pivot(
+                phase,
+                State {
+                    A,
+                    c,
+                    x: &x,
+                    z: &z,
+                    B: &B,
+                    N: &N,
+                },
+                entering,
+                leaving,
+            );
+            if control == Control::Stop {
+                return Ok(SolveResult::Stopped);
+            }
+        }
+
         // Select our entering variable using the largest coefficient rule.
         // If there is no suitable entering variable it means we have
         // reached an optimal solution.
-        let (i, i_idx) = match select_entering(&B, &x) {
+        let entering = if bland {
+            select_entering_bland(&B, &x)
+        } else {
+            select_entering(&B, &x)
+        };
+        let (i, i_idx) = match entering {
+            None if !accurate => return Err(lost_accuracy(&A_B)),
             None => {
-                let objective_value = (c_B.transpose() * x_B)[0];
+                // Exact arithmetic has no rounding to hide the
+                // perturbation behind, so recompute x_B from the
+                // original `b` using the optimal basis.
+                let x_B = if T::EXACT && !no_perturb {
+                    monitor.factorized(phase);
+                    let x_B =
+                        T::solve(&A_B, unperturbed_b).ok_or_else(|| {
+                            String::from("Failed to solve for x_B")
+                        })?;
+                    x = Vector::zeros(m + n);
+                    write_view(&mut x, &x_B, &B);
+                    x_B
+                } else {
+                    x_B
+                };
+
+                // The solution was recomputed from the original `b` in
+                // exact arithmetic, otherwise it's for the perturbed `b`
+                let solved_b = if T::EXACT { unperturbed_b } else { &b };
+                debug_check(A, solved_b, c, &x, &B);
+                monitor.finish(
+                    phase,
+                    State {
+                        A,
+                        c,
+                        x: &x,
+                        z: &z,
+                        B: &B,
+                        N: &N,
+                    },
+                );
+
+                let objective_value = c_B.dot(&x_B);
                 return Ok(SolveResult::Optimal(Solution {
-                    variable_values: x.iter().take(n).copied().collect(),
+                    variable_values: x.iter().take(n).cloned().collect(),
+                    dual_values: z.iter().skip(n).cloned().collect(),
+                    reduced_costs: z.iter().take(n).cloned().collect(),
                     objective_value,
                     pivots,
+                    condition: condition_estimate(&A_B),
                     B,
                     N,
                 }));
@@ -98,33 +203,83 @@ pub fn dual(
         };
 
         let mut u = Vector::zeros(z_B.len());
-        u[i_idx] = 1.0;
+        u[i_idx] = T::one();
         let u = u;
 
         // Compute delta_z_N by solving A_B^T * v = u and setting delta_z_N = -A_N^T * v
         let mut delta_z = Vector::zeros(m + n);
-        let v = A_B
-            .transpose()
-            .lu()
-            .solve(&u)
+        monitor.factorized(phase);
+        let (v, v_accurate) = solve_refined(&transpose(&A_B), &u)
             .ok_or_else(|| String::from("Failed to solve for v"))?;
+        accurate &= v_accurate;
 
-        let delta_z_N = -(A_N.transpose() * v);
+        let delta_z_N = -(tr_mul(&A_N, &v));
         write_view(&mut delta_z, &delta_z_N, &N);
 
+        // Once a solve with the basis loses accuracy, switch to the
+        // more stable ratio test (see `primal`)
+        if !accurate {
+            if stable {
+                return Err(lost_accuracy(&A_B));
+            }
+            stable = true;
+        }
+
         // Select our leaving variable. If there is no leaving
         // variable the problem is unbounded (primal infeasible)
-        let (s, j, j_idx) = match select_leaving(&N, &z, &delta_z) {
-            None => return Ok(SolveResult::Infeasible),
+        let leaving = if bland {
+            select_leaving_bland(&N, &z, &delta_z)
+        } else if stable {
+            select_leaving_stable(&N, &z, &delta_z)
+        } else {
+            select_leaving(&N, &z, &delta_z)
+        };
+        let (s, j, j_idx) = match leaving {
+            None => {
+                monitor.finish(
+                    phase,
+                    State {
+                        A,
+                        c,
+                        x: &x,
+                        z: &z,
+                        B: &B,
+                        N: &N,
+                    },
+                );
+                return Ok(SolveResult::Infeasible);
+            }
             Some(p) => p,
         };
 
-        let z_N = z_N.clone_owned() - s * delta_z_N;
+        monitor.pivot(
+            phase,
+            State {
+                A,
+                c,
+                x: &x,
+                z: &z,
+                B: &B,
+                N: &N,
+            },
+            j,
+            i,
+            &s,
+        );
+
+        if s > T::epsilon() {
+            degenerate_pivots = 0;
+        } else {
+            degenerate_pivots += 1;
+        }
+
+        let z_N = z_N - delta_z_N * s.clone();
         write_view(&mut z, &z_N, &N);
 
         z[i] = s;
         B[i_idx] = j;
         N[j_idx] = i;
         pivots += 1;
+        last_pivot = Some((j, i));
     }
 }
@@ -0,0 +1,269 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::solve::{Solution, SolveResult};
+use crate::{Matrix, Vector};
+
+/**
+ * Relative tolerance on the primal residual, dual residual
+ * and duality gap for declaring a point optimal.
+ */
+const IPM_TOLERANCE: f64 = 1e-8;
+
+/**
+ * Once `tau` has shrunk this far relative to `kappa` the
+ * embedding has converged to an infeasibility certificate.
+ */
+const IPM_INFEASIBLE_TOLERANCE: f64 = 1e-9;
+
+const IPM_MAX_ITERATIONS: usize = 200;
+
+/**
+ * Relative amount added to the diagonal of the normal equations.
+ * Close to the solution `X S^-1` has entries going to both zero and
+ * infinity which makes `A D A^T` numerically singular.
+ */
+const REGULARIZATION: f64 = 1e-14;
+
+/**
+ * Fraction of the distance to the boundary that each step may
+ * travel. Stepping all the way to the boundary would make some
+ * of the `x`/`s` values exactly zero.
+ */
+const STEP_FRACTION: f64 = 0.99;
+
+/**
+ * A point in the homogeneous self-dual embedding
+ */
+struct Point {
+    x: Vector,
+    y: Vector,
+    s: Vector,
+    tau: f64,
+    kappa: f64,
+}
+
+/**
+ * A search direction in the homogeneous self-dual embedding
+ */
+struct Direction {
+    x: Vector,
+    y: Vector,
+    s: Vector,
+    tau: f64,
+    kappa: f64,
+}
+
+/**
+ * Solve the linear program with Mehrotra's predictor-corrector
+ * interior point method. The problem is embedded in the homogeneous
+ * self-dual model
+ *
+ *   A x - b tau            = 0
+ *   A^T y + s - c' tau     = 0
+ *   -c'^T x + b^T y - kappa = 0
+ *
+ * where `c' = -c` (the simplex routines maximize, the embedding is
+ * written as a minimization). An optimal solution is recovered as
+ * `x / tau` when `tau` stays positive, and a certificate of primal or
+ * dual infeasibility when `tau` goes to zero instead.
+ *
+ * The resulting `Solution` has no basis, so `B` and `N` are empty.
+ */
+pub fn ipm(A: &Matrix, b: &Vector, c: &Vector) -> Result<SolveResult, String> {
+    let m = A.nrows();
+    let total = A.ncols();
+    let n = total - m;
+    let c = -c;
+
+    let mut p = Point {
+        x: Vector::from_element(total, 1.0),
+        y: Vector::zeros(m),
+        s: Vector::from_element(total, 1.0),
+        tau: 1.0,
+        kappa: 1.0,
+    };
+
+    let b_norm = 1.0 + b.norm();
+    let c_norm = 1.0 + c.norm();
+
+    for _ in 0..IPM_MAX_ITERATIONS {
+        let r_p = b * p.tau - A * &p.x;
+        let r_d = &c * p.tau - A.transpose() * &p.y - &p.s;
+        let r_g = c.dot(&p.x) - b.dot(&p.y) + p.kappa;
+        let mu = (p.x.dot(&p.s) + p.tau * p.kappa) / (total + 1) as f64;
+
+        let primal_obj = c.dot(&p.x) / p.tau;
+        let dual_obj = b.dot(&p.y) / p.tau;
+        let primal_infeas = r_p.norm() / p.tau / b_norm;
+        let dual_infeas = r_d.norm() / p.tau / c_norm;
+        let gap = (primal_obj - dual_obj).abs() / (1.0 + primal_obj.abs());
+
+        if primal_infeas < IPM_TOLERANCE
+            && dual_infeas < IPM_TOLERANCE
+            && gap < IPM_TOLERANCE
+        {
+            return Ok(SolveResult::Optimal(Solution {
+                objective_value: -primal_obj,
+                variable_values: p
+                    .x
+                    .iter()
+                    .take(n)
+                    .map(|x| x / p.tau)
+                    .collect(),
+                dual_values: p.y.iter().map(|y| -y / p.tau).collect(),
+                reduced_costs: p.s.iter().take(n).map(|s| s / p.tau).collect(),
+                pivots: 0,
+                B: Vec::new(),
+                N: Vec::new(),
+                condition: None,
+            }));
+        }
+
+        if p.tau < IPM_INFEASIBLE_TOLERANCE * p.kappa.max(1.0)
+            && mu < IPM_INFEASIBLE_TOLERANCE
+        {
+            // The embedding converged to a ray with b^T y - c'^T x > 0,
+            // A^T y <= 0 and A x = 0. Positive b^T y is a certificate
+            // that the primal has no feasible point. Negative c'^T x
+            // only shows that the dual has none, so the primal is
+            // unbounded if it's feasible and infeasible otherwise. With
+            // a zero objective the dual is always feasible, so solving
+            // that finds out which.
+            let certificate = IPM_TOLERANCE * p.kappa.max(1.0);
+            if b.dot(&p.y) > certificate {
+                return Ok(SolveResult::Infeasible);
+            }
+            if c.dot(&p.x) >= -certificate {
+                return Err(String::from(
+                    "Interior point method converged to neither an \
+                     infeasibility nor an unboundedness certificate",
+                ));
+            }
+            return match ipm(A, b, &Vector::zeros(total))? {
+                SolveResult::Optimal(_) => Ok(SolveResult::Unbounded),
+                other => Ok(other),
+            };
+        }
+
+        // Predictor (affine scaling) step
+        let r_xs = -p.x.component_mul(&p.s);
+        let r_tk = -p.tau * p.kappa;
+        let affine =
+            direction(A, b, &c, &p, 1.0, &r_p, &r_d, r_g, &r_xs, r_tk)?;
+        let alpha_aff = step_length(&p, &affine);
+
+        let mu_aff = ((&p.x + alpha_aff * &affine.x)
+            .dot(&(&p.s + alpha_aff * &affine.s))
+            + (p.tau + alpha_aff * affine.tau)
+                * (p.kappa + alpha_aff * affine.kappa))
+            / (total + 1) as f64;
+        let sigma = (mu_aff / mu).powi(3).min(1.0);
+
+        // Corrector step, with the second order term from the predictor
+        let r_xs =
+            r_xs.add_scalar(sigma * mu) - affine.x.component_mul(&affine.s);
+        let r_tk = r_tk + sigma * mu - affine.tau * affine.kappa;
+        let eta = 1.0 - sigma;
+        let d = direction(A, b, &c, &p, eta, &r_p, &r_d, r_g, &r_xs, r_tk)?;
+        let alpha = (STEP_FRACTION * step_length(&p, &d)).min(1.0);
+
+        p.x += alpha * d.x;
+        p.y += alpha * d.y;
+        p.s += alpha * d.s;
+        p.tau += alpha * d.tau;
+        p.kappa += alpha * d.kappa;
+    }
+
+    Err(String::from("Interior point method did not converge"))
+}
+
+/**
+ * Solve the Newton system of the embedding for the search direction.
+ * The system is reduced to the normal equations `A D A^T dy = rhs`
+ * with `D = X S^-1`, which is solved twice to eliminate `dtau`.
+ */
+#[allow(clippy::too_many_arguments)]
+fn direction(
+    A: &Matrix,
+    b: &Vector,
+    c: &Vector,
+    p: &Point,
+    eta: f64,
+    r_p: &Vector,
+    r_d: &Vector,
+    r_g: f64,
+    r_xs: &Vector,
+    r_tk: f64,
+) -> Result<Direction, String> {
+    let d = p.x.component_div(&p.s);
+    let x_inv_r_xs = r_xs.component_div(&p.x);
+
+    let mut AD = A.clone_owned();
+    AD.column_iter_mut()
+        .zip(d.iter())
+        .for_each(|(mut col, d_j)| col *= *d_j);
+    let mut M = &AD * A.transpose();
+    let delta = REGULARIZATION * (1.0 + M.diagonal().max());
+    (0..M.nrows()).for_each(|i| M[(i, i)] += delta);
+
+    let solve = |rhs: &Vector| -> Result<Vector, String> {
+        match M.clone().cholesky() {
+            Some(chol) => Ok(chol.solve(rhs)),
+            None => M.clone().lu().solve(rhs).ok_or_else(|| {
+                String::from("Failed to solve the normal equations")
+            }),
+        }
+    };
+
+    let p_vec = solve(&(&AD * c + b))?;
+    let q_vec = solve(&(eta * r_p + &AD * (eta * r_d - &x_inv_r_xs)))?;
+
+    let u = (A.transpose() * &p_vec - c).component_mul(&d);
+    let w =
+        (A.transpose() * &q_vec - eta * r_d + &x_inv_r_xs).component_mul(&d);
+
+    let d_tau = (eta * r_g + c.dot(&w) - b.dot(&q_vec) + r_tk / p.tau)
+        / (-c.dot(&u) + b.dot(&p_vec) + p.kappa / p.tau);
+
+    let d_y = p_vec * d_tau + q_vec;
+    let d_x = u * d_tau + w;
+    let d_s = (r_xs - p.s.component_mul(&d_x)).component_div(&p.x);
+    let d_kappa = (r_tk - p.kappa * d_tau) / p.tau;
+
+    Ok(Direction {
+        x: d_x,
+        y: d_y,
+        s: d_s,
+        tau: d_tau,
+        kappa: d_kappa,
+    })
+}
+
+/**
+ * Largest step (capped at 1) along `d` which keeps
+ * `x`, `s`, `tau` and `kappa` nonnegative
+ */
+fn step_length(p: &Point, d: &Direction) -> f64 {
+    p.x.iter()
+        .zip(d.x.iter())
+        .chain(p.s.iter().zip(d.s.iter()))
+        .chain(std::iter::once((&p.tau, &d.tau)))
+        .chain(std::iter::once((&p.kappa, &d.kappa)))
+        .filter(|(_, dv)| **dv < 0.0)
+        .map(|(v, dv)| -v / dv)
+        .fold(1.0, f64::min)
+}
@@ -14,91 +14,314 @@
  * along with bblp. If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::util::round_sig_figs;
+use crate::output::{self, Precision};
+use crate::parse::ParsedLP;
+use crate::scalar::Scalar;
+use crate::scale::{ScaleFactors, Scaling};
+use crate::util::norm_inf;
+use crate::{Matrix, Vector};
+use serde::Serialize;
 use std::fmt;
+use std::str::FromStr;
 
+mod accuracy;
+mod bb;
+mod crossover;
+mod cuts;
 mod dual;
+mod ipm;
+mod monitor;
 mod primal;
 
+pub use accuracy::*;
+pub use bb::*;
+pub use crossover::*;
+pub use cuts::*;
 pub use dual::*;
+pub use ipm::*;
+pub use monitor::*;
 pub use primal::*;
 
 /**
- * Number of significant figures to print in the results.
- * (not the number of sig figs used in the calculations)
+ * Perturbing `b` is optional for the primal simplex and doesn't stop
+ * the dual simplex from cycling through bases with zero reduced costs,
+ * so after this many degenerate pivots in a row both switch to Bland's
+ * rule until the objective moves again
  */
-const PRINT_SIG_FIGS: u32 = 7;
+const DEGENERATE_PIVOTS_BEFORE_BLAND: usize = 50;
+
+/**
+ * Bland's rule can't cycle, but rounding can still stop the simplex
+ * methods from making progress, so they give up after this many pivots
+ */
+const MAX_PIVOTS: usize = 100_000;
 
 /**
  * Represents an optimal solution to a linear program
  */
-pub struct Solution {
-    objective_value: f64,
-    variable_values: Vec<f64>,
+#[derive(Serialize)]
+pub struct Solution<T = f64> {
+    pub objective_value: T,
+    pub variable_values: Vec<T>,
+    /**
+     * The dual values and reduced costs are empty for solutions
+     * to problems with integer variables
+     */
+    pub dual_values: Vec<T>,
+    pub reduced_costs: Vec<T>,
     pub pivots: usize,
     pub B: Vec<usize>,
     pub N: Vec<usize>,
+    /**
+     * Estimated condition number of the optimal basis,
+     * or `None` if the solution didn't come from a basis
+     */
+    pub condition: Option<f64>,
 }
 
 /**
  * The possible outcomes of attempting to run
- * the simplex method on a given linear program
+ * the simplex method on a given linear program.
+ * `Feasible` is only produced by branch and bound when
+ * it stops at a limit, and carries the relative gap (in
+ * percent) between the solution and the best bound.
+ * `Stopped` means the pivot callback stopped the solver, or
+ * branch and bound reached a limit before it found any integer
+ * solution.
  */
-pub enum SolveResult {
+#[derive(Serialize)]
+#[serde(tag = "status", content = "solution", rename_all = "lowercase")]
+pub enum SolveResult<T = f64> {
     Infeasible,
     Unbounded,
-    Optimal(Solution),
+    Optimal(Solution<T>),
+    Feasible(Solution<T>, f64),
+    Stopped,
 }
 
 /**
- * Format the results for submission
+ * The algorithms that can be used to solve a linear program
  */
-impl fmt::Display for SolveResult {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            SolveResult::Infeasible => write!(f, "infeasible"),
-            SolveResult::Unbounded => write!(f, "unbounded"),
-            SolveResult::Optimal(results) => {
-                let x_vals = results
-                    .variable_values
-                    .iter()
-                    .map(|v| format!("{}", round_sig_figs(*v, PRINT_SIG_FIGS)))
-                    .collect::<Vec<String>>()
-                    .join(" ");
-                write!(
-                    f,
-                    "optimal\n{}\n{}",
-                    round_sig_figs(results.objective_value, PRINT_SIG_FIGS),
-                    x_vals
-                )
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Method {
+    Simplex,
+    Ipm,
+}
+
+impl FromStr for Method {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "simplex" => Ok(Method::Simplex),
+            "ipm" => Ok(Method::Ipm),
+            _ => {
+                Err(format!("Unknown method '{}' (expected simplex or ipm)", s))
             }
         }
     }
 }
 
 /**
- * Format the results for debugging
+ * Solve the linear program starting from the slack basis. If the
+ * slack basis is primal feasible the primal simplex is used, if it is
+ * dual feasible the dual simplex is used, and otherwise an auxiliary
+ * problem is solved first to find a dual feasible basis. If the
+ * auxiliary problem is unbounded the problem is either unbounded or
+ * infeasible, and a primal phase 1 decides which.
  */
-impl fmt::Debug for SolveResult {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            SolveResult::Infeasible => write!(f, "infeasible"),
-            SolveResult::Unbounded => write!(f, "unbounded"),
-            SolveResult::Optimal(results) => {
-                let x_vals = results
-                    .variable_values
-                    .iter()
-                    .map(|v| format!("{}", round_sig_figs(*v, PRINT_SIG_FIGS)))
-                    .collect::<Vec<String>>()
-                    .join(" ");
-                write!(
-                    f,
-                    "{} pivots\noptimal\n{}\n{}",
-                    results.pivots,
-                    round_sig_figs(results.objective_value, PRINT_SIG_FIGS),
-                    x_vals
-                )
+pub fn simplex<T: Scalar>(
+    A: &Matrix<T>,
+    b: &Vector<T>,
+    c: &Vector<T>,
+    no_perturb: bool,
+    monitor: &mut Monitor<T>,
+) -> Result<SolveResult<T>, String> {
+    let m = A.nrows();
+    let n = A.ncols() - m;
+    let N: Vec<usize> = (0..n).collect();
+    let B: Vec<usize> = (n..n + m).collect();
+
+    if !b.iter().any(|v| *v < -T::epsilon()) {
+        primal(A, b, c, B, N, no_perturb, monitor)
+    } else if !c.iter().any(|v| *v > T::epsilon()) {
+        dual(A, b, c, B, N, no_perturb, monitor)
+    } else {
+        let zero = Vector::zeros(b.len());
+
+        // Solve the aux problem and feed the results into the
+        // dual solver.
+        monitor.auxiliary = true;
+        let aux_result = primal(A, &zero, c, B, N, no_perturb, monitor);
+        monitor.auxiliary = false;
+
+        match aux_result? {
+            SolveResult::Optimal(aux_solution) => {
+                dual(A, b, c, aux_solution.B, aux_solution.N, true, monitor)
+            }
+            SolveResult::Stopped => Ok(SolveResult::Stopped),
+            _ => {
+                // The auxiliary problem is unbounded, so the problem
+                // has no optimal solution. It's unbounded if it's
+                // feasible and infeasible otherwise.
+                monitor.auxiliary = true;
+                let feasible = feasible(A, b, no_perturb, monitor);
+                monitor.auxiliary = false;
+
+                Ok(match feasible? {
+                    Some(true) => SolveResult::Unbounded,
+                    Some(false) => SolveResult::Infeasible,
+                    None => SolveResult::Stopped,
+                })
             }
         }
     }
 }
+
+/**
+ * Whether `A x = b, x >= 0` has a solution, found with a primal phase 1
+ * which minimizes a single artificial variable `x0` subtracted from
+ * every row. Pivoting `x0` in for the slack of the most negative `b_i`
+ * makes the slack basis feasible, and the problem is feasible if the
+ * primal simplex can then drive `x0` to zero. `None` means the monitor
+ * stopped it.
+ */
+fn feasible<T: Scalar>(
+    A: &Matrix<T>,
+    b: &Vector<T>,
+    no_perturb: bool,
+    monitor: &mut Monitor<T>,
+) -> Result<Option<bool>, String> {
+    let m = A.nrows();
+    let n = A.ncols() - m;
+
+    // The columns are the structural ones, then x0, then the slacks
+    let A = Matrix::from_iterator(
+        m,
+        n + 1 + m,
+        (0..n + 1 + m).flat_map(|j| {
+            (0..m).map(move |i| match j {
+                j if j < n => A[(i, j)].clone(),
+                j if j == n => -T::one(),
+                j => A[(i, j - 1)].clone(),
+            })
+        }),
+    );
+    let mut c = Vector::zeros(n + 1 + m);
+    c[n] = -T::one();
+
+    let r = (0..m)
+        .min_by(|i, k| b[*i].partial_cmp(&b[*k]).unwrap())
+        .unwrap_or(0);
+    let B: Vec<usize> =
+        (0..m).map(|i| if i == r { n } else { n + 1 + i }).collect();
+    let N: Vec<usize> = (0..n).chain(std::iter::once(n + 1 + r)).collect();
+
+    match primal(&A, b, &c, B, N, no_perturb, monitor)? {
+        SolveResult::Optimal(solution) => {
+            // Perturbation can leave x0 slightly above zero
+            let tolerance = T::epsilon().to_f64().sqrt() * (1.0 + norm_inf(b));
+            Ok(Some(solution.variable_values[n].to_f64() <= tolerance))
+        }
+        SolveResult::Stopped => Ok(None),
+        _ => Err(String::from("Phase 1 problem has no optimal solution")),
+    }
+}
+
+/**
+ * How `solve` solves a problem
+ */
+pub struct Options {
+    pub method: Method,
+    pub scaling: Scaling,
+    pub cuts: Cuts,
+    pub limits: Limits,
+    pub no_perturb: bool,
+    pub crossover: bool,
+}
+
+/**
+ * Scale the problem and solve it with the chosen method, using branch
+ * and bound or cutting planes if it has integer variables. The result
+ * is for the unscaled problem.
+ */
+pub fn solve(
+    lp: &ParsedLP,
+    options: &Options,
+    monitor: &mut Monitor,
+) -> Result<SolveResult, String> {
+    let no_perturb = options.no_perturb;
+    let mut factors = ScaleFactors::compute(&lp.A, lp.n, options.scaling);
+    factors.exclude(&lp.integer);
+    let (A, b, c) = factors.apply(&lp.A, &lp.b, &lp.c);
+
+    let solve_result =
+        match options.method {
+            Method::Simplex if !lp.integer.is_empty() => match options.cuts {
+                Cuts::Gomory => cutting_planes(
+                    &A,
+                    &b,
+                    &c,
+                    &lp.integer,
+                    &options.limits,
+                    no_perturb,
+                    monitor,
+                )?,
+                cuts => branch_and_bound(
+                    &A,
+                    &b,
+                    &c,
+                    &lp.integer,
+                    &options.limits,
+                    cuts,
+                    no_perturb,
+                    monitor,
+                )?,
+            },
+            Method::Ipm if !lp.integer.is_empty() => return Err(String::from(
+                "Integer variables can only be solved with the simplex method",
+            )),
+            Method::Simplex => simplex(&A, &b, &c, no_perturb, monitor)?,
+            Method::Ipm => match ipm(&A, &b, &c)? {
+                SolveResult::Optimal(solution) if options.crossover => {
+                    crossover(&A, &b, &c, &solution, no_perturb, monitor)?
+                }
+                other => other,
+            },
+        };
+
+    Ok(factors.unscale(solve_result))
+}
+
+impl<T> SolveResult<T> {
+    /**
+     * The name of the outcome, as printed on the first line of the results
+     */
+    pub fn status(&self) -> &'static str {
+        match self {
+            SolveResult::Infeasible => "infeasible",
+            SolveResult::Unbounded => "unbounded",
+            SolveResult::Optimal(_) => "optimal",
+            SolveResult::Feasible(..) => "feasible",
+            SolveResult::Stopped => "stopped",
+        }
+    }
+}
+
+/**
+ * Format the results for submission
+ */
+impl<T: Scalar> fmt::Display for SolveResult<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&output::text(self, &Precision::default(), false))
+    }
+}
+
+/**
+ * Format the results for debugging
+ */
+impl<T: Scalar> fmt::Debug for SolveResult<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&output::text(self, &Precision::default(), true))
+    }
+}
@@ -0,0 +1,546 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::output::{
+    basis_variable_name, dictionary, DictionaryFormat, DictionaryLayout,
+};
+use crate::scalar::Scalar;
+use crate::{Matrix, Vector};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::Instant;
+
+/**
+ * How much of the iteration log is printed
+ *
+ * - `None`: nothing
+ * - `Phase`: one line at the end of each run of the primal or dual
+ *   simplex
+ * - `Iteration`: a line for every pivot as well
+ */
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum LogLevel {
+    None,
+    Phase,
+    Iteration,
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(LogLevel::None),
+            "phase" => Ok(LogLevel::Phase),
+            "iteration" => Ok(LogLevel::Iteration),
+            _ => Err(format!(
+                "Unknown log level '{}' (expected none, phase or iteration)",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * The formats the iteration log can be printed in
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    Table,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(LogFormat::Table),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!(
+                "Unknown log format '{}' (expected table or json)",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * The simplex method making the pivots. `Auxiliary` is the primal
+ * simplex on the auxiliary problem used to find a dual feasible basis.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    Primal,
+    Dual,
+    Auxiliary,
+}
+
+impl FromStr for Phase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "primal" => Ok(Phase::Primal),
+            "dual" => Ok(Phase::Dual),
+            "aux" => Ok(Phase::Auxiliary),
+            _ => Err(format!(
+                "Unknown phase '{}' (expected primal, dual or aux)",
+                s
+            )),
+        }
+    }
+}
+
+impl Phase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Primal => "primal",
+            Phase::Dual => "dual",
+            Phase::Auxiliary => "aux",
+        }
+    }
+}
+
+/**
+ * A line of the iteration log. The objective and infeasibilities are
+ * those of the basis at the start of the iteration, and the
+ * infeasibilities are the sums of the negative basic values and the
+ * negative reduced costs. The entering and leaving variables, step
+ * length and degeneracy describe the pivot, and are left out of the
+ * line at the end of a phase.
+ */
+#[derive(Clone, Debug, Serialize)]
+pub struct Iteration {
+    pub iteration: usize,
+    pub phase: Phase,
+    pub objective: f64,
+    pub primal_infeasibility: f64,
+    pub dual_infeasibility: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entering: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leaving: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub degenerate: Option<bool>,
+}
+
+/**
+ * The work done by one phase of the simplex method. The basis isn't
+ * updated between pivots, so each solve with the basis factors it
+ * from scratch and is counted as a factorization.
+ */
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct PhaseStats {
+    pub pivots: usize,
+    pub factorizations: usize,
+    pub seconds: f64,
+}
+
+/**
+ * The work done by each phase, summed over every run of the primal
+ * and dual simplex that a monitor was passed to
+ */
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct SolveStats {
+    pub auxiliary: PhaseStats,
+    pub primal: PhaseStats,
+    pub dual: PhaseStats,
+}
+
+impl SolveStats {
+    pub fn phase(&self, phase: Phase) -> &PhaseStats {
+        match phase {
+            Phase::Primal => &self.primal,
+            Phase::Dual => &self.dual,
+            Phase::Auxiliary => &self.auxiliary,
+        }
+    }
+
+    fn phase_mut(&mut self, phase: Phase) -> &mut PhaseStats {
+        match phase {
+            Phase::Primal => &mut self.primal,
+            Phase::Dual => &mut self.dual,
+            Phase::Auxiliary => &mut self.auxiliary,
+        }
+    }
+
+    /**
+     * The work done by all the phases together
+     */
+    pub fn total(&self) -> PhaseStats {
+        let phases = [self.auxiliary, self.primal, self.dual];
+        PhaseStats {
+            pivots: phases.iter().map(|p| p.pivots).sum(),
+            factorizations: phases.iter().map(|p| p.factorizations).sum(),
+            seconds: phases.iter().map(|p| p.seconds).sum(),
+        }
+    }
+}
+
+/**
+ * The state of the simplex method at an iteration: the values `x` and
+ * reduced costs `z` of all the variables along with the basis
+ */
+pub(crate) struct State<'a, T> {
+    pub A: &'a Matrix<T>,
+    pub c: &'a Vector<T>,
+    pub x: &'a Vector<T>,
+    pub z: &'a Vector<T>,
+    pub B: &'a [usize],
+    pub N: &'a [usize],
+}
+
+/**
+ * What the solver should do after a pivot, as returned by the pivot
+ * callback
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+/**
+ * A pivot of the primal or dual simplex, as passed to the pivot
+ * callback. `B`, `N`, `x` and `z` are the basis, values and reduced
+ * costs after the pivot, where `x` and `z` cover all the variables
+ * including the slacks. `entering` is the variable which entered the
+ * basis and `leaving` the one which left it.
+ */
+pub struct Pivot<'a, T> {
+    pub iteration: usize,
+    pub phase: Phase,
+    pub B: &'a [usize],
+    pub N: &'a [usize],
+    pub x: &'a Vector<T>,
+    pub z: &'a Vector<T>,
+    pub entering: usize,
+    pub leaving: usize,
+}
+
+type PivotCallback<'a, T> = Box<dyn FnMut(&Pivot<T>) -> Control + 'a>;
+
+/**
+ * Follows the progress of the simplex method, printing the iteration
+ * log to stderr and calling the pivot callback. Iterations are counted
+ * across every run of the primal and dual simplex that the monitor is
+ * passed to, so the iterations of a branch and bound search are
+ * numbered from the root relaxation. The default monitor doesn't print
+ * anything and has no callback.
+ */
+pub struct Monitor<'a, T = f64> {
+    level: LogLevel,
+    format: LogFormat,
+    interval: usize,
+    iterations: usize,
+    header_printed: bool,
+    callback: Option<PivotCallback<'a, T>>,
+    dictionaries: Option<(DictionaryLayout, DictionaryFormat)>,
+    stats: SolveStats,
+    started: Option<(Phase, Instant)>,
+    pub(crate) auxiliary: bool,
+}
+
+impl<T: Scalar> Default for Monitor<'_, T> {
+    fn default() -> Self {
+        Monitor::new(LogLevel::None, LogFormat::Table, 1)
+    }
+}
+
+impl<'a, T: Scalar> Monitor<'a, T> {
+    /**
+     * A monitor printing the log at `level` in `format`. At the
+     * `Iteration` level only every `interval`th pivot is printed.
+     */
+    pub fn new(level: LogLevel, format: LogFormat, interval: usize) -> Self {
+        Monitor {
+            level,
+            format,
+            interval: interval.max(1),
+            iterations: 0,
+            header_printed: false,
+            callback: None,
+            dictionaries: None,
+            stats: SolveStats::default(),
+            started: None,
+            auxiliary: false,
+        }
+    }
+
+    /**
+     * Call `callback` after every pivot of the primal and dual simplex.
+     * If it returns `Control::Stop` the solver stops and returns
+     * `SolveResult::Stopped`.
+     */
+    pub fn on_pivot(
+        mut self,
+        callback: impl FnMut(&Pivot<T>) -> Control + 'a,
+    ) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /**
+     * Print the dictionary or tableau to stderr at every pivot of the
+     * primal and dual simplex, and at the end of each phase
+     */
+    pub fn show_dictionaries(
+        mut self,
+        layout: DictionaryLayout,
+        format: DictionaryFormat,
+    ) -> Self {
+        self.dictionaries = Some((layout, format));
+        self
+    }
+
+    /**
+     * The pivots, factorizations and time of each phase so far
+     */
+    pub fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    /**
+     * The primal simplex reports as the auxiliary phase while it
+     * is solving the auxiliary problem
+     */
+    pub(crate) fn primal_phase(&self) -> Phase {
+        match self.auxiliary {
+            true => Phase::Auxiliary,
+            false => Phase::Primal,
+        }
+    }
+
+    /**
+     * Record the start of a run of the primal or dual simplex, timing
+     * it until `finish` or until the pivot callback stops it
+     */
+    pub(crate) fn start(&mut self, phase: Phase) {
+        self.stop_clock();
+        self.started = Some((phase, Instant::now()));
+    }
+
+    /**
+     * Record a factorization of the basis
+     */
+    pub(crate) fn factorized(&mut self, phase: Phase) {
+        self.stats.phase_mut(phase).factorizations += 1;
+    }
+
+    fn stop_clock(&mut self) {
+        if let Some((phase, start)) = self.started.take() {
+            self.stats.phase_mut(phase).seconds +=
+                start.elapsed().as_secs_f64();
+        }
+    }
+
+    /**
+     * Record a pivot on `entering` and `leaving` with step length `step`
+     */
+    pub(crate) fn pivot(
+        &mut self,
+        phase: Phase,
+        state: State<T>,
+        entering: usize,
+        leaving: usize,
+        step: &T,
+    ) {
+        self.iterations += 1;
+        self.stats.phase_mut(phase).pivots += 1;
+        let n = state.N.len();
+
+        if self.level >= LogLevel::Iteration
+            && self.iterations.is_multiple_of(self.interval)
+        {
+            let mut iteration = self.iteration(phase, &state);
+            iteration.entering = Some(basis_variable_name(entering, n));
+            iteration.leaving = Some(basis_variable_name(leaving, n));
+            iteration.step = Some(step.to_f64());
+            iteration.degenerate = Some(step.abs() <= T::epsilon());
+            self.print(&iteration);
+        }
+
+        let heading = format!(
+            "Iteration {} ({}): {} enters, {} leaves",
+            self.iterations,
+            phase.name(),
+            basis_variable_name(entering, n),
+            basis_variable_name(leaving, n)
+        );
+        self.print_dictionary(
+            &heading,
+            phase,
+            &state,
+            Some((entering, leaving)),
+        );
+    }
+
+    /**
+     * Call the pivot callback, if there is one, after the pivot on
+     * `entering` and `leaving` which led to `state`
+     */
+    pub(crate) fn after_pivot(
+        &mut self,
+        phase: Phase,
+        state: State<T>,
+        entering: usize,
+        leaving: usize,
+    ) -> Control {
+        let iteration = self.iterations;
+        let control = match &mut self.callback {
+            Some(callback) => callback(&Pivot {
+                iteration,
+                phase,
+                B: state.B,
+                N: state.N,
+                x: state.x,
+                z: state.z,
+                entering,
+                leaving,
+            }),
+            None => Control::Continue,
+        };
+
+        if control == Control::Stop {
+            self.stop_clock();
+        }
+        control
+    }
+
+    /**
+     * Record the end of a run of the primal or dual simplex
+     */
+    pub(crate) fn finish(&mut self, phase: Phase, state: State<T>) {
+        self.stop_clock();
+        if self.level >= LogLevel::Phase {
+            let iteration = self.iteration(phase, &state);
+            self.print(&iteration);
+        }
+
+        let heading = format!("End of {} phase", phase.name());
+        self.print_dictionary(&heading, phase, &state, None);
+    }
+
+    /**
+     * Print the dictionary for `state` to stderr under `heading`, if
+     * dictionaries are shown
+     */
+    fn print_dictionary(
+        &self,
+        heading: &str,
+        phase: Phase,
+        state: &State<T>,
+        pivot: Option<(usize, usize)>,
+    ) {
+        let (layout, format) = match self.dictionaries {
+            Some(dictionaries) => dictionaries,
+            None => return,
+        };
+
+        let shown = dictionary(state, phase, pivot, layout, format)
+            .unwrap_or_else(|| String::from("(singular basis)"));
+        match format {
+            DictionaryFormat::Text => eprintln!("{}\n{}\n", heading, shown),
+            DictionaryFormat::Latex => eprintln!("% {}\n{}\n", heading, shown),
+        }
+    }
+
+    fn iteration(&self, phase: Phase, state: &State<T>) -> Iteration {
+        let negative_sum = |v: &Vector<T>, indices: &[usize]| {
+            indices
+                .iter()
+                .map(|&j| v[j].to_f64())
+                .filter(|v| *v < 0.0)
+                .map(|v| -v)
+                .fold(0.0, |sum, v| sum + v)
+        };
+
+        Iteration {
+            iteration: self.iterations,
+            phase,
+            objective: state
+                .B
+                .iter()
+                .map(|&j| (state.c[j].clone() * state.x[j].clone()).to_f64())
+                .fold(0.0, |sum, v| sum + v),
+            primal_infeasibility: negative_sum(state.x, state.B),
+            dual_infeasibility: negative_sum(state.z, state.N),
+            entering: None,
+            leaving: None,
+            step: None,
+            degenerate: None,
+        }
+    }
+
+    fn print(&mut self, iteration: &Iteration) {
+        match self.format {
+            LogFormat::Json => match serde_json::to_string(iteration) {
+                Ok(line) => eprintln!("{}", line),
+                Err(e) => eprintln!("{}", e),
+            },
+            LogFormat::Table => {
+                if !self.header_printed {
+                    eprintln!(
+                        concat!(
+                            "{:>6} {:<7} {:>13} {:>10} {:>10} ",
+                            "{:>6} {:>6} {:>10} {}"
+                        ),
+                        "iter",
+                        "phase",
+                        "objective",
+                        "primal inf",
+                        "dual inf",
+                        "enter",
+                        "leave",
+                        "step",
+                        "degen",
+                    );
+                    self.header_printed = true;
+                }
+
+                let step = match iteration.step {
+                    Some(step) => format!("{:.3e}", step),
+                    None => String::from("-"),
+                };
+                let degenerate = match iteration.degenerate {
+                    Some(true) => "yes",
+                    Some(false) => "no",
+                    None => "-",
+                };
+
+                eprintln!(
+                    concat!(
+                        "{:>6} {:<7} {:>13.6e} {:>10.3e} {:>10.3e} ",
+                        "{:>6} {:>6} {:>10} {}"
+                    ),
+                    iteration.iteration,
+                    iteration.phase.name(),
+                    iteration.objective,
+                    iteration.primal_infeasibility,
+                    iteration.dual_infeasibility,
+                    iteration.entering.as_deref().unwrap_or("-"),
+                    iteration.leaving.as_deref().unwrap_or("-"),
+                    step,
+                    degenerate
+                );
+            }
+        }
+    }
+}
@@ -14,24 +14,36 @@
  * along with bblp. If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::solve::{Solution, SolveResult};
+use crate::check::debug_check;
+use crate::scalar::Scalar;
+use crate::solve::{
+    condition_estimate, lost_accuracy, relative_residual, solve_refined,
+    Control, Monitor, Solution, SolveResult, State,
+    DEGENERATE_PIVOTS_BEFORE_BLAND, MAX_PIVOTS,
+};
 use crate::util::{
-    col_view, perturb, row_view, select_entering, select_leaving, write_view,
+    col_view, perturb, row_view, select_entering, select_entering_bland,
+    select_leaving, select_leaving_bland, select_leaving_stable, tr_mul,
+    transpose, write_view,
 };
-use crate::{Matrix, Vector, EPSILON};
+use crate::{Matrix, Vector};
 
-pub fn primal(
-    A: &Matrix,
-    b: &Vector,
-    c: &Vector,
+pub fn primal<T: Scalar>(
+    A: &Matrix<T>,
+    b: &Vector<T>,
+    c: &Vector<T>,
     B: Vec<usize>,
     N: Vec<usize>,
     no_perturb: bool,
-) -> Result<SolveResult, String> {
+    monitor: &mut Monitor<T>,
+) -> Result<SolveResult<T>, String> {
     let mut B = B;
     let mut N = N;
     let n = N.len();
     let m = B.len();
+    let unperturbed_b = b;
+    let phase = monitor.primal_phase();
+    monitor.start(phase);
 
     // Perturb the `b` vector if that setting is enabled
     let b = if no_perturb {
@@ -42,45 +54,128 @@ pub fn primal(
 
     // Compute x_B by solving A_B * x_B = b
     let mut x = Vector::zeros(m + n);
-    let x_B = col_view(A, &B)
-        .lu()
-        .solve(&b)
+    monitor.factorized(phase);
+    let (x_B, _) = solve_refined(&col_view(A, &B), &b)
         .ok_or_else(|| String::from("Failed to for x_B"))?;
     write_view(&mut x, &x_B, &B);
 
-    if x_B.min() < -EPSILON {
+    if x_B.iter().any(|v| *v < -T::epsilon()) {
         return Err(String::from("Initial basis is not feasible."));
     }
 
     let mut pivots = 0;
+    let mut stable = false;
+    let mut degenerate_pivots = 0;
+    let mut last_pivot = None;
     loop {
-        let x_B = row_view(&x, &B);
+        if pivots >= MAX_PIVOTS {
+            return Err(format!(
+                "Primal simplex did not finish within {} pivots",
+                MAX_PIVOTS
+            ));
+        }
+        let bland = degenerate_pivots >= DEGENERATE_PIVOTS_BEFORE_BLAND;
+
+        let mut x_B = row_view(&x, &B);
         let c_B = row_view(c, &B);
         let c_N = row_view(c, &N);
 
         let A_B = col_view(A, &B);
         let A_N = col_view(A, &N);
 
+        // x_B is updated in place after each pivot, which accumulates
+        // rounding error. Refactor it from scratch once it no longer
+        // satisfies A_B * x_B = b.
+        let mut accurate = true;
+        if !T::EXACT
+            && relative_residual(&A_B, &x_B, &b) > T::residual_tolerance()
+        {
+            monitor.factorized(phase);
+            let (refactored, x_accurate) = solve_refined(&A_B, &b)
+                .ok_or_else(|| String::from("Failed to for x_B"))?;
+            x_B = refactored;
+            accurate = x_accurate;
+            x = Vector::zeros(m + n);
+            write_view(&mut x, &x_B, &B);
+        }
+
         // Compute z by solving A_B^T * v = c_B then setting z_N = A_N^T * v - c_N
         let mut z = Vector::zeros(m + n);
-        let v = A_B
-            .transpose()
-            .lu()
-            .solve(&c_B)
+        monitor.factorized(phase);
+        let (v, v_accurate) = solve_refined(&transpose(&A_B), &c_B)
             .ok_or_else(|| String::from("Failed to solve for v"))?;
-        let z_N = A_N.transpose() * v - c_N;
+        accurate &= v_accurate;
+        let z_N = tr_mul(&A_N, &v) - c_N;
         write_view(&mut z, &z_N, &N);
 
+        if let Some((entering, leaving)) = last_pivot {
+            let control = monitor.after_pivot(
+                phase,
+                State {
+                    A,
+                    c,
+                    x: &x,
+                    z: &z,
+                    B: &B,
+                    N: &N,
+                },
+                entering,
+                leaving,
+            );
+            if control == Control::Stop {
+                return Ok(SolveResult::Stopped);
+            }
+        }
+
         // Select our entering variable using the largest coefficient rule.
         // If there is no suitable entering variable it means we have
         // reached an optimal solution.
-        let (j, j_idx) = match select_entering(&N, &z) {
+        let entering = if bland {
+            select_entering_bland(&N, &z)
+        } else {
+            select_entering(&N, &z)
+        };
+        let (j, j_idx) = match entering {
+            None if !accurate => return Err(lost_accuracy(&A_B)),
             None => {
-                let objective_value = (c_B.transpose() * x_B)[0];
+                // Exact arithmetic has no rounding to hide the
+                // perturbation behind, so recompute x_B from the
+                // original `b` using the optimal basis.
+                let x_B = if T::EXACT && !no_perturb {
+                    monitor.factorized(phase);
+                    let x_B = T::solve(&A_B, unperturbed_b)
+                        .ok_or_else(|| String::from("Failed to for x_B"))?;
+                    x = Vector::zeros(m + n);
+                    write_view(&mut x, &x_B, &B);
+                    x_B
+                } else {
+                    x_B
+                };
+
+                // The solution was recomputed from the original `b` in
+                // exact arithmetic, otherwise it's for the perturbed `b`
+                let solved_b = if T::EXACT { unperturbed_b } else { &b };
+                debug_check(A, solved_b, c, &x, &B);
+                monitor.finish(
+                    phase,
+                    State {
+                        A,
+                        c,
+                        x: &x,
+                        z: &z,
+                        B: &B,
+                        N: &N,
+                    },
+                );
+
+                let objective_value = c_B.dot(&x_B);
                 return Ok(SolveResult::Optimal(Solution {
-                    variable_values: x.iter().take(n).copied().collect(),
+                    variable_values: x.iter().take(n).cloned().collect(),
+                    dual_values: z.iter().skip(n).cloned().collect(),
+                    reduced_costs: z.iter().take(n).cloned().collect(),
                     objective_value,
                     pivots,
+                    condition: condition_estimate(&A_B),
                     B,
                     N,
                 }));
@@ -90,25 +185,79 @@ pub fn primal(
 
         // Compute delta_x_B by solving A_B * delta_x_B = Aj
         let mut delta_x = Vector::zeros(m + n);
-        let delta_x_B = A_B
-            .lu()
-            .solve(&A.column(j))
-            .ok_or_else(|| String::from("Failed to solve for delta_x_B"))?;
+        monitor.factorized(phase);
+        let (delta_x_B, delta_accurate) =
+            solve_refined(&A_B, &A.column(j).clone_owned())
+                .ok_or_else(|| String::from("Failed to solve for delta_x_B"))?;
+        accurate &= delta_accurate;
 
         write_view(&mut delta_x, &delta_x_B, &B);
 
+        // Once a solve with the basis loses accuracy, switch to the
+        // more stable ratio test to steer towards better conditioned
+        // bases. If accuracy is lost again after that, give up rather
+        // than risk reporting a wrong answer.
+        if !accurate {
+            if stable {
+                return Err(lost_accuracy(&A_B));
+            }
+            stable = true;
+        }
+
         // Select our leaving variable. If there is no leaving
         // variable the problem is unbounded
-        let (t, i, i_idx) = match select_leaving(&B, &x, &delta_x) {
-            None => return Ok(SolveResult::Unbounded),
+        let leaving = if bland {
+            select_leaving_bland(&B, &x, &delta_x)
+        } else if stable {
+            select_leaving_stable(&B, &x, &delta_x)
+        } else {
+            select_leaving(&B, &x, &delta_x)
+        };
+        let (t, i, i_idx) = match leaving {
+            None => {
+                monitor.finish(
+                    phase,
+                    State {
+                        A,
+                        c,
+                        x: &x,
+                        z: &z,
+                        B: &B,
+                        N: &N,
+                    },
+                );
+                return Ok(SolveResult::Unbounded);
+            }
             Some(p) => p,
         };
 
-        write_view(&mut x, &(x_B.clone_owned() - t * delta_x_B), &B);
+        monitor.pivot(
+            phase,
+            State {
+                A,
+                c,
+                x: &x,
+                z: &z,
+                B: &B,
+                N: &N,
+            },
+            j,
+            i,
+            &t,
+        );
+
+        if t > T::epsilon() {
+            degenerate_pivots = 0;
+        } else {
+            degenerate_pivots += 1;
+        }
+
+        write_view(&mut x, &(x_B - delta_x_B * t.clone()), &B);
 
         x[j] = t;
         B[i_idx] = j;
         N[j_idx] = i;
         pivots += 1;
+        last_pivot = Some((j, i));
     }
 }
@@ -14,7 +14,8 @@
  * along with bblp. If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::{Matrix, Vector, EPSILON, PERTURB_AMT};
+use crate::scalar::Scalar;
+use crate::{Matrix, Vector};
 
 /**
  * Round `value` to `d` significant digits.
@@ -40,18 +41,21 @@ pub fn round_sig_figs(value: f64, d: u32) -> f64 {
  * Select the entering variable from the index set `N`
  * based on Dantzig's largest-coefficient rule.
  */
-pub fn select_entering(N: &[usize], coefs: &Vector) -> Option<(usize, usize)> {
+pub fn select_entering<T: Scalar>(
+    N: &[usize],
+    coefs: &Vector<T>,
+) -> Option<(usize, usize)> {
     N.iter()
         .enumerate()
         .filter_map(|(idx, N_val)| {
-            let item = coefs[*N_val];
-            if item < -EPSILON {
+            let item = &coefs[*N_val];
+            if *item < -T::epsilon() {
                 Some((item, *N_val, idx))
             } else {
                 None
             }
         })
-        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+        .min_by(|a, b| a.0.partial_cmp(b.0).unwrap())
         .map(|(_, j, j_idx)| (j, j_idx))
 }
 
@@ -60,18 +64,18 @@ pub fn select_entering(N: &[usize], coefs: &Vector) -> Option<(usize, usize)> {
  * the values of the optimization variables, and the deltas
  * of the optimization variables.
  */
-pub fn select_leaving(
+pub fn select_leaving<T: Scalar>(
     B: &[usize],
-    vars: &Vector,
-    delta_vars: &Vector,
-) -> Option<(f64, usize, usize)> {
+    vars: &Vector<T>,
+    delta_vars: &Vector<T>,
+) -> Option<(T, usize, usize)> {
     B.iter()
         .enumerate()
         .filter_map(|(idx, B_val)| {
-            let vars_i = vars[*B_val];
-            let delta_vars_i = delta_vars[*B_val];
+            let vars_i = vars[*B_val].clone();
+            let delta_vars_i = delta_vars[*B_val].clone();
 
-            if delta_vars_i > EPSILON {
+            if delta_vars_i > T::epsilon() {
                 Some((vars_i / delta_vars_i, *B_val, idx))
             } else {
                 None
@@ -85,6 +89,88 @@ pub fn select_leaving(
         .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
 }
 
+/**
+ * Select the entering variable from the index set `N` based on
+ * Bland's rule, which picks the smallest index with a negative
+ * coefficient. It's slower than the largest-coefficient rule but
+ * can't cycle.
+ */
+pub fn select_entering_bland<T: Scalar>(
+    N: &[usize],
+    coefs: &Vector<T>,
+) -> Option<(usize, usize)> {
+    N.iter()
+        .enumerate()
+        .filter(|(_, N_val)| coefs[**N_val] < -T::epsilon())
+        .min_by_key(|(_, N_val)| **N_val)
+        .map(|(idx, N_val)| (*N_val, idx))
+}
+
+/**
+ * Select the leaving variable like `select_leaving`, but break ties
+ * in the ratio test by picking the smallest index for Bland's rule
+ */
+pub fn select_leaving_bland<T: Scalar>(
+    B: &[usize],
+    vars: &Vector<T>,
+    delta_vars: &Vector<T>,
+) -> Option<(T, usize, usize)> {
+    let candidates: Vec<(T, usize, usize)> = B
+        .iter()
+        .enumerate()
+        .filter(|(_, B_val)| delta_vars[**B_val] > T::epsilon())
+        .map(|(idx, B_val)| {
+            let ratio = vars[*B_val].clone() / delta_vars[*B_val].clone();
+            (ratio, *B_val, idx)
+        })
+        .collect();
+
+    let bound = candidates
+        .iter()
+        .map(|(ratio, _, _)| ratio)
+        .min_by(|a, b| a.partial_cmp(b).unwrap())?
+        .clone();
+
+    candidates
+        .into_iter()
+        .filter(|(ratio, _, _)| *ratio <= bound)
+        .min_by_key(|(_, i, _)| *i)
+}
+
+/**
+ * A more stable version of `select_leaving` using Harris' two pass
+ * ratio test. The first pass finds the smallest ratio with each value
+ * relaxed by the epsilon, and the second pass picks the candidate with
+ * the largest delta among those whose ratio is within that bound.
+ * Pivoting on larger deltas keeps the next basis better conditioned
+ * at the cost of slightly infeasible (within the epsilon) values.
+ */
+pub fn select_leaving_stable<T: Scalar>(
+    B: &[usize],
+    vars: &Vector<T>,
+    delta_vars: &Vector<T>,
+) -> Option<(T, usize, usize)> {
+    let candidates: Vec<(usize, usize)> = B
+        .iter()
+        .enumerate()
+        .filter(|(_, B_val)| delta_vars[**B_val] > T::epsilon())
+        .map(|(idx, B_val)| (*B_val, idx))
+        .collect();
+
+    let bound = candidates
+        .iter()
+        .map(|(i, _)| {
+            (vars[*i].clone() + T::epsilon()) / delta_vars[*i].clone()
+        })
+        .min_by(|a, b| a.partial_cmp(b).unwrap())?;
+
+    candidates
+        .into_iter()
+        .filter(|(i, _)| vars[*i].clone() / delta_vars[*i].clone() <= bound)
+        .max_by(|a, b| delta_vars[a.0].partial_cmp(&delta_vars[b.0]).unwrap())
+        .map(|(i, idx)| (vars[i].clone() / delta_vars[i].clone(), i, idx))
+}
+
 /**
  * Perturb the vector b based on the method described here:
  * <https://people.math.carleton.ca/~kcheung/math/notes/MATH5801/1/01_perturb.html>
@@ -92,21 +178,29 @@ pub fn select_leaving(
  * This prevents cycling and allows the use of the largest-coefficient
  * rule for all pivots.
  */
-pub fn perturb(A: &Matrix, B: &[usize], b: &Vector) -> Vector {
+pub fn perturb<T: Scalar>(
+    A: &Matrix<T>,
+    B: &[usize],
+    b: &Vector<T>,
+) -> Vector<T> {
     let A_B = col_view(A, B);
     let m = A.nrows();
+    let mut amt = T::one();
     let e = Vector::from_iterator(
         m,
-        (0..m).map(|idx| PERTURB_AMT.powi(idx as i32 + 1)),
+        (0..m).map(|_| {
+            amt *= T::perturb_amt();
+            amt.clone()
+        }),
     );
-    b + A_B * e
+    b + mul(&A_B, &e)
 }
 
 /**
  * Construct a new matrix consisting of the columns from `M`
  * given by the indices from `idxs`
  */
-pub fn col_view(M: &Matrix, idxs: &[usize]) -> Matrix {
+pub fn col_view<T: Scalar>(M: &Matrix<T>, idxs: &[usize]) -> Matrix<T> {
     let mut ret = Matrix::zeros(M.nrows(), idxs.len());
     idxs.iter()
         .enumerate()
@@ -118,7 +212,7 @@ pub fn col_view(M: &Matrix, idxs: &[usize]) -> Matrix {
  * Construct a new vector consisting of the elements from `V`
  * given by the indices from `idxs`
  */
-pub fn row_view(V: &Vector, idxs: &[usize]) -> Vector {
+pub fn row_view<T: Scalar>(V: &Vector<T>, idxs: &[usize]) -> Vector<T> {
     let mut ret = Vector::zeros(idxs.len());
     idxs.iter()
         .enumerate()
@@ -130,8 +224,85 @@ pub fn row_view(V: &Vector, idxs: &[usize]) -> Vector {
  * Copy the elements from `view` into `main` according
  * to the indices given by `idxs`.
  */
-pub fn write_view(main: &mut Vector, view: &Vector, idxs: &[usize]) {
+pub fn write_view<T: Scalar>(
+    main: &mut Vector<T>,
+    view: &Vector<T>,
+    idxs: &[usize],
+) {
     idxs.iter()
         .enumerate()
-        .for_each(|(e, i)| main[*i] = view[e]);
+        .for_each(|(e, i)| main[*i] = view[e].clone());
+}
+
+/*
+ * nalgebra 0.28 builds the results of `transpose` and matrix products
+ * in uninitialized memory, which is only sound for `Copy` scalars. The
+ * functions below build their results from iterators instead so they
+ * can also be used with `Rational`.
+ */
+
+/**
+ * Compute the transpose of `M`
+ */
+pub fn transpose<T: Scalar>(M: &Matrix<T>) -> Matrix<T> {
+    Matrix::from_iterator(
+        M.ncols(),
+        M.nrows(),
+        M.row_iter()
+            .flat_map(|row| row.iter().cloned().collect::<Vec<T>>()),
+    )
+}
+
+/**
+ * Compute the product `M * v`
+ */
+pub fn mul<T: Scalar>(M: &Matrix<T>, v: &Vector<T>) -> Vector<T> {
+    let mut ret = Vector::zeros(M.nrows());
+    M.column_iter()
+        .zip(v.iter())
+        .for_each(|(col, v_j)| ret.axpy(v_j.clone(), &col, T::one()));
+    ret
+}
+
+/**
+ * Compute the product `M^T * v`
+ */
+pub fn tr_mul<T: Scalar>(M: &Matrix<T>, v: &Vector<T>) -> Vector<T> {
+    Vector::from_iterator(M.ncols(), M.column_iter().map(|col| col.dot(v)))
+}
+
+/**
+ * Compute the infinity norm of `v` (as an `f64`, since
+ * it is only used for checking tolerances)
+ */
+pub fn norm_inf<T: Scalar>(v: &Vector<T>) -> f64 {
+    v.iter().map(|v| v.to_f64().abs()).fold(0.0, f64::max)
+}
+
+/**
+ * Append the constraints `rows * x <= rhs` to the problem, each with
+ * its own slack column. `rows` has a coefficient for every existing
+ * column of `A`. The new slack columns go at the end so the indices of
+ * the existing columns (and any basis built from them) are unchanged.
+ */
+pub fn add_rows(
+    A: &Matrix,
+    b: &Vector,
+    c: &Vector,
+    rows: &Matrix,
+    rhs: &Vector,
+) -> (Matrix, Vector, Vector) {
+    let m = A.nrows();
+    let cols = A.ncols();
+    let k = rows.nrows();
+
+    let mut A = A.clone_owned().resize(m + k, cols + k, 0.0);
+    A.slice_mut((m, 0), (k, cols)).copy_from(rows);
+    (0..k).for_each(|i| A[(m + i, cols + i)] = 1.0);
+
+    let mut b = b.clone_owned().resize_vertically(m + k, 0.0);
+    b.rows_mut(m, k).copy_from(rhs);
+
+    let c = c.clone_owned().resize_vertically(cols + k, 0.0);
+    (A, b, c)
 }
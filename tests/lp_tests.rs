@@ -0,0 +1,228 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Solves every model in `lp_tests/input` that has an expected result
+//! of the same name in `lp_tests/output`, with the simplex method and,
+//! for models without integer variables, the interior point method, and
+//! compares the status and objective value. The expected results are in
+//! the text output format, but only the first two lines (the status and
+//! the objective value) are compared, with a tolerance, so they don't
+//! need to match exactly.
+//! Expected results with dual values are also checked against the KKT
+//! conditions with `bblp check`.
+//!
+//! Run with `cargo test --test lp_tests`, optionally followed by `--`
+//! and substrings of the case names to run.
+
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, ExitCode};
+
+/**
+ * Largest relative difference from the expected objective value
+ */
+const TOLERANCE: f64 = 1e-6;
+
+/**
+ * The methods each model is solved with. Models with integer variables
+ * are only solved with the first.
+ */
+const METHODS: [&str; 2] = ["simplex", "ipm"];
+
+/**
+ * The status and objective value of a result
+ */
+struct Outcome {
+    status: String,
+    objective: Option<f64>,
+    duals: bool,
+}
+
+fn main() -> ExitCode {
+    let filters: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with('-'))
+        .collect();
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("lp_tests");
+
+    let mut inputs: Vec<_> = fs::read_dir(root.join("input"))
+        .expect("couldn't read lp_tests/input")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            filters.is_empty() || filters.iter().any(|f| name.contains(f))
+        })
+        .collect();
+    inputs.sort();
+
+    println!("\nrunning {} lp_tests models", inputs.len());
+    println!(
+        "{:<30} {:<12} {:>16} {:>8} {:>10}",
+        "case", "status", "objective", "pivots", "time (ms)"
+    );
+
+    let mut passed = 0;
+    let mut failed = Vec::new();
+    let mut skipped = 0;
+    for input in &inputs {
+        let name = input.file_name().unwrap().to_string_lossy();
+        let expected_path =
+            root.join("output").join(input.file_name().unwrap());
+
+        let expected = match fs::read_to_string(&expected_path) {
+            Ok(contents) => expected_outcome(&contents),
+            Err(_) => {
+                println!("{:<30} skipped (no expected output)", name);
+                skipped += 1;
+                continue;
+            }
+        };
+
+        // The interior point method doesn't do integer variables
+        let contents = fs::read_to_string(input).unwrap_or_default();
+        let integer = contents
+            .lines()
+            .any(|line| line.trim_start().starts_with("integer"));
+        let methods = match integer {
+            true => &METHODS[..1],
+            false => &METHODS[..],
+        };
+
+        for method in methods {
+            let case = format!("{} ({})", name, method);
+            match solve(input, method) {
+                Ok((outcome, pivots, time)) => {
+                    let objective = outcome
+                        .objective
+                        .map_or(String::from("-"), |v| format!("{:.6}", v));
+                    let verdict = compare(&outcome, &expected)
+                        .or_else(|| certify(input, &expected_path, &expected));
+                    println!(
+                        "{:<30} {:<12} {:>16} {:>8} {:>10.3}  {}",
+                        case,
+                        outcome.status,
+                        objective,
+                        pivots,
+                        time * 1000.0,
+                        verdict.as_deref().unwrap_or("ok")
+                    );
+                    match verdict {
+                        Some(_) => failed.push(case),
+                        None => passed += 1,
+                    }
+                }
+                Err(e) => {
+                    println!("{:<30} error: {}", case, e);
+                    failed.push(case);
+                }
+            }
+        }
+    }
+
+    println!(
+        "\nlp_tests result: {} passed; {} failed; {} skipped\n",
+        passed,
+        failed.len(),
+        skipped
+    );
+
+    match failed.is_empty() {
+        true => ExitCode::SUCCESS,
+        false => {
+            println!("failures: {}", failed.join(", "));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/**
+ * Solve the model with the `bblp` binary and `method`, returning the
+ * outcome, the number of pivots and the solve time in seconds
+ */
+fn solve(input: &Path, method: &str) -> Result<(Outcome, u64, f64), String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_bblp"))
+        .args(["--output", "json", "--method", method])
+        .arg(input)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    let json: Value = serde_json::from_slice(&output.stdout).map_err(|_| {
+        String::from_utf8_lossy(&output.stderr).trim().to_string()
+    })?;
+
+    let outcome = Outcome {
+        status: json["status"].as_str().unwrap_or("").to_string(),
+        objective: json["objective"].as_f64(),
+        duals: false,
+    };
+    let pivots = json["pivots"].as_u64().unwrap_or(0);
+    let time = json["time"].as_f64().unwrap_or(0.0);
+    Ok((outcome, pivots, time))
+}
+
+/**
+ * The outcome from the first two lines of the text output, and whether
+ * it has dual values on the 4th line
+ */
+fn expected_outcome(contents: &str) -> Outcome {
+    let mut lines = contents.lines().map(str::trim);
+    Outcome {
+        status: lines.next().unwrap_or("").to_string(),
+        objective: lines.next().and_then(|l| l.parse().ok()),
+        duals: lines.nth(1).is_some_and(|l| !l.is_empty()),
+    }
+}
+
+/**
+ * Check an expected optimal result with dual values against the KKT
+ * conditions with `bblp check`, so a wrong expected result can't pass
+ * just because the solver makes the same mistake
+ */
+fn certify(input: &Path, path: &Path, expected: &Outcome) -> Option<String> {
+    if !expected.duals {
+        return None;
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_bblp"))
+        .arg("check")
+        .arg(input)
+        .arg(path)
+        .output();
+    match output {
+        Ok(output) if output.status.success() => None,
+        Ok(_) => Some(String::from("expected result fails the KKT check")),
+        Err(e) => Some(e.to_string()),
+    }
+}
+
+/**
+ * Why the outcome doesn't match the expected outcome, if it doesn't
+ */
+fn compare(outcome: &Outcome, expected: &Outcome) -> Option<String> {
+    if outcome.status != expected.status {
+        return Some(format!("expected {}", expected.status));
+    }
+
+    match (outcome.objective, expected.objective) {
+        (Some(v), Some(e)) => {
+            let error = (v - e).abs() / e.abs().max(1.0);
+            (error > TOLERANCE).then(|| format!("expected objective {}", e))
+        }
+        (None, None) => None,
+        (_, e) => Some(format!("expected objective {:?}", e)),
+    }
+}
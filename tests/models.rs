@@ -0,0 +1,429 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Tests of the general models in `bblp::model` and the formats they're
+//! read and written in. Every model is solved in exact arithmetic, so
+//! objective values are compared exactly.
+
+use bblp::model::{self, Model, ModelFormat, ObjectiveSense};
+use bblp::scalar::{Rational, Scalar};
+use bblp::solve::{self, Monitor, SolveResult};
+use std::fs;
+use std::path::Path;
+
+/**
+ * Models in the LP format with every row sense and kind of bound, as
+ * (name, model)
+ */
+const GENERAL_MODELS: [(&str, &str); 3] = [
+    (
+        "maximize",
+        "Maximize
+ obj: 3 x + 2 y - z + w
+Subject To
+ c1: x + y + z <= 10
+ c2: x - y >= -2
+ c3: x + w = 4
+ c4: y - z + w <= 8
+Bounds
+ -3 <= x <= 5
+ y free
+ -inf <= z <= 0
+ w free
+End
+",
+    ),
+    (
+        "minimize",
+        "Minimize
+ obj: 2 x - y + 3 z
+Subject To
+ c1: x + y + z >= 2
+ c2: x - z = 1
+ c3: x + y <= 6
+Bounds
+ x >= -1
+ -2 <= y <= 4
+ z free
+End
+",
+    ),
+    (
+        "nonpositive",
+        "Minimize
+ obj: x - 2 y
+Subject To
+ c1: x + y >= -5
+ c2: x - y <= 3
+ c3: x + 2 y = -4
+Bounds
+ x <= 0
+ -inf <= y <= 1
+End
+",
+    ),
+];
+
+/**
+ * The same ranged model in fixed MPS, in free MPS without the optional
+ * vector names, and in the LP format with each range written as a
+ * second constraint. `X3` has a negative upper bound and no lower
+ * bound, so it's unbounded below, and its minimum is 10.
+ */
+const RANGED_FIXED_MPS: &str = "NAME          RANGED
+ROWS
+ N  COST
+ L  LIM1
+ G  LIM2
+ E  MYEQN
+ E  MYEQN2
+COLUMNS
+    X1        COST         1.0         LIM1         1.0
+    X1        LIM2         1.0
+    X2        COST         2.0         LIM1         1.0
+    X2        MYEQN       -1.0
+    X3        COST        -1.0         MYEQN       -1.0
+    X3        MYEQN2       1.0
+    X4        COST         1.0         LIM2         1.0
+    X4        MYEQN2       1.0
+RHS
+    RHS       LIM1         4.0         LIM2         1.0
+    RHS       MYEQN        7.0         MYEQN2       2.0
+RANGES
+    RNG       LIM1         4.0         LIM2         6.0
+    RNG       MYEQN        2.0         MYEQN2      -3.0
+BOUNDS
+ UP BND       X1           4.0
+ LO BND       X2          -1.0
+ UP BND       X2           1.0
+ UP BND       X3          -1.0
+ MI BND       X4
+ UP BND       X4           5.0
+ENDATA
+";
+
+const RANGED_FREE_MPS: &str = "NAME RANGED
+ROWS
+ N COST
+ L LIM1
+ G LIM2
+ E MYEQN
+ E MYEQN2
+COLUMNS
+ X1 COST 1 LIM1 1
+ X1 LIM2 1
+ X2 COST 2 LIM1 1
+ X2 MYEQN -1
+ X3 COST -1 MYEQN -1
+ X3 MYEQN2 1
+ X4 COST 1 LIM2 1
+ X4 MYEQN2 1
+RHS
+ LIM1 4 LIM2 1
+ MYEQN 7 MYEQN2 2
+RANGES
+ LIM1 4 LIM2 6
+ MYEQN 2 MYEQN2 -3
+BOUNDS
+ UP X1 4
+ LO X2 -1
+ UP X2 1
+ UP X3 -1
+ MI X4
+ UP X4 5
+ENDATA
+";
+
+const RANGED_LP: &str = "Minimize
+ obj: x1 + 2 x2 - x3 + x4
+Subject To
+ lim1: x1 + x2 <= 4
+ lim1_lo: x1 + x2 >= 0
+ lim2: x1 + x4 >= 1
+ lim2_up: x1 + x4 <= 7
+ myeqn: - x2 - x3 >= 7
+ myeqn_up: - x2 - x3 <= 9
+ myeqn2: x3 + x4 <= 2
+ myeqn2_lo: x3 + x4 >= -1
+Bounds
+ x1 <= 4
+ -1 <= x2 <= 1
+ -inf <= x3 <= -1
+ -inf <= x4 <= 5
+End
+";
+
+/**
+ * The formats that keep everything in a general model
+ */
+const GENERAL_FORMATS: [ModelFormat; 3] =
+    [ModelFormat::Lp, ModelFormat::Mps, ModelFormat::Json];
+
+/**
+ * The models in `lp_tests/input`, as (file name, model)
+ */
+fn lp_test_models() -> Vec<(String, Model<Rational>)> {
+    let inputs = Path::new(env!("CARGO_MANIFEST_DIR")).join("lp_tests/input");
+    fs::read_dir(inputs)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let contents = fs::read_to_string(&path).unwrap();
+            let model =
+                model::read_model(&contents, ModelFormat::Native).unwrap();
+            (name, model)
+        })
+        .collect()
+}
+
+/**
+ * Write the model in `format` and read it back
+ */
+fn round_trip(model: &Model<Rational>, format: ModelFormat) -> Model<Rational> {
+    let contents = model::write_model(model, format).unwrap();
+    model::read_model(&contents, format)
+        .unwrap_or_else(|e| panic!("{:?}: {}\n{}", format, e, contents))
+}
+
+/**
+ * The status of the model and its objective value, in the model's own
+ * sense, if it's optimal
+ */
+fn solve(model: &Model<Rational>) -> (&'static str, Option<Rational>) {
+    let lp = model.to_parsed().unwrap();
+    let result =
+        solve::simplex(&lp.A, &lp.b, &lp.c, false, &mut Monitor::default())
+            .unwrap();
+    let objective = match &result {
+        SolveResult::Optimal(solution) => Some(match model.sense {
+            ObjectiveSense::Maximize => solution.objective_value.clone(),
+            ObjectiveSense::Minimize => -solution.objective_value.clone(),
+        }),
+        _ => None,
+    };
+
+    (result.status(), objective)
+}
+
+/**
+ * Check that the dual of the model has the same objective value when
+ * the model is optimal, and is infeasible when it's unbounded. An
+ * infeasible model can have an infeasible or an unbounded dual.
+ */
+fn check_dual(name: &str, model: &Model<Rational>) {
+    let (status, objective) = solve(model);
+    let (dual_status, dual_objective) = solve(&model.dual().unwrap());
+
+    match status {
+        "optimal" => assert_eq!(
+            dual_objective, objective,
+            "{}: the dual's objective value differs",
+            name
+        ),
+        "unbounded" => assert_eq!(
+            dual_status, "infeasible",
+            "{}: the primal is unbounded",
+            name
+        ),
+        _ => assert_ne!(
+            dual_status, "optimal",
+            "{}: the primal is {}",
+            name, status
+        ),
+    }
+}
+
+#[test]
+fn lp_test_duals_match_the_primal() {
+    for (name, model) in lp_test_models() {
+        if model.variables.iter().any(|v| v.integer) {
+            assert!(model.dual().is_err(), "{}: has an LP dual", name);
+            continue;
+        }
+        check_dual(&name, &model);
+    }
+}
+
+#[test]
+fn general_model_duals_match_the_primal() {
+    for (name, contents) in GENERAL_MODELS {
+        let model: Model<Rational> =
+            model::read_model(contents, ModelFormat::Lp).unwrap();
+        assert_eq!(solve(&model).0, "optimal", "{}", name);
+        check_dual(name, &model);
+
+        // The dual of the dual is the model again
+        let dual_dual = model.dual().unwrap().dual().unwrap();
+        assert_eq!(solve(&dual_dual).1, solve(&model).1, "{}", name);
+    }
+}
+
+#[test]
+fn lp_test_models_round_trip_through_every_format() {
+    for (name, model) in lp_test_models() {
+        let expected = solve(&model);
+        let integer = model.to_parsed().unwrap().integer;
+
+        for format in GENERAL_FORMATS {
+            // Native to the format and back to native
+            let native = model::write_model(
+                &round_trip(&model, format),
+                ModelFormat::Native,
+            )
+            .unwrap();
+            let again: Model<Rational> =
+                model::read_model(&native, ModelFormat::Native).unwrap();
+
+            assert_eq!(solve(&again), expected, "{} via {:?}", name, format);
+            assert_eq!(
+                again.to_parsed().unwrap().integer,
+                integer,
+                "{} via {:?}",
+                name,
+                format
+            );
+        }
+    }
+}
+
+#[test]
+fn general_models_round_trip_through_every_format() {
+    let models = GENERAL_MODELS.iter().chain(&[("ranged", RANGED_LP)]).map(
+        |(name, contents)| {
+            (*name, model::read_model(contents, ModelFormat::Lp).unwrap())
+        },
+    );
+
+    for (name, model) in models {
+        let model: Model<Rational> = model;
+        for format in GENERAL_FORMATS {
+            let again = round_trip(&model, format);
+            assert_eq!(
+                solve(&again),
+                solve(&model),
+                "{} via {:?}",
+                name,
+                format
+            );
+            assert_eq!(again.sense, model.sense, "{} via {:?}", name, format);
+
+            let senses = |m: &Model<Rational>| {
+                m.constraints
+                    .iter()
+                    .map(|c| (c.name.clone(), c.sense))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(senses(&again), senses(&model), "{}", name);
+
+            let bounds = |m: &Model<Rational>| {
+                m.variables
+                    .iter()
+                    .map(|v| (v.name.clone(), v.lower.clone(), v.upper.clone()))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(bounds(&again), bounds(&model), "{}", name);
+        }
+    }
+}
+
+#[test]
+fn mps_ranges_and_bounds() {
+    let expected =
+        solve(&model::read_model(RANGED_LP, ModelFormat::Lp).unwrap());
+    assert_eq!(expected, ("optimal", Some(Rational::parse("10").unwrap())));
+
+    for contents in [RANGED_FIXED_MPS, RANGED_FREE_MPS] {
+        let model: Model<Rational> =
+            model::read_model(contents, ModelFormat::Mps).unwrap();
+        assert_eq!(solve(&model), expected);
+
+        // Each range adds a constraint
+        assert_eq!(model.constraints.len(), 8);
+        let bounds: Vec<_> = model
+            .variables
+            .iter()
+            .map(|v| (v.lower.clone(), v.upper.clone()))
+            .collect();
+        let value = |s: &str| Some(Rational::parse(s).unwrap());
+        assert_eq!(
+            bounds,
+            [
+                (value("0"), value("4")),
+                (value("-1"), value("1")),
+                (None, value("-1")),
+                (None, value("5")),
+            ]
+        );
+    }
+}
+
+#[test]
+fn readers_reject_duplicate_rows_and_bad_numbers() {
+    let cases = [
+        (
+            ModelFormat::Lp,
+            "Maximize\n obj: x + y\nSubject To\n c1: x + y <= 4\n \
+             c1: x - y <= 1\nEnd\n",
+            "Duplicate constraint 'c1'",
+        ),
+        (
+            ModelFormat::Lp,
+            "Maximize\n obj: x + y\nSubject To\n c1: x + 1.2.3 y <= 4\n\
+             End\n",
+            "Invalid number '1.2.3'",
+        ),
+        (
+            ModelFormat::Mps,
+            "NAME d\nROWS\n N obj\n L c1\n L c1\nCOLUMNS\n x obj 1 c1 1\n\
+             RHS\n rhs c1 4\nENDATA\n",
+            "line 5: Duplicate constraint 'c1'",
+        ),
+        (
+            ModelFormat::Mps,
+            "NAME d\nROWS\n N obj\n L c1\nCOLUMNS\n x obj 1 c1 one\n\
+             RHS\n rhs c1 4\nENDATA\n",
+            "line 6: Invalid number 'one'",
+        ),
+        (
+            ModelFormat::Mps,
+            "NAME d\nROWS\n N obj\n L c1\nCOLUMNS\n x obj 1 c1 1\n\
+             RHS\n rhs c1 4\nRANGES\n rng c1 1e\nENDATA\n",
+            "line 10: Invalid number '1e'",
+        ),
+        (ModelFormat::Native, "1 2\n1 x 3\n", "Invalid number 'x'"),
+        (
+            ModelFormat::Json,
+            r#"{"name": "bad", "sense": "maximize", "variables": [
+                {"name": "x", "objective": "abc", "lower": 0,
+                 "upper": null, "integer": false}], "constraints": []}"#,
+            "Invalid number 'abc'",
+        ),
+    ];
+
+    for (format, contents, message) in cases {
+        match model::read_model::<f64>(contents, format) {
+            Ok(_) => panic!("{:?} model was read:\n{}", format, contents),
+            Err(e) => assert!(
+                e.contains(message),
+                "{:?}: '{}' doesn't contain '{}'",
+                format,
+                e,
+                message
+            ),
+        }
+    }
+}
@@ -0,0 +1,710 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Property tests of the simplex routines on small problems from
+//! `bblp::generator`. Each result is checked against an oracle which
+//! enumerates every basis of `[A | I]`, keeps the feasible ones and
+//! picks the best, in exact arithmetic. The problems are small enough
+//! that there are at most 70 bases.
+
+#![allow(non_snake_case)]
+
+use bblp::generator::{self, Kind};
+use bblp::model::{self, Model, ModelFormat};
+use bblp::parse::{self, ParsedLP};
+use bblp::scalar::{DoubleDouble, Rational, Scalar};
+use bblp::scale::Scaling;
+use bblp::solve::{self, Cuts, Limits, Method, Monitor, Solution, SolveResult};
+use bblp::util::col_view;
+use bblp::{Matrix, Vector};
+use num_traits::{One, Zero};
+
+/**
+ * Largest relative difference from the oracle's objective value
+ */
+const TOLERANCE: f64 = 1e-6;
+
+/**
+ * `TOLERANCE` for `f32`. Its perturbation amount is `1e-5`, which
+ * shows in the objective value.
+ */
+const F32_TOLERANCE: f64 = 1e-4;
+
+/**
+ * The generated problems have 1 to `MAX_SIZE` rows and columns
+ */
+const MAX_SIZE: usize = 4;
+
+/**
+ * The seeds generated for each kind, size and density
+ */
+const SEEDS: u64 = 4;
+
+/**
+ * The upper bound given to every variable of the integer problems
+ */
+const INTEGER_BOUND: i64 = 3;
+
+const KINDS: [Kind; 5] = [
+    Kind::Random,
+    Kind::Feasible,
+    Kind::Infeasible,
+    Kind::Unbounded,
+    Kind::Degenerate,
+];
+
+/**
+ * The outcome found by the oracle
+ */
+#[derive(Clone, Debug, PartialEq)]
+enum Expected {
+    Infeasible,
+    Unbounded,
+    Optimal(Rational),
+}
+
+/**
+ * A generated problem, in exact arithmetic, and its description
+ */
+struct Case {
+    name: String,
+    lp: ParsedLP<Rational>,
+}
+
+/**
+ * Every generated problem. The Klee-Minty cubes and the cycling
+ * example are included with the random ones.
+ */
+fn cases() -> Vec<Case> {
+    let mut options = Vec::new();
+    for kind in KINDS {
+        for rows in 1..=MAX_SIZE {
+            for cols in 1..=MAX_SIZE {
+                for density in [0.5, 1.0] {
+                    for seed in 0..SEEDS {
+                        options.push(generator::Options {
+                            kind,
+                            rows,
+                            cols,
+                            density,
+                            seed,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    for cols in 1..=MAX_SIZE {
+        options.push(generator::Options {
+            kind: Kind::KleeMinty,
+            rows: cols,
+            cols,
+            density: 1.0,
+            seed: 0,
+        });
+    }
+    options.push(generator::Options {
+        kind: Kind::Cycling,
+        rows: 3,
+        cols: 4,
+        density: 1.0,
+        seed: 0,
+    });
+
+    // Some kinds can't be generated at every size
+    options
+        .iter()
+        .filter_map(|options| {
+            let lp = generator::generate(options).ok()?;
+            let native = model::write_model(
+                &Model::from_parsed(&lp),
+                ModelFormat::Native,
+            )
+            .unwrap();
+            Some(Case {
+                name: format!(
+                    "{:?} {}x{} density {} seed {}",
+                    options.kind,
+                    options.rows,
+                    options.cols,
+                    options.density,
+                    options.seed
+                ),
+                lp: parse::parse(&native).unwrap(),
+            })
+        })
+        .collect()
+}
+
+/**
+ * The `k` element subsets of `0..n`, in lexicographic order
+ */
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+
+    (k - 1..n)
+        .flat_map(|last| {
+            combinations(last, k - 1).into_iter().map(move |mut c| {
+                c.push(last);
+                c
+            })
+        })
+        .collect()
+}
+
+/**
+ * The best objective value over the basic feasible solutions of
+ * `A x = b` and `x >= 0`, or `None` if there aren't any
+ */
+fn best_vertex(lp: &ParsedLP<Rational>) -> Option<Rational> {
+    let m = lp.A.nrows();
+    let mut best: Option<Rational> = None;
+
+    for B in combinations(lp.A.ncols(), m) {
+        let x_B = match Rational::solve(&col_view(&lp.A, &B), &lp.b) {
+            Some(x_B) => x_B,
+            None => continue,
+        };
+        if x_B.iter().any(|x| *x < Rational::zero()) {
+            continue;
+        }
+
+        let objective = B
+            .iter()
+            .zip(x_B.iter())
+            .map(|(&j, x)| lp.c[j].clone() * x.clone())
+            .fold(Rational::zero(), |sum, v| sum + v);
+        if best.as_ref().is_none_or(|best| objective > *best) {
+            best = Some(objective);
+        }
+    }
+
+    best
+}
+
+/**
+ * Solve the problem by enumerating its bases. A feasible problem has
+ * an optimal solution exactly when its dual, `-A^T y <= -c` and
+ * `y >= 0`, is feasible, which is checked the same way.
+ */
+fn oracle(lp: &ParsedLP<Rational>) -> Expected {
+    let best = match best_vertex(lp) {
+        Some(best) => best,
+        None => return Expected::Infeasible,
+    };
+
+    let m = lp.A.nrows();
+    let dual = ParsedLP::new(
+        vec![Rational::zero(); m],
+        (0..lp.n)
+            .map(|j| (0..m).map(|i| -lp.A[(i, j)].clone()).collect())
+            .collect(),
+        (0..lp.n).map(|j| -lp.c[j].clone()).collect(),
+        Vec::new(),
+    )
+    .unwrap();
+
+    match best_vertex(&dual) {
+        Some(_) => Expected::Optimal(best),
+        None => Expected::Unbounded,
+    }
+}
+
+/**
+ * Make every variable integer and bound it by `INTEGER_BOUND`, so the
+ * integer oracle only has to try the points of a small box
+ */
+fn bounded_integer(lp: &mut ParsedLP<Rational>) {
+    let m = lp.A.nrows();
+    let n = lp.n;
+    let bound = Rational::parse(&INTEGER_BOUND.to_string()).unwrap();
+    let rows = (0..m)
+        .map(|i| (0..n).map(|j| lp.A[(i, j)].clone()).collect())
+        .chain((0..n).map(|j| {
+            (0..n)
+                .map(|k| match k == j {
+                    true => Rational::one(),
+                    false => Rational::zero(),
+                })
+                .collect()
+        }))
+        .collect();
+    let b =
+        lp.b.iter()
+            .cloned()
+            .chain((0..n).map(|_| bound.clone()))
+            .collect();
+    let c = (0..n).map(|j| lp.c[j].clone()).collect();
+    *lp = ParsedLP::new(c, rows, b, (0..n).collect()).unwrap();
+}
+
+/**
+ * Solve a pure integer problem by trying every integer point with
+ * coordinates from 0 to `bound`, which has to bound the feasible region.
+ * The generated coefficients are integers, so this is exact in floating
+ * point.
+ */
+fn integer_oracle(lp: &ParsedLP<Rational>, bound: i64) -> Expected {
+    let lp = to_f64(lp);
+    let m = lp.A.nrows();
+    let mut best: Option<f64> = None;
+    let mut x = vec![0; lp.n];
+
+    loop {
+        let feasible = (0..m).all(|i| {
+            (0..lp.n).map(|j| lp.A[(i, j)] * x[j] as f64).sum::<f64>()
+                <= lp.b[i]
+        });
+        if feasible {
+            let objective =
+                (0..lp.n).map(|j| lp.c[j] * x[j] as f64).sum::<f64>();
+            if best.is_none_or(|best| objective > best) {
+                best = Some(objective);
+            }
+        }
+
+        // Step to the next point, like an odometer
+        match x.iter().position(|v| *v < bound) {
+            Some(j) => {
+                x[j] += 1;
+                x[..j].iter_mut().for_each(|v| *v = 0);
+            }
+            None => break,
+        }
+    }
+
+    match best {
+        Some(best) => {
+            Expected::Optimal(Rational::parse(&best.to_string()).unwrap())
+        }
+        None => Expected::Infeasible,
+    }
+}
+
+/**
+ * Solve an integer problem with `solve::solve` and each way of using
+ * cuts, describing the ones that don't match `expected`
+ */
+fn integer_mismatches(
+    lp: &ParsedLP<Rational>,
+    expected: &Expected,
+) -> Vec<Option<String>> {
+    let f64_lp = to_f64(lp);
+    [Cuts::None, Cuts::Gomory, Cuts::GomoryBranch]
+        .iter()
+        .map(|&cuts| {
+            let options = solve::Options {
+                method: Method::Simplex,
+                scaling: Scaling::None,
+                cuts,
+                limits: Limits::default(),
+                no_perturb: false,
+                crossover: false,
+            };
+            let result =
+                solve::solve(&f64_lp, &options, &mut Monitor::default());
+            mismatch(result, expected)
+                .map(|failure| format!("{:?}: {}", cuts, failure))
+        })
+        .collect()
+}
+
+/**
+ * The problem in another number type. The generated coefficients are
+ * integers, so this is exact for every type.
+ */
+fn to_scalar<T: Scalar>(lp: &ParsedLP<Rational>) -> ParsedLP<T> {
+    let convert = |v: &Rational| T::parse(&v.to_f64().to_string()).unwrap();
+    let (m, cols) = lp.A.shape();
+    ParsedLP {
+        A: Matrix::from_iterator(m, cols, lp.A.iter().map(convert)),
+        b: Vector::from_iterator(m, lp.b.iter().map(convert)),
+        c: Vector::from_iterator(cols, lp.c.iter().map(convert)),
+        n: lp.n,
+        integer: lp.integer.clone(),
+    }
+}
+
+/**
+ * The problem in floating point
+ */
+fn to_f64(lp: &ParsedLP<Rational>) -> ParsedLP {
+    to_scalar(lp)
+}
+
+/**
+ * Describe how a result differs from the oracle, or `None` if the
+ * statuses match and so do the objective values, to within
+ * `TOLERANCE`
+ */
+fn mismatch<T: Scalar>(
+    result: Result<SolveResult<T>, String>,
+    expected: &Expected,
+) -> Option<String> {
+    mismatch_within(result, expected, TOLERANCE)
+}
+
+/**
+ * `mismatch` with a different tolerance on the objective value
+ */
+fn mismatch_within<T: Scalar>(
+    result: Result<SolveResult<T>, String>,
+    expected: &Expected,
+    tolerance: f64,
+) -> Option<String> {
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            return Some(format!("error '{}', expected {:?}", e, expected))
+        }
+    };
+
+    match (&result, expected) {
+        (SolveResult::Infeasible, Expected::Infeasible)
+        | (SolveResult::Unbounded, Expected::Unbounded) => None,
+        (SolveResult::Optimal(solution), Expected::Optimal(objective)) => {
+            let actual = solution.objective_value.to_f64();
+            let objective = objective.to_f64();
+            let error = (actual - objective).abs() / objective.abs().max(1.0);
+            match error > tolerance {
+                true => Some(format!(
+                    "objective {}, expected {}",
+                    actual, objective
+                )),
+                false => None,
+            }
+        }
+        (result, expected) => {
+            Some(format!("{}, expected {:?}", result.status(), expected))
+        }
+    }
+}
+
+/**
+ * Check a solver against the oracle on every case, after applying
+ * `adjust` to the problem, and panic with a list of the mismatches
+ */
+fn check_solver(
+    adjust: impl Fn(&mut ParsedLP<Rational>),
+    solver: impl Fn(&ParsedLP<Rational>) -> Vec<Option<String>>,
+) {
+    let failures: Vec<String> = cases()
+        .into_iter()
+        .flat_map(|mut case| {
+            adjust(&mut case.lp);
+            solver(&case.lp)
+                .into_iter()
+                .flatten()
+                .map(move |failure| format!("{}: {}", case.name, failure))
+        })
+        .collect();
+
+    assert!(
+        failures.is_empty(),
+        "{} mismatches:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+/**
+ * The slack basis
+ */
+fn slack_basis<T: Scalar>(lp: &ParsedLP<T>) -> (Vec<usize>, Vec<usize>) {
+    let m = lp.A.nrows();
+    ((lp.n..lp.n + m).collect(), (0..lp.n).collect())
+}
+
+#[test]
+fn generated_problems_have_their_kind() {
+    for case in cases() {
+        let kind = case.name.split(' ').next().unwrap();
+        let expected = oracle(&case.lp);
+        let ok = match (kind, &expected) {
+            ("Random", _) => true,
+            ("Infeasible", Expected::Infeasible) => true,
+            ("Unbounded", Expected::Unbounded) => true,
+            ("Feasible" | "Degenerate", Expected::Optimal(_)) => true,
+            ("KleeMinty", Expected::Optimal(objective)) => {
+                let n = case.lp.n as i32;
+                objective.to_f64() == 5_f64.powi(n)
+            }
+            ("Cycling", Expected::Optimal(objective)) => {
+                objective.to_f64() == 1.25
+            }
+            _ => false,
+        };
+        assert!(ok, "{} is {:?}", case.name, expected);
+    }
+}
+
+#[test]
+fn simplex_matches_oracle() {
+    check_solver(
+        |_| {},
+        |lp| {
+            let expected = oracle(lp);
+            let f64_lp = to_f64(lp);
+            let f32_lp = to_scalar::<f32>(lp);
+            let double_double_lp = to_scalar::<DoubleDouble>(lp);
+            vec![
+                mismatch(
+                    solve::simplex(
+                        &f64_lp.A,
+                        &f64_lp.b,
+                        &f64_lp.c,
+                        false,
+                        &mut Monitor::default(),
+                    ),
+                    &expected,
+                ),
+                mismatch(
+                    solve::simplex(
+                        &lp.A,
+                        &lp.b,
+                        &lp.c,
+                        false,
+                        &mut Monitor::default(),
+                    ),
+                    &expected,
+                )
+                .map(|failure| format!("exact: {}", failure)),
+                mismatch_within(
+                    solve::simplex(
+                        &f32_lp.A,
+                        &f32_lp.b,
+                        &f32_lp.c,
+                        false,
+                        &mut Monitor::default(),
+                    ),
+                    &expected,
+                    F32_TOLERANCE,
+                )
+                .map(|failure| format!("f32: {}", failure)),
+                mismatch(
+                    solve::simplex(
+                        &double_double_lp.A,
+                        &double_double_lp.b,
+                        &double_double_lp.c,
+                        false,
+                        &mut Monitor::default(),
+                    ),
+                    &expected,
+                )
+                .map(|failure| format!("double-double: {}", failure)),
+            ]
+        },
+    );
+}
+
+#[test]
+fn solve_matches_oracle() {
+    check_solver(
+        |_| {},
+        |lp| {
+            let expected = oracle(lp);
+            let f64_lp = to_f64(lp);
+            [Scaling::None, Scaling::Both]
+                .iter()
+                .map(|&scaling| {
+                    let options = solve::Options {
+                        method: Method::Simplex,
+                        scaling,
+                        cuts: Cuts::None,
+                        limits: Limits::default(),
+                        no_perturb: false,
+                        crossover: false,
+                    };
+                    let result = solve::solve(
+                        &f64_lp,
+                        &options,
+                        &mut Monitor::default(),
+                    );
+                    mismatch(result, &expected)
+                        .map(|failure| format!("{:?}: {}", scaling, failure))
+                })
+                .collect()
+        },
+    );
+}
+
+#[test]
+fn cuts_match_integer_oracle() {
+    check_solver(bounded_integer, |lp| {
+        integer_mismatches(lp, &integer_oracle(lp, INTEGER_BOUND))
+    });
+
+    // The Gomory cuts stall on this one before the relaxation becomes
+    // integral, with a bound of about 15.04
+    let lp =
+        parse::parse::<Rational>("5 5\n3 8 24\n7 3 16\n1 1 10\ninteger 1 2\n")
+            .unwrap();
+    let expected = integer_oracle(&lp, 10);
+    assert_eq!(expected, Expected::Optimal(Rational::parse("15").unwrap()));
+    let failures: Vec<String> = integer_mismatches(&lp, &expected)
+        .into_iter()
+        .flatten()
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn primal_matches_oracle() {
+    // The primal simplex needs a primal feasible slack basis
+    check_solver(
+        |lp| lp.b.iter_mut().for_each(|b| *b = b.abs()),
+        |lp| {
+            let expected = oracle(lp);
+            let f64_lp = to_f64(lp);
+            let (B, N) = slack_basis(lp);
+            vec![
+                mismatch(
+                    solve::primal(
+                        &f64_lp.A,
+                        &f64_lp.b,
+                        &f64_lp.c,
+                        B.clone(),
+                        N.clone(),
+                        false,
+                        &mut Monitor::default(),
+                    ),
+                    &expected,
+                ),
+                mismatch(
+                    solve::primal(
+                        &lp.A,
+                        &lp.b,
+                        &lp.c,
+                        B.clone(),
+                        N.clone(),
+                        false,
+                        &mut Monitor::default(),
+                    ),
+                    &expected,
+                )
+                .map(|failure| format!("exact: {}", failure)),
+                // Without the perturbation the cycling example relies on
+                // the switch to Bland's rule
+                mismatch(
+                    solve::primal(
+                        &f64_lp.A,
+                        &f64_lp.b,
+                        &f64_lp.c,
+                        B,
+                        N,
+                        true,
+                        &mut Monitor::default(),
+                    ),
+                    &expected,
+                )
+                .map(|failure| format!("no perturbation: {}", failure)),
+            ]
+        },
+    );
+}
+
+#[test]
+fn dual_matches_oracle() {
+    // The dual simplex needs a dual feasible slack basis
+    check_solver(
+        |lp| lp.c.iter_mut().for_each(|c| *c = -c.abs()),
+        |lp| {
+            let expected = oracle(lp);
+            let f64_lp = to_f64(lp);
+            let (B, N) = slack_basis(lp);
+            vec![
+                mismatch(
+                    solve::dual(
+                        &f64_lp.A,
+                        &f64_lp.b,
+                        &f64_lp.c,
+                        B.clone(),
+                        N.clone(),
+                        false,
+                        &mut Monitor::default(),
+                    ),
+                    &expected,
+                ),
+                mismatch(
+                    solve::dual(
+                        &lp.A,
+                        &lp.b,
+                        &lp.c,
+                        B,
+                        N,
+                        false,
+                        &mut Monitor::default(),
+                    ),
+                    &expected,
+                )
+                .map(|failure| format!("exact: {}", failure)),
+            ]
+        },
+    );
+}
+
+#[test]
+fn ipm_matches_oracle() {
+    check_solver(
+        |_| {},
+        |lp| {
+            let f64_lp = to_f64(lp);
+            let result = solve::ipm(&f64_lp.A, &f64_lp.b, &f64_lp.c);
+            vec![mismatch(result, &oracle(lp))]
+        },
+    );
+}
+
+/**
+ * Crossover from points which aren't optimal, or even feasible, often
+ * leaves a basis which is neither primal nor dual feasible
+ */
+#[test]
+fn crossover_matches_oracle_from_any_point() {
+    check_solver(
+        |_| {},
+        |lp| {
+            let f64_lp = to_f64(lp);
+            let m = lp.A.nrows();
+            let point = Solution {
+                objective_value: 0.0,
+                variable_values: (0..lp.n)
+                    .map(|j| ((j * 7 + 3) % 5) as f64 / 2.0)
+                    .collect(),
+                dual_values: (0..m)
+                    .map(|i| ((i * 3 + 1) % 4) as f64 - 1.0)
+                    .collect(),
+                reduced_costs: vec![0.0; lp.n],
+                pivots: 0,
+                B: Vec::new(),
+                N: Vec::new(),
+                condition: None,
+            };
+            let result = solve::crossover(
+                &f64_lp.A,
+                &f64_lp.b,
+                &f64_lp.c,
+                &point,
+                false,
+                &mut Monitor::default(),
+            );
+            vec![mismatch(result, &oracle(lp))]
+        },
+    );
+}
@@ -0,0 +1,184 @@
+/*
+ * Copyright © 2021 Jayden Chan. All rights reserved.
+ *
+ * bblp is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 3
+ * as published by the Free Software Foundation.
+ *
+ * bblp is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with bblp. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Regression tests for bugs that were found in specific problems. Each
+//! test describes the problem and what used to go wrong.
+
+use bblp::check;
+use bblp::generator::{self, Kind};
+use bblp::output::{self, Precision, SolutionFormat};
+use bblp::parse::{self, ParsedLP};
+use bblp::scale::Scaling;
+use bblp::solve::{self, Cuts, Limits, Method, Monitor, SolveResult};
+
+/**
+ * An infeasible problem (`bblp gen --kind random --rows 15 --cols 12
+ * --density 0.5 --seed 18`) where the interior point method also
+ * finds a dual ray, which it used to report as unbounded
+ */
+#[test]
+fn ipm_infeasible_problem_with_dual_ray() {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/lp_tests/input/ipm_infeasible.txt"
+    );
+    let lp = parse::parse::<f64>(&parse::read_file(path).unwrap()).unwrap();
+    let result = solve::ipm(&lp.A, &lp.b, &lp.c).unwrap();
+    assert_eq!(result.status(), "infeasible");
+}
+
+/**
+ * An infeasible problem whose auxiliary problem is unbounded, which
+ * the two-phase driver used to report as unbounded
+ */
+#[test]
+fn infeasible_problem_with_unbounded_auxiliary_problem() {
+    let lp = parse::parse::<f64>("1\n-1 -1\n0 -1\n").unwrap();
+    let result =
+        solve::simplex(&lp.A, &lp.b, &lp.c, false, &mut Monitor::default())
+            .unwrap();
+    assert_eq!(result.status(), "infeasible");
+}
+
+/**
+ * Branch and bound stopping at a limit before it has found an integer
+ * solution, which used to be reported as an error
+ */
+#[test]
+fn node_limit_before_an_integer_solution() {
+    let lp = parse::parse::<f64>("1 1\n2 2 3\ninteger 1 2\n").unwrap();
+    let options = solve::Options {
+        method: Method::Simplex,
+        scaling: Scaling::None,
+        cuts: Cuts::None,
+        limits: Limits {
+            nodes: Some(1),
+            time: None,
+        },
+        no_perturb: false,
+        crossover: false,
+    };
+    let result = solve::solve(&lp, &options, &mut Monitor::default()).unwrap();
+    assert_eq!(result.status(), "stopped");
+}
+
+/**
+ * Integer solutions used to keep the dual values and reduced costs of
+ * the LP they were found in, which has branching rows and cuts that
+ * aren't part of the problem
+ */
+#[test]
+fn integer_solutions_have_no_duals() {
+    let lp = parse::parse::<f64>("3 2\n4 3 10\n1 2 4\ninteger 1 2\n").unwrap();
+    for cuts in [Cuts::None, Cuts::Gomory, Cuts::GomoryBranch] {
+        let options = solve::Options {
+            method: Method::Simplex,
+            scaling: Scaling::None,
+            cuts,
+            limits: Limits::default(),
+            no_perturb: false,
+            crossover: false,
+        };
+        let result =
+            solve::solve(&lp, &options, &mut Monitor::default()).unwrap();
+        let solution = match &result {
+            SolveResult::Optimal(solution) => solution,
+            other => panic!("{:?}: {}", cuts, other.status()),
+        };
+        assert!(solution.dual_values.is_empty(), "{:?}", cuts);
+        assert!(solution.reduced_costs.is_empty(), "{:?}", cuts);
+
+        for format in [SolutionFormat::Glpk, SolutionFormat::Cplex] {
+            output::write_solution(format, &lp, &result, &Precision::default())
+                .unwrap();
+        }
+    }
+}
+
+/**
+ * Optimal solutions written in GLPK's format, which rounds them to 6
+ * significant digits, used to fail the KKT check. `medium.txt` and
+ * small generated problems are checked.
+ */
+#[test]
+fn rounded_solution_files_pass_the_check() {
+    let medium =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/lp_tests/input/medium.txt");
+    let mut lps: Vec<(String, ParsedLP)> = vec![(
+        String::from("medium.txt"),
+        parse::parse(&parse::read_file(medium).unwrap()).unwrap(),
+    )];
+    for kind in [Kind::Random, Kind::Feasible, Kind::Degenerate] {
+        for (size, density, seed) in (1..=4)
+            .flat_map(|size| [(size, 0.5), (size, 1.0)])
+            .flat_map(|(size, density)| (0..4).map(move |s| (size, density, s)))
+        {
+            let options = generator::Options {
+                kind,
+                rows: size,
+                cols: size,
+                density,
+                seed,
+            };
+            if let Ok(lp) = generator::generate(&options) {
+                let name = format!(
+                    "{:?} {}x{} density {} seed {}",
+                    kind, size, size, density, seed
+                );
+                lps.push((name, lp));
+            }
+        }
+    }
+
+    let failures: Vec<String> = lps
+        .iter()
+        .filter_map(|(name, lp)| {
+            let result = solve::simplex(
+                &lp.A,
+                &lp.b,
+                &lp.c,
+                false,
+                &mut Monitor::default(),
+            )
+            .ok()?;
+            if result.status() != "optimal" {
+                return None;
+            }
+
+            let contents = output::write_solution(
+                SolutionFormat::Glpk,
+                lp,
+                &result,
+                &Precision::default(),
+            )
+            .unwrap();
+            match check::check_file(&lp.A, &lp.b, &lp.c, &contents) {
+                Ok(file_report) if file_report.passes() => None,
+                Ok(file_report) => {
+                    Some(format!("{}:\n{}", name, file_report.report))
+                }
+                Err(e) => Some(format!("{}: {}", name, e)),
+            }
+        })
+        .collect();
+
+    assert!(
+        failures.is_empty(),
+        "{} failures:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}